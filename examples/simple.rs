@@ -1,5 +1,5 @@
 use anyhow::Context;
-use vdtfont::{Point, PointHandle, Font, TriangulatedGlyph};
+use vdtfont::{Point, PointHandle, Font, TriangulatedGlyph, Triangulator};
 use vdtfont::delaunay::{DelaunayTriangleHandle, Visibility};
 
 use std::mem;
@@ -182,7 +182,8 @@ fn save(glyph: &TriangulatedGlyph, name: &str) -> anyhow::Result<()> {
 fn main() -> anyhow::Result<()> {
     let font_data = include_bytes!("/usr/share/fonts/truetype/open-sans/OpenSans-Regular.ttf");
 
-    let mut font = Font::from_vec(font_data.to_vec())?;
+    let mut triangulator = Triangulator::builder().build()?;
+    let font = Font::from_vec(font_data.to_vec())?;
 
     let mut s = "".to_string();
     loop {
@@ -191,8 +192,8 @@ fn main() -> anyhow::Result<()> {
         let c = s.chars().nth(0).context("No character was provided")?;
 
         let glyph = font.glyph(c);
-        let outlined_glyph = font.outline_glyph(glyph);
-        let triangulated_glyph = font.triangulate_glyph(outlined_glyph)?;
+        let outlined_glyph = font.outline_glyph(glyph)?;
+        let triangulated_glyph = font.triangulate_glyph(&mut triangulator, outlined_glyph)?;
 
         save(&triangulated_glyph, &format!("{c}.png"))?;
         println!("The image of the symbol was saved in {c}.png");
@@ -0,0 +1,217 @@
+use crate::font::{Glyph, TriangulatedGlyph};
+use crate::mesh::Mesh;
+
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+/// Shared vertex and index buffers which accumulate meshes of many glyphs,
+/// so a whole text can be drawn with a single draw call.
+///
+/// Glyphs are stored in the em square, in which 1.0 corresponds to one em, with the y axis
+/// pointing up and the origin on the baseline, see [`TriangulatedGlyph::to_em_mesh`].
+/// Their indices refer to the vertices of the whole atlas, so the triangles of a glyph
+/// are drawn by the range of the index buffer in its [`AtlasEntry`].
+///
+/// The atlas only grows, so the ranges of glyphs never move. New glyphs are appended
+/// to the end of the buffers and the appended parts are reported by
+/// [`MeshAtlas::take_changes`]. An atlas is meant for glyphs of one font,
+/// because glyphs are identified by their ids only.
+#[derive(Debug, Clone, Default)]
+pub struct MeshAtlas {
+    mesh: Mesh,
+    entries: BTreeMap<Glyph, AtlasEntry>,
+
+    // The lengths of the buffers which were reported by the last changes.
+    reported_vertices: usize,
+    reported_indices: usize,
+    new_glyphs: Vec<Glyph>,
+}
+
+impl MeshAtlas {
+    /// Creates a new empty [`MeshAtlas`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends the mesh of the triangulated glyph to the atlas and returns its entry.
+    ///
+    /// If the glyph is already in the atlas, the existing entry is returned
+    /// and the buffers don't change.
+    pub fn insert(&mut self, glyph: &TriangulatedGlyph) -> &AtlasEntry {
+        let id = glyph.glyph();
+        if !self.entries.contains_key(&id) {
+            let entry = self.append(glyph);
+            self.entries.insert(id, entry);
+            self.new_glyphs.push(id);
+        }
+
+        &self.entries[&id]
+    }
+
+    /// Appends the meshes of the triangulated glyphs which aren't in the atlas yet.
+    pub fn extend<'a>(&mut self, glyphs: impl IntoIterator<Item = &'a TriangulatedGlyph>) {
+        glyphs.into_iter().for_each(|glyph| {
+            self.insert(glyph);
+        });
+    }
+
+    /// Returns the entry of the glyph, or `None` if it isn't in the atlas.
+    pub fn entry(&self, glyph: Glyph) -> Option<&AtlasEntry> {
+        self.entries.get(&glyph)
+    }
+
+    /// Checks if the glyph is in the atlas.
+    pub fn contains(&self, glyph: Glyph) -> bool {
+        self.entries.contains_key(&glyph)
+    }
+
+    /// Returns the entries of all glyphs in the atlas ordered by their ids.
+    pub fn entries(&self) -> impl Iterator<Item = &AtlasEntry> + '_ {
+        self.entries.values()
+    }
+
+    /// Returns a number of glyphs in the atlas.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Checks if the atlas has no glyphs.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the mesh which contains the vertices and triangles of all glyphs.
+    pub fn mesh(&self) -> &Mesh {
+        &self.mesh
+    }
+
+    /// Returns the parts of the buffers which were appended since the last call,
+    /// or `None` if the atlas hasn't changed.
+    ///
+    /// The first call reports the whole buffers, so they are uploaded completely.
+    pub fn take_changes(&mut self) -> Option<AtlasChanges> {
+        if self.new_glyphs.is_empty() {
+            return None;
+        }
+
+        let changes = AtlasChanges {
+            vertices: self.reported_vertices..self.mesh.positions().len(),
+            indices: self.reported_indices..self.mesh.indices().len(),
+            glyphs: std::mem::take(&mut self.new_glyphs),
+        };
+        self.reported_vertices = changes.vertices.end;
+        self.reported_indices = changes.indices.end;
+
+        Some(changes)
+    }
+
+    /// Removes all glyphs from the atlas.
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    // Appends the mesh of the glyph to the buffers.
+    fn append(&mut self, glyph: &TriangulatedGlyph) -> AtlasEntry {
+        let mesh = glyph.to_em_mesh();
+        let (vertices_start, indices_start) =
+            (self.mesh.positions().len(), self.mesh.indices().len());
+        self.mesh.append(&mesh);
+
+        let placement = glyph.placement();
+        let bounds = placement.bounds();
+        let units_per_em = placement.units_per_em();
+
+        AtlasEntry {
+            glyph: glyph.glyph(),
+            vertices: vertices_start..self.mesh.positions().len(),
+            indices: indices_start..self.mesh.indices().len(),
+            bounds: [bounds.x_min, bounds.y_min, bounds.x_max, bounds.y_max]
+                .map(|b| b as f32 / units_per_em),
+            advance: placement.hor_advance() / units_per_em,
+            side_bearing: placement.hor_side_bearing() / units_per_em,
+        }
+    }
+}
+
+/// A glyph in [`MeshAtlas`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct AtlasEntry {
+    glyph: Glyph,
+
+    vertices: Range<usize>,
+    indices: Range<usize>,
+
+    bounds: [f32; 4],
+    advance: f32,
+    side_bearing: f32,
+}
+
+impl AtlasEntry {
+    /// Returns a glyph.
+    pub fn glyph(&self) -> Glyph {
+        self.glyph
+    }
+
+    /// Returns the range of the vertices of the glyph in the atlas.
+    pub fn vertices(&self) -> Range<usize> {
+        self.vertices.clone()
+    }
+
+    /// Returns the range of the indices of the glyph in the atlas.
+    ///
+    /// Every three consecutive indices in the range form a triangle of the glyph.
+    pub fn indices(&self) -> Range<usize> {
+        self.indices.clone()
+    }
+
+    /// Returns a number of triangles of the glyph.
+    pub fn triangle_count(&self) -> usize {
+        self.indices.len() / 3
+    }
+
+    /// Returns bounds of the glyph in the em square as `[x_min, y_min, x_max, y_max]`.
+    pub fn bounds(&self) -> [f32; 4] {
+        self.bounds
+    }
+
+    /// Returns the horizontal advance of the glyph in the em square.
+    pub fn advance(&self) -> f32 {
+        self.advance
+    }
+
+    /// Returns the horizontal side bearing of the glyph in the em square.
+    pub fn side_bearing(&self) -> f32 {
+        self.side_bearing
+    }
+
+    /// Checks if the glyph has no triangles, e.g. space or control characters.
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+}
+
+/// Parts of the buffers of [`MeshAtlas`] which were appended since they were uploaded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AtlasChanges {
+    vertices: Range<usize>,
+    indices: Range<usize>,
+
+    glyphs: Vec<Glyph>,
+}
+
+impl AtlasChanges {
+    /// Returns the range of the vertices which were appended.
+    pub fn vertices(&self) -> Range<usize> {
+        self.vertices.clone()
+    }
+
+    /// Returns the range of the indices which were appended.
+    pub fn indices(&self) -> Range<usize> {
+        self.indices.clone()
+    }
+
+    /// Returns the glyphs which were appended in the order of their insertion.
+    pub fn glyphs(&self) -> &[Glyph] {
+        &self.glyphs
+    }
+}
@@ -0,0 +1,129 @@
+use crate::error::Error;
+use crate::ocl;
+
+/// A backend which is used to build Voronoi diagrams and Delaunay triangulations.
+#[derive(Debug, Clone)]
+pub enum Backend {
+    /// Runs OpenCL kernels on the given queue.
+    OpenCl(ocl::Queue),
+    /// Runs the pure-Rust implementation of the OpenCL kernels on CPU.
+    ///
+    /// It doesn't require any OpenCL drivers to be installed.
+    Cpu,
+}
+
+impl Backend {
+    /// Creates a new OpenCL [`Backend`] on the first device which matches `filter`.
+    pub fn opencl(filter: &DeviceFilter) -> crate::Result<Self> {
+        let (platform, device) = filter.select()?;
+
+        let context = ocl::Context::builder().platform(platform).devices(device).build()?;
+        let queue =
+            ocl::Queue::new(&context, device, Some(ocl::CommandQueueProperties::PROFILING_ENABLE))?;
+
+        Ok(Self::OpenCl(queue))
+    }
+
+    /// Creates a new OpenCL [`Backend`] on the first available GPU.
+    ///
+    /// If there are no GPUs, the first available OpenCL device of any type is used.
+    pub fn default_opencl() -> crate::Result<Self> {
+        Self::opencl(&DeviceFilter::new().device_type(ocl::DeviceType::GPU))
+            .or_else(|_| Self::opencl(&DeviceFilter::new()))
+    }
+
+    /// Checks if the backend runs on CPU without OpenCL.
+    pub fn is_cpu(&self) -> bool {
+        matches!(self, Self::Cpu)
+    }
+}
+
+/// A filter which selects an OpenCL platform and device for [`Backend`].
+///
+/// ```no_run
+/// use vdtfont::backend::{Backend, DeviceFilter};
+/// use vdtfont::ocl::DeviceType;
+///
+/// // Use the second CPU device of the Portable Computing Language platform.
+/// let filter = DeviceFilter::new()
+///     .platform_name("Portable Computing Language")
+///     .device_type(DeviceType::CPU)
+///     .device_index(1);
+/// let backend = Backend::opencl(&filter).unwrap();
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct DeviceFilter {
+    platform_name: Option<String>,
+    device_name: Option<String>,
+    device_type: Option<ocl::DeviceType>,
+    device_index: usize,
+}
+
+impl DeviceFilter {
+    /// Creates a new [`DeviceFilter`] which matches any OpenCL device.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Selects only platforms which names contain `name`, ignoring case.
+    pub fn platform_name(mut self, name: impl Into<String>) -> Self {
+        self.platform_name = Some(name.into());
+        self
+    }
+
+    /// Selects only devices which names contain `name`, ignoring case.
+    pub fn device_name(mut self, name: impl Into<String>) -> Self {
+        self.device_name = Some(name.into());
+        self
+    }
+
+    /// Selects only devices of the given type, e.g. [`ocl::DeviceType::CPU`],
+    /// [`ocl::DeviceType::GPU`] or [`ocl::DeviceType::ACCELERATOR`].
+    pub fn device_type(mut self, device_type: ocl::DeviceType) -> Self {
+        self.device_type = Some(device_type);
+        self
+    }
+
+    /// Selects the device with the given `index` among all matching devices.
+    pub fn device_index(mut self, index: usize) -> Self {
+        self.device_index = index;
+        self
+    }
+
+    /// Returns all platforms and devices which match the filter.
+    pub fn matching_devices(&self) -> crate::Result<Vec<(ocl::Platform, ocl::Device)>> {
+        let mut devices = vec![];
+
+        for platform in ocl::Platform::list() {
+            if !matches_name(platform.name()?, self.platform_name.as_deref()) {
+                continue;
+            }
+
+            for device in ocl::Device::list(platform, self.device_type)? {
+                if matches_name(device.name()?, self.device_name.as_deref()) {
+                    devices.push((platform, device));
+                }
+            }
+        }
+
+        Ok(devices)
+    }
+
+    // Selects the platform and the device which match the filter.
+    fn select(&self) -> crate::Result<(ocl::Platform, ocl::Device)> {
+        let devices = self.matching_devices()?;
+        let matching_devices = devices.len();
+
+        devices
+            .into_iter()
+            .nth(self.device_index)
+            .ok_or_else(|| Error::DeviceNotFound { filter: self.clone(), matching_devices })
+    }
+}
+
+fn matches_name(name: String, pattern: Option<&str>) -> bool {
+    match pattern {
+        Some(pattern) => name.to_lowercase().contains(&pattern.to_lowercase()),
+        None => true,
+    }
+}
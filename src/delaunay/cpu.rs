@@ -0,0 +1,187 @@
+use super::triangle::{DelaunayTriangle, TriangleId};
+
+use crate::point::{Point, PointId};
+use crate::voronoi::VoronoiImage;
+
+use std::collections::HashMap;
+
+use arena_system::Arena;
+use smallvec::SmallVec;
+
+// Runs the steps of the triangulation on CPU.
+//
+// Every step mirrors the corresponding kernel from `delaunay.cl`.
+pub(super) struct CpuDelaunayFactory;
+
+impl CpuDelaunayFactory {
+    // Creates a new [`CpuDelaunayFactory`].
+    pub(super) fn new() -> Self {
+        Self
+    }
+
+    // Builds triangles according to data from the [`VoronoiImage`].
+    pub(super) fn build_triangles(
+        &mut self,
+        voronoi_image: &VoronoiImage<'_>,
+        points: &Arena<Point>,
+    ) -> crate::Result<Vec<DelaunayTriangle>> {
+        let dim = voronoi_image.dim();
+        let pixels = voronoi_image.to_raw_pixels()?;
+
+        // Returns the id of the site which is the nearest to the pixel at (`x`; `y`).
+        let site_id = |x: usize, y: usize| -> PointId {
+            if x >= dim || y >= dim {
+                return -1;
+            }
+
+            pixels[(x + y * dim) * 4 + 2] as PointId
+        };
+
+        let mut triangles = vec![];
+        for y in 0..dim {
+            for x in 0..dim {
+                let vertex = [
+                    site_id(x, y),         // bottom left pixel
+                    site_id(x + 1, y),     // bottom right pixel
+                    site_id(x + 1, y + 1), // upper right pixel
+                    site_id(x, y + 1),     // upper left pixel
+                ];
+
+                match count_triangles_in_vertex(vertex) {
+                    1 => {
+                        let mut vertices = [-1; 3];
+                        let mut current_triangle_vertex = 0;
+
+                        let mut prev_v = vertex[3];
+                        for curr_v in vertex {
+                            if curr_v != prev_v {
+                                vertices[current_triangle_vertex] = curr_v;
+                                current_triangle_vertex += 1;
+                            }
+
+                            prev_v = curr_v;
+                        }
+
+                        triangles.push(DelaunayTriangle::new(vertices));
+                    }
+                    2 => {
+                        for i in 0..2 {
+                            let vertices = [0, 1, 2].map(|j| vertex[(j + i * 2) % 4]);
+
+                            triangles.push(DelaunayTriangle::new(vertices));
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        triangles.iter_mut().for_each(|t| t.make_counterclockwise(points));
+
+        Ok(triangles)
+    }
+
+    // Connects triangles with their neighbours.
+    pub(super) fn calculate_triangle_neighbours(
+        &mut self,
+        triangles: &mut [DelaunayTriangle],
+    ) -> crate::Result<()> {
+        // Find triangles which contain every edge.
+        let mut edge_triangles: HashMap<[PointId; 2], SmallVec<[TriangleId; 2]>> = HashMap::new();
+        triangles.iter().enumerate().for_each(|(i, t)| {
+            (0..3).for_each(|j| {
+                let edge = sorted_edge(t.vertices[j], t.vertices[(j + 1) % 3]);
+                edge_triangles.entry(edge).or_default().push(i as TriangleId);
+            });
+        });
+
+        for i in 0..triangles.len() {
+            let mut supposed_neighbours = (0..3)
+                .flat_map(|j| {
+                    let t = &triangles[i];
+                    let edge = sorted_edge(t.vertices[j], t.vertices[(j + 1) % 3]);
+
+                    edge_triangles[&edge].clone()
+                })
+                .collect::<Vec<TriangleId>>();
+            supposed_neighbours.sort_unstable();
+            supposed_neighbours.dedup();
+
+            for supposed_neighbour in supposed_neighbours {
+                let shared_points_number =
+                    count_shared_points(&triangles[i], &triangles[supposed_neighbour as usize]);
+
+                if shared_points_number == 2 {
+                    let triangle = &mut triangles[i];
+
+                    let neighbour_idx = triangle.neighbours_number as usize;
+                    triangle.neighbours_number += 1;
+                    if neighbour_idx < 3 {
+                        triangle.neighbours[neighbour_idx] = supposed_neighbour;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // Sets triangle fans for points.
+    pub(super) fn calculate_triangle_fans(
+        &mut self,
+        triangles: &[DelaunayTriangle],
+        points: &mut Arena<Point>,
+    ) -> crate::Result<()> {
+        let mut triangle_fans = vec![SmallVec::<[TriangleId; 6]>::new(); points.len()];
+        triangles.iter().enumerate().for_each(|(i, t)| {
+            t.vertices.iter().for_each(|v| {
+                if let Some(triangle_fan) = triangle_fans.get_mut(*v as usize) {
+                    if !triangle_fan.contains(&(i as TriangleId)) {
+                        triangle_fan.push(i as TriangleId);
+                    }
+                }
+            });
+        });
+
+        triangle_fans.into_iter().enumerate().for_each(|(center, tf)| {
+            points.lookup_mut((center as PointId).into()).unwrap().set_triangle_fan(tf);
+        });
+
+        Ok(())
+    }
+}
+
+// Counts triangles which are formed by the sites around the Voronoi vertex.
+fn count_triangles_in_vertex(vertex: [PointId; 4]) -> usize {
+    let mut same_points_number = 0;
+    let mut prev_point = vertex[3];
+    for current_point in vertex {
+        if current_point <= -1 {
+            return 0;
+        }
+
+        if current_point == prev_point {
+            same_points_number += 1;
+        }
+
+        prev_point = current_point;
+    }
+
+    if same_points_number > 2 || vertex[0] == vertex[2] || vertex[1] == vertex[3] {
+        return 0;
+    }
+
+    2 - same_points_number
+}
+
+fn count_shared_points(triangle0: &DelaunayTriangle, triangle1: &DelaunayTriangle) -> usize {
+    triangle0
+        .vertices
+        .iter()
+        .map(|v0| triangle1.vertices.iter().filter(|v1| v0 == *v1).count())
+        .sum()
+}
+
+fn sorted_edge(v0: PointId, v1: PointId) -> [PointId; 2] {
+    [v0.min(v1), v0.max(v1)]
+}
@@ -1,9 +1,10 @@
 use super::DelaunayTriangleHandle;
 
+use crate::error::Error;
 use crate::point::PointHandle;
+use crate::predicates;
 
 use std::convert;
-use std::ops::ControlFlow;
 
 use arena_system::Handle;
 use smallvec::SmallVec;
@@ -37,34 +38,44 @@ impl<'arena> Edge<'arena> {
             || (p1 == pp0 && !p1.index().is_invalid() && !pp0.index().is_invalid())
     }
 
+    /// Returns the change of the winding number when the edge is crossed
+    /// from the side of the `point`.
+    ///
+    /// The change is nonzero only for edges in the contour and its sign depends on
+    /// the direction of the contour.
+    pub fn winding_change_from(&self, point: PointHandle<'arena>) -> i32 {
+        let [p0, p1] = self.points;
+
+        [(p0, p1), (p1, p0)]
+            .into_iter()
+            .filter(|(from, to)| {
+                !from.index().is_invalid()
+                    && !to.index().is_invalid()
+                    && to.previous_in_outline() == *from
+            })
+            .map(|(from, to)| if point.skew_product(&from, &to) > 0.0 { -1 } else { 1 })
+            .sum()
+    }
+
     /// Checks if the edge contains the `point`.
     pub fn contains(&self, point: PointHandle<'arena>) -> bool {
         self.points.contains(&point)
     }
 
     /// Checks if the edge intersects the `other` edge.
+    ///
+    /// The edges intersect only if they cross each other at the inner points.
     pub fn intersects(&self, other: &Self) -> bool {
-        let det = (self.points[1].x() - self.points[0].x())
-            * (other.points[1].y() - other.points[0].y())
-            - (other.points[1].x() - other.points[0].x())
-                * (self.points[1].y() - self.points[0].y());
-
-        if det == 0.0 {
-            false
-        } else {
-            let lambda = ((other.points[1].y() - other.points[0].y())
-                * (other.points[1].x() - self.points[0].x())
-                - (other.points[1].x() - other.points[0].x())
-                    * (other.points[1].y() - self.points[0].y()))
-                / det;
-            let gamma = ((self.points[0].y() - self.points[1].y())
-                * (other.points[1].x() - self.points[0].x())
-                + (self.points[1].x() - self.points[0].x())
-                    * (other.points[1].y() - self.points[0].y()))
-                / det;
-
-            (0.0 < lambda && lambda < 1.0) && (0.0 < gamma && gamma < 1.0)
-        }
+        let [p0, p1] = self.points.map(|p| predicates::coords(&p));
+        let [q0, q1] = other.points.map(|p| predicates::coords(&p));
+
+        let by_different_sides = |a: f64, b: f64| a > 0.0 && b < 0.0 || a < 0.0 && b > 0.0;
+
+        by_different_sides(predicates::orient2d(p0, p1, q0), predicates::orient2d(p0, p1, q1))
+            && by_different_sides(
+                predicates::orient2d(q0, q1, p0),
+                predicates::orient2d(q0, q1, p1),
+            )
     }
 
     /// Checks if the edge is equal to the `other` edge.
@@ -74,55 +85,71 @@ impl<'arena> Edge<'arena> {
     }
 
     /// Finds all edges and triangles which are intersected by the edge.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the track can't be found, see [`Edge::try_find_triangle_track`].
     pub fn find_triangle_track(
         &'arena self,
     ) -> (Vec<Edge<'arena>>, Vec<DelaunayTriangleHandle<'arena>>) {
+        self.try_find_triangle_track().expect("Couldn't find the triangle track")
+    }
+
+    /// Finds all edges and triangles which are intersected by the edge.
+    ///
+    /// Returns [`Error::Triangulation`] if the edge doesn't start in a triangle
+    /// of the triangulation or the track is broken before reaching the second point.
+    pub fn try_find_triangle_track(
+        &'arena self,
+    ) -> crate::Result<(Vec<Edge<'arena>>, Vec<DelaunayTriangleHandle<'arena>>)> {
+        let [start, end] = self.points();
+
         // Find the first edge and triangle which are intersected by the edge.
-        let res = self.points()[0].triangle_fan().into_iter().try_for_each(|t| {
-            let opposite_edge = t.opposite_edge_to(self.points()[0]);
-            if t.points().contains(&self.points()[1]) || opposite_edge.intersects(self) {
-                return ControlFlow::Break((opposite_edge, t));
+        let mut first = None;
+        for t in start.try_triangle_fan()? {
+            let opposite_edge = t.opposite_edge_to(start);
+            if t.try_points()?.contains(&end) || opposite_edge.intersects(self) {
+                first = Some((opposite_edge, t));
+                break;
             }
+        }
 
-            ControlFlow::Continue(())
-        });
-
-        let (mut e, mut t) = match res {
-            ControlFlow::Break((e, t)) => (e, t),
-            _ => panic!("Couldn't find any triangles in track"),
-        };
+        let (mut e, mut t) = first.ok_or_else(|| {
+            Error::Triangulation("Couldn't find any triangles in track".to_string())
+        })?;
         let mut edges = vec![e];
         let mut triangles = vec![t];
 
         // If the first triangle contains the whole edge, return from the function.
-        if t.points().contains(&self.points()[1]) {
-            return (edges, triangles);
+        if t.try_points()?.contains(&end) {
+            return Ok((edges, triangles));
         }
 
-        loop {
+        // Each triangle is crossed at most once, so a longer track is broken.
+        for _ in 0..t.arena().len() {
             // Obtain the next triangle which is intersected by the edge.
-            let n = t.neighbour_on_edge(e);
+            let n = t.try_neighbour_on_edge(e)?;
             triangles.push(n);
 
             // If the triangle contains the whole edge, all intersected triangles are found,
             // so return from the function.
-            if n.points().contains(&self.points()[1]) {
-                return (edges, triangles);
+            if n.try_points()?.contains(&end) {
+                return Ok((edges, triangles));
             }
 
-            let es = n.edges_except(e);
-
             // Obtain the next edge which is intersected by the edge.
-            if es[0].intersects(self) {
-                e = es[0];
-            } else if es[1].intersects(self) {
-                e = es[1];
-            }
-
+            e =
+                n.edges_except(e).into_iter().find(|e| e.intersects(self)).ok_or_else(|| {
+                    Error::Triangulation("The triangle track is broken".to_string())
+                })?;
             edges.push(e);
 
             t = n;
         }
+
+        Err(Error::Triangulation(
+            "The triangle track doesn't reach the end of the edge".to_string(),
+        ))
     }
 }
 
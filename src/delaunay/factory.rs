@@ -1,15 +1,24 @@
 use super::bounds::Bounds;
+use super::cpu::CpuDelaunayFactory;
+use super::insertion::insert_points;
+use super::opencl::OpenClDelaunayFactory;
 use super::triangle::{DelaunayTriangle, TriangleId};
-use super::triangle_fan::TriangleFan;
 use super::Delaunay;
 
+use crate::backend::Backend;
 use crate::delaunay::DelaunayTriangleHandle;
-use crate::opencl::Buffer;
+use crate::error::Error;
+use crate::opencl::ProgramCache;
 use crate::point::{Point, PointHandle, PointId};
 use crate::voronoi::{Pixel, VoronoiImage};
 
+use std::collections::HashSet;
+use std::iter;
+
 use arena_system::{Arena, Handle};
-use smallvec::ToSmallVec;
+
+// The maximal number of passes which repair inverted triangles.
+const MAX_REPAIR_PASSES: usize = 8;
 
 /// A factory for constructing [`Delaunay`].
 ///
@@ -18,105 +27,28 @@ use smallvec::ToSmallVec;
 /// A full algorithm of triangulation is described in the paper
 /// ["Computing Two-dimensional Delaunay Triangulation Using Graphics Hardware"](https://www.comp.nus.edu.sg/%7Etants/delaunay/GPUDT.pdf)
 pub struct DelaunayFactory {
-    count_triangles_kernel: ocl::Kernel,
-    triangle_number_buffer: Buffer<i32>,
-
-    build_triangles_kernel: ocl::Kernel,
-    triangles_buffer: Buffer<DelaunayTriangle>,
-    free_triangle_index_buffer: Buffer<i32>,
-
-    calculate_triangle_neighbours_kernel: ocl::Kernel,
-
-    count_triangles_in_fans_kernel: ocl::Kernel,
-    triangle_fans_buffer: Buffer<TriangleFan>,
-
-    calculate_triangle_offset_in_fans_kernel: ocl::Kernel,
+    inner: FactoryInner,
+}
 
-    find_triangles_in_fans_kernel: ocl::Kernel,
-    flatten_triangle_fans_buffer: Buffer<TriangleId>,
+// A backend-specific implementation of [`DelaunayFactory`].
+enum FactoryInner {
+    OpenCl(OpenClDelaunayFactory),
+    Cpu(CpuDelaunayFactory),
 }
 
 impl DelaunayFactory {
-    /// Creates a new [`DelaunayFactory`].
-    pub fn new(queue: ocl::Queue) -> anyhow::Result<Self> {
-        let program = ocl::Program::builder()
-            .src_file("src/opencl/kernels/delaunay.cl")
-            .build(&queue.context())?;
-
-        let count_triangles_kernel = ocl::Kernel::builder()
-            .queue(queue.clone())
-            .program(&program)
-            .name("count_triangles")
-            .arg(None::<&ocl::Image<i32>>)
-            .arg(None::<&ocl::Buffer<i32>>)
-            .build()?;
-        let mut triangle_number_buffer = Buffer::<i32>::new(queue.clone())?;
-        triangle_number_buffer.write(&[0])?;
-
-        let build_triangles_kernel = ocl::Kernel::builder()
-            .queue(queue.clone())
-            .program(&program)
-            .name("build_triangles")
-            .arg(None::<&ocl::Image<i32>>)
-            .arg(None::<&ocl::Buffer<DelaunayTriangle>>)
-            .arg(None::<&ocl::Buffer<TriangleId>>)
-            .build()?;
-        let triangles_buffer = Buffer::<DelaunayTriangle>::new(queue.clone())?;
-        let mut free_triangle_index_buffer = Buffer::<i32>::new(queue.clone())?;
-        free_triangle_index_buffer.write(&[0])?;
-
-        let calculate_triangle_neighbours_kernel = ocl::Kernel::builder()
-            .queue(queue.clone())
-            .program(&program)
-            .name("calculate_triangle_neighbours")
-            .arg(None::<&ocl::Buffer<DelaunayTriangle>>)
-            .build()?;
-
-        let count_triangles_in_fans_kernel = ocl::Kernel::builder()
-            .queue(queue.clone())
-            .program(&program)
-            .name("count_triangles_in_fans")
-            .arg(None::<&ocl::Buffer<DelaunayTriangle>>)
-            .arg(None::<&ocl::Buffer<TriangleFan>>)
-            .build()?;
-        let triangle_fans_buffer = Buffer::<TriangleFan>::new(queue.clone())?;
-
-        let calculate_triangle_offset_in_fans_kernel = ocl::Kernel::builder()
-            .queue(queue.clone())
-            .program(&program)
-            .name("calculate_triangle_offset_in_fans")
-            .arg(None::<&ocl::Buffer<TriangleFan>>)
-            .arg(None::<&ocl::Buffer<i32>>)
-            .build()?;
-
-        let find_triangles_in_fans_kernel = ocl::Kernel::builder()
-            .queue(queue.clone())
-            .program(&program)
-            .name("find_triangles_in_fans")
-            .arg(None::<&ocl::Buffer<DelaunayTriangle>>)
-            .arg(None::<&ocl::Buffer<TriangleFan>>)
-            .arg(None::<&ocl::Buffer<TriangleId>>)
-            .build()?;
-        let flatten_triangle_fans_buffer = Buffer::<TriangleId>::new(queue)?;
-
-        Ok(Self {
-            count_triangles_kernel,
-            triangle_number_buffer,
-
-            build_triangles_kernel,
-            triangles_buffer,
-            free_triangle_index_buffer,
-
-            calculate_triangle_neighbours_kernel,
-
-            count_triangles_in_fans_kernel,
-            triangle_fans_buffer,
-
-            calculate_triangle_offset_in_fans_kernel,
-
-            find_triangles_in_fans_kernel,
-            flatten_triangle_fans_buffer,
-        })
+    /// Creates a new [`DelaunayFactory`] which runs on the given `backend`.
+    ///
+    /// If `program_cache` is given, compiled OpenCL programs are cached there.
+    pub fn new(backend: Backend, program_cache: Option<&ProgramCache>) -> crate::Result<Self> {
+        let inner = match backend {
+            Backend::OpenCl(queue) => {
+                FactoryInner::OpenCl(OpenClDelaunayFactory::new(queue, program_cache)?)
+            }
+            Backend::Cpu => FactoryInner::Cpu(CpuDelaunayFactory::new()),
+        };
+
+        Ok(Self { inner })
     }
 
     /// Constructs a new [`Delaunay`].
@@ -125,7 +57,7 @@ impl DelaunayFactory {
     ///
     /// A full algorithm of triangulation is described in
     /// [this paper](https://www.comp.nus.edu.sg/%7Etants/delaunay/GPUDT.pdf).
-    pub fn construct(&mut self, voronoi_image: &VoronoiImage<'_>) -> anyhow::Result<Delaunay> {
+    pub fn construct(&mut self, voronoi_image: &VoronoiImage<'_>) -> crate::Result<Delaunay> {
         let dim = voronoi_image.dim();
         let mut points = self.collect_discrete_points(voronoi_image.sites());
         let mut triangles = self.build_triangles(voronoi_image, &points)?;
@@ -133,13 +65,37 @@ impl DelaunayFactory {
         let mut voronoi_image_pixels = voronoi_image.to_pixels()?;
         let bounds = self.add_bounds(dim, &mut points, &mut voronoi_image_pixels);
 
-        self.fix_convex_hull(dim, &points, &mut triangles, &voronoi_image_pixels)?;
+        self.fix_convex_hull(dim, &points, &mut triangles, &voronoi_image_pixels);
         self.calculate_triangle_neighbours(&mut triangles)?;
 
-        let triangles: Arena<DelaunayTriangle> = triangles.into();
-        self.flip_triangles(&triangles, &points);
+        let mut triangles: Arena<DelaunayTriangle> = triangles.into();
+        self.flip_triangles(&triangles, &points)?;
+
+        // The topology is built on the discrete grid, but the resulting points
+        // keep their original coordinates.
+        self.restore_continuous_points(voronoi_image.sites(), &mut points);
+        if self.repair_inverted_triangles(&triangles, &points).is_err() {
+            // The discrete topology can't be unfolded, so the triangulation is built
+            // from the continuous coordinates instead.
+            triangles = insert_points(&points, bounds)?;
+            self.repair_inverted_triangles(&triangles, &points)?;
+        }
+
+        // Moved points can break the Delaunay condition, so the triangles are flipped again.
+        self.flip_triangles(&triangles, &points)?;
+
+        let triangle_vec = triangles
+            .handle_iter::<DelaunayTriangleHandle>(&points)
+            .map(|t| {
+                let index: TriangleId = t.index().into();
+                t.get().map(|t| *t).map_err(|_| {
+                    Error::Triangulation(format!("The triangle {index} can't be borrowed"))
+                })
+            })
+            .collect::<crate::Result<Vec<DelaunayTriangle>>>()?;
 
-        self.calculate_triangle_fans(&mut points)?;
+        self.calculate_triangle_fans(&triangle_vec, &mut points)?;
+        self.skip_merged_points_in_outline(&mut points);
 
         self.reset()?;
 
@@ -165,53 +121,11 @@ impl DelaunayFactory {
         &mut self,
         voronoi_image: &VoronoiImage<'_>,
         points: &Arena<Point>,
-    ) -> anyhow::Result<Vec<DelaunayTriangle>> {
-        let triangle_number = self.count_triangles(voronoi_image)?;
-        if triangle_number == 0 {
-            return Ok(vec![]);
-        }
-
-        let mut triangles = vec![DelaunayTriangle::default(); triangle_number as usize];
-        self.triangles_buffer.write(&triangles)?;
-
-        self.free_triangle_index_buffer.clear()?;
-
-        self.build_triangles_kernel
-            .set_default_global_work_size((voronoi_image.dim(), voronoi_image.dim()).into())
-            .set_default_local_work_size((8, 8).into());
-
-        self.build_triangles_kernel.set_arg(0, voronoi_image.image().ocl_image())?;
-        self.build_triangles_kernel.set_arg(1, self.triangles_buffer.as_raw())?;
-        self.build_triangles_kernel
-            .set_arg(2, self.free_triangle_index_buffer.as_raw())?;
-
-        unsafe {
-            self.build_triangles_kernel.enq()?;
-        }
-
-        self.triangles_buffer.read(&mut triangles)?;
-        triangles.iter_mut().for_each(|t| t.make_counterclockwise(points));
-
-        Ok(triangles)
-    }
-
-    // Counts triangles in triangulation according to data from the [`VoronoiImage`].
-    fn count_triangles(&mut self, voronoi_image: &VoronoiImage<'_>) -> anyhow::Result<i32> {
-        self.count_triangles_kernel
-            .set_default_global_work_size((voronoi_image.dim(), voronoi_image.dim()).into())
-            .set_default_local_work_size((8, 8).into());
-
-        self.count_triangles_kernel.set_arg(0, voronoi_image.image().ocl_image())?;
-        self.count_triangles_kernel
-            .set_arg(1, self.triangle_number_buffer.as_raw())?;
-
-        unsafe {
-            self.count_triangles_kernel.enq()?;
+    ) -> crate::Result<Vec<DelaunayTriangle>> {
+        match self.inner {
+            FactoryInner::OpenCl(ref mut factory) => factory.build_triangles(voronoi_image, points),
+            FactoryInner::Cpu(ref mut factory) => factory.build_triangles(voronoi_image, points),
         }
-
-        let triangle_number = self.triangle_number_buffer.first()?;
-
-        Ok(triangle_number)
     }
 
     // Adds a super-structure to the triangulation.
@@ -286,12 +200,12 @@ impl DelaunayFactory {
     // A full algorithm of triangulation is described in the section C1 of
     // [this paper](https://www.comp.nus.edu.sg/%7Etants/delaunay/GPUDT.pdf).
     fn fix_convex_hull(
-        &mut self,
+        &self,
         dim: usize,
         points: &Arena<Point>,
         triangles: &mut Vec<DelaunayTriangle>,
         voronoi_image_pixels: &[Pixel],
-    ) -> anyhow::Result<()> {
+    ) {
         let mut pixel_stack: Vec<&Pixel> = vec![];
 
         // Iterate over border pixels.
@@ -326,153 +240,194 @@ impl DelaunayFactory {
 
             pixel_stack.push(pixel);
         }
-
-        self.triangles_buffer.write(triangles)?;
-
-        Ok(())
     }
 
     // Connects triangles with their neighbours.
     fn calculate_triangle_neighbours(
         &mut self,
-        triangles: &mut Vec<DelaunayTriangle>,
-    ) -> anyhow::Result<()> {
-        self.calculate_triangle_neighbours_kernel
-            .set_default_global_work_size((triangles.len(), triangles.len()).into())
-            .set_default_local_work_size((1, 1).into());
-
-        self.calculate_triangle_neighbours_kernel
-            .set_arg(0, self.triangles_buffer.as_raw())?;
-
-        unsafe {
-            self.calculate_triangle_neighbours_kernel.enq()?;
+        triangles: &mut [DelaunayTriangle],
+    ) -> crate::Result<()> {
+        match self.inner {
+            FactoryInner::OpenCl(ref mut factory) => {
+                factory.calculate_triangle_neighbours(triangles)
+            }
+            FactoryInner::Cpu(ref mut factory) => factory.calculate_triangle_neighbours(triangles),
         }
-
-        self.triangles_buffer.read(triangles)?;
-
-        Ok(())
     }
 
     // Sets triangle fans for points.
-    fn calculate_triangle_fans(&mut self, points: &mut Arena<Point>) -> anyhow::Result<()> {
-        let mut triangle_fans = self.create_triangle_fans(points.len())?;
-        self.count_triangles_in_fans()?;
-        self.calculate_triangle_offset_in_fans()?;
-        let flatten_triangle_fans = self.find_triangles_in_fans()?;
-        self.triangle_fans_buffer.read(&mut triangle_fans)?;
-
-        triangle_fans.into_iter().for_each(|tf| {
-            let start = tf.triangle_offset as usize;
-            let end = tf.triangle_offset as usize + tf.triangle_number as usize;
-            points
-                .lookup_mut(tf.center.into())
-                .unwrap()
-                .set_triangle_fan(flatten_triangle_fans[start..end].to_smallvec());
-        });
-
-        Ok(())
+    fn calculate_triangle_fans(
+        &mut self,
+        triangles: &[DelaunayTriangle],
+        points: &mut Arena<Point>,
+    ) -> crate::Result<()> {
+        match self.inner {
+            FactoryInner::OpenCl(ref mut factory) => {
+                factory.calculate_triangle_fans(triangles, points)
+            }
+            FactoryInner::Cpu(ref mut factory) => {
+                factory.calculate_triangle_fans(triangles, points)
+            }
+        }
     }
 
-    // Creates empty triangle fans.
-    fn create_triangle_fans(&mut self, points_number: usize) -> anyhow::Result<Vec<TriangleFan>> {
-        let triangle_fans = (0..points_number as PointId)
-            .map(TriangleFan::new)
-            .collect::<Vec<TriangleFan>>();
+    // Connects points in the outline to the previous points which are in the triangulation.
+    //
+    // Points which share a pixel of the Voronoi image with other points don't get
+    // triangles, so the outline skips them to stay closed.
+    fn skip_merged_points_in_outline(&self, points: &mut Arena<Point>) {
+        let point_count = points.len() as PointId;
+        let is_merged = |point: &Point| point.triangle_fan().is_empty();
 
-        self.triangle_fans_buffer.write(&triangle_fans)?;
+        let previous_points = points
+            .handle_iter::<PointHandle>(None)
+            .map(|p| {
+                let Ok(point) = p.get() else { return None };
+                if is_merged(&point) || (point.previous_in_outline() as usize) >= points.len() {
+                    return None;
+                }
 
-        Ok(triangle_fans)
-    }
+                // Walk back along the outline until a point in the triangulation is found.
+                let mut previous = point.previous_in_outline();
+                for _ in 0..point_count {
+                    if !(0..point_count).contains(&previous) {
+                        return None;
+                    }
 
-    // Counts triangles in the triangle fan of every point.
-    fn count_triangles_in_fans(&mut self) -> anyhow::Result<()> {
-        self.count_triangles_in_fans_kernel
-            .set_default_global_work_size(
-                (self.triangles_buffer.len(), self.triangle_fans_buffer.len()).into(),
-            )
-            .set_default_local_work_size((1, 1).into());
-
-        self.count_triangles_in_fans_kernel
-            .set_arg(0, self.triangles_buffer.as_raw())?;
-        self.count_triangles_in_fans_kernel
-            .set_arg(1, self.triangle_fans_buffer.as_raw())?;
-
-        unsafe {
-            self.count_triangles_in_fans_kernel.enq()?;
-        }
+                    let previous_point = points.handle::<PointHandle>(previous.into(), None);
+                    let Ok(previous_point) = previous_point.get() else { return None };
+                    if !is_merged(&previous_point) {
+                        break;
+                    }
 
-        Ok(())
-    }
+                    previous = previous_point.previous_in_outline();
+                }
 
-    // Assigns offset to every triangle fan to use it later
-    // when read from flatten triangle fans list.
-    fn calculate_triangle_offset_in_fans(&mut self) -> anyhow::Result<()> {
-        self.free_triangle_index_buffer.clear()?;
+                let id: PointId = p.index().into();
+                (previous != point.previous_in_outline() && previous != id)
+                    .then_some((p.index(), previous))
+            })
+            .collect::<Vec<_>>();
 
-        self.calculate_triangle_offset_in_fans_kernel
-            .set_default_global_work_size(self.triangle_fans_buffer.len().into())
-            .set_default_local_work_size((1, 1).into());
+        previous_points.into_iter().flatten().for_each(|(index, previous)| {
+            if let Ok(mut point) = points.lookup_mut(index) {
+                point.set_previous_in_outline(previous);
+            }
+        });
+    }
 
-        self.calculate_triangle_offset_in_fans_kernel
-            .set_arg(0, self.triangle_fans_buffer.as_raw())?;
-        self.calculate_triangle_offset_in_fans_kernel
-            .set_arg(1, self.free_triangle_index_buffer.as_raw())?;
+    // Flips triangles with their neighbours until all of them satisfy the Delaunay condition.
+    //
+    // Every triangle is checked once and then only the triangles around the flipped ones
+    // are checked again. Returns [`Error::Triangulation`] if the number of flips exceeds
+    // the quadratic bound of the flip algorithm, which can only happen if the flips cycle.
+    fn flip_triangles(
+        &self,
+        triangles: &Arena<DelaunayTriangle>,
+        points: &Arena<Point>,
+    ) -> crate::Result<()> {
+        let max_flips = triangles.len().saturating_mul(triangles.len()).max(1);
+        let mut flips = 0;
 
-        unsafe {
-            self.calculate_triangle_offset_in_fans_kernel.enq()?;
+        let mut worklist = triangles
+            .handle_iter::<DelaunayTriangleHandle>(points)
+            .filter(|t| t.get().is_ok())
+            .collect::<Vec<_>>();
+        let mut queued = worklist.iter().map(|t| t.index()).collect::<HashSet<_>>();
+
+        while let Some(mut triangle) = worklist.pop() {
+            queued.remove(&triangle.index());
+
+            let neighbour =
+                triangle.neighbours().into_iter().find(|n| triangle.is_flippable_with(n));
+            let Some(mut neighbour) = neighbour else {
+                continue;
+            };
+
+            if flips == max_flips {
+                return Err(Error::Triangulation(format!(
+                    "The triangles don't satisfy the Delaunay condition after {max_flips} flips"
+                )));
+            }
+
+            triangle.flip_with(&mut neighbour, 1);
+            flips += 1;
+
+            [triangle, neighbour]
+                .into_iter()
+                .flat_map(|t| iter::once(t).chain(t.neighbours()))
+                .for_each(|t| {
+                    if queued.insert(t.index()) {
+                        worklist.push(t);
+                    }
+                });
         }
 
         Ok(())
     }
 
-    // Finds all triangles in triangle fans and writes them to `flatten_triangle_fans`.
-    fn find_triangles_in_fans(&mut self) -> anyhow::Result<Vec<TriangleId>> {
-        let mut flatten_triangle_fans = vec![-1; self.triangles_buffer.len() * 3];
-        self.flatten_triangle_fans_buffer.write(&flatten_triangle_fans)?;
-
-        self.find_triangles_in_fans_kernel
-            .set_default_global_work_size(
-                (self.triangles_buffer.len(), self.triangle_fans_buffer.len()).into(),
-            )
-            .set_default_local_work_size((1, 1).into());
-
-        self.find_triangles_in_fans_kernel
-            .set_arg(0, self.triangles_buffer.as_raw())?;
-        self.find_triangles_in_fans_kernel
-            .set_arg(1, self.triangle_fans_buffer.as_raw())?;
-        self.find_triangles_in_fans_kernel
-            .set_arg(2, self.flatten_triangle_fans_buffer.as_raw())?;
-
-        unsafe {
-            self.find_triangles_in_fans_kernel.enq()?;
-        }
+    // Replaces discrete coordinates of the points with the continuous coordinates of `sites`.
+    fn restore_continuous_points(&self, sites: &Arena<Point>, points: &mut Arena<Point>) {
+        sites.handle_iter::<PointHandle>(None).for_each(|site| {
+            if let Ok(mut point) = points.lookup_mut(site.index()) {
+                point.set_coords(site.coords());
+            }
+        });
+    }
 
-        self.flatten_triangle_fans_buffer.read(&mut flatten_triangle_fans)?;
+    // Repairs triangles which are inverted after restoring continuous coordinates.
+    //
+    // Returns [`Error::Triangulation`] if some triangles are still inverted after the last pass.
+    fn repair_inverted_triangles(
+        &self,
+        triangles: &Arena<DelaunayTriangle>,
+        points: &Arena<Point>,
+    ) -> crate::Result<()> {
+        // Every repair can only be blocked by another inverted triangle,
+        // so several passes are enough to repair all triangles which can be repaired.
+        for _ in 0..MAX_REPAIR_PASSES {
+            let mut inverted_number = 0;
+            let mut repaired_number = 0;
+
+            triangles
+                .handle_iter::<DelaunayTriangleHandle>(points)
+                .filter(|t| t.get().is_ok() && !t.is_counterclockwise())
+                .for_each(|mut t| {
+                    inverted_number += 1;
+                    if t.repair_inversion() {
+                        repaired_number += 1;
+                    }
+                });
+
+            if inverted_number == 0 {
+                return Ok(());
+            }
 
-        Ok(flatten_triangle_fans)
-    }
+            if repaired_number == 0 {
+                break;
+            }
+        }
 
-    // Flips all triangles with their neighbours if it is possible.
-    fn flip_triangles(&mut self, triangles: &Arena<DelaunayTriangle>, points: &Arena<Point>) {
-        triangles
+        let inverted_number = triangles
             .handle_iter::<DelaunayTriangleHandle>(points)
-            .for_each(|mut triangle_handle| {
-                triangle_handle.flip_with_neighbours_except(None, 128);
-            });
+            .filter(|t| t.get().is_ok() && !t.is_counterclockwise())
+            .count();
 
-        let triangle_vec = triangles
-            .handle_iter::<DelaunayTriangleHandle>(points)
-            .map(|t| *t.get().unwrap())
-            .collect::<Vec<DelaunayTriangle>>();
-        self.triangles_buffer.write(&triangle_vec).unwrap();
+        if inverted_number == 0 {
+            return Ok(());
+        }
+
+        Err(Error::Triangulation(format!(
+            "{inverted_number} inverted triangles can't be repaired after {MAX_REPAIR_PASSES} passes"
+        )))
     }
 
     // Clears [`DelaunayFactory`] state.
-    fn reset(&mut self) -> anyhow::Result<()> {
-        self.triangle_number_buffer.clear()?;
-        self.free_triangle_index_buffer.clear()?;
-
-        Ok(())
+    fn reset(&mut self) -> crate::Result<()> {
+        match self.inner {
+            FactoryInner::OpenCl(ref mut factory) => factory.reset(),
+            FactoryInner::Cpu(_) => Ok(()),
+        }
     }
 }
 
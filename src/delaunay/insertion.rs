@@ -0,0 +1,253 @@
+use super::{Bounds, DelaunayTriangle, DelaunayTriangleHandle, Edge, TriangleId};
+
+use crate::error::Error;
+use crate::point::{Point, PointHandle, PointId};
+use crate::predicates;
+
+use arena_system::{Arena, Handle, Index};
+use smallvec::SmallVec;
+
+// A place of a point in a triangulation.
+enum Location {
+    // The point is inside the triangle.
+    Triangle(Index),
+    // The point is on the edge which is shared by the triangles.
+    Edge([Index; 2]),
+    // The point coincides with a vertex of the triangulation.
+    Vertex,
+}
+
+// A result of checking a point against a triangle.
+enum Step<'arena> {
+    // The point is found in the triangle.
+    Found(Location),
+    // The point is behind the edge of the triangle.
+    Behind(Edge<'arena>),
+}
+
+// Triangulates `points` by inserting them one by one into two triangles which are
+// spanned on the `bounds` and flipping the triangles around every inserted point.
+//
+// Points which coincide with other points don't get triangles, like the points which
+// share a pixel of the Voronoi image.
+pub(crate) fn insert_points(
+    points: &Arena<Point>,
+    bounds: Bounds,
+) -> crate::Result<Arena<DelaunayTriangle>> {
+    let [b0, b1, b2, b3]: [PointId; 4] = bounds.into();
+
+    let mut triangles =
+        vec![DelaunayTriangle::new([b0, b1, b2]), DelaunayTriangle::new([b0, b2, b3])];
+    triangles.iter_mut().for_each(|t| t.make_counterclockwise(points));
+    triangles[0].neighbours[0] = 1;
+    triangles[1].neighbours[0] = 0;
+
+    let mut triangles: Arena<DelaunayTriangle> = triangles.into();
+    let mut last: Index = (0 as TriangleId).into();
+
+    for id in (0..points.len() as PointId).filter(|id| !bounds.contains(*id)) {
+        last = match locate(&triangles, points, id, last)? {
+            Location::Triangle(triangle) => split_triangle(&mut triangles, points, triangle, id),
+            Location::Edge(edge_triangles) => {
+                split_edge(&mut triangles, points, edge_triangles, id)
+            }
+            Location::Vertex => continue,
+        };
+    }
+
+    Ok(triangles)
+}
+
+// Finds the place of the point `id` walking through the triangles from `start`.
+fn locate(
+    triangles: &Arena<DelaunayTriangle>,
+    points: &Arena<Point>,
+    id: PointId,
+    start: Index,
+) -> crate::Result<Location> {
+    let point = predicates::coords(&points.handle::<PointHandle>(id.into(), None));
+
+    // The walk can cycle in triangulations which aren't Delaunay ones,
+    // so it is bounded and then all triangles are checked.
+    let mut triangle = triangles.handle::<DelaunayTriangleHandle>(start, points);
+    for _ in 0..triangles.len() {
+        match step(triangle, point) {
+            Step::Found(location) => return Ok(location),
+            Step::Behind(edge) => match triangle.try_neighbour_on_edge(edge) {
+                Ok(neighbour) => triangle = neighbour,
+                Err(_) => break,
+            },
+        }
+    }
+
+    triangles
+        .handle_iter::<DelaunayTriangleHandle>(points)
+        .find_map(|t| match step(t, point) {
+            Step::Found(location) => Some(location),
+            Step::Behind(_) => None,
+        })
+        .ok_or_else(|| {
+            Error::Triangulation(format!("The point {id} is outside of the triangulation"))
+        })
+}
+
+// Checks on which side of every edge of the `triangle` the `point` is.
+fn step(triangle: DelaunayTriangleHandle, point: [f64; 2]) -> Step {
+    let vertices = triangle.points();
+    let [a, b, c] = vertices.map(|p| predicates::coords(&p));
+    let orientation = predicates::orient2d(a, b, c).signum();
+
+    let mut on_edge = None;
+    let mut on_edges_number = 0;
+    for i in 0..3 {
+        let edge = [vertices[i], vertices[(i + 1) % 3]];
+        let [p0, p1] = edge.map(|p| predicates::coords(&p));
+        let side = predicates::orient2d(p0, p1, point) * orientation;
+
+        if side < 0.0 {
+            return Step::Behind(edge.into());
+        }
+
+        if side == 0.0 {
+            on_edge = Some(Edge::from(edge));
+            on_edges_number += 1;
+        }
+    }
+
+    match (on_edge, on_edges_number) {
+        (None, _) => Step::Found(Location::Triangle(triangle.index())),
+        (Some(edge), 1) => match triangle.try_neighbour_on_edge(edge) {
+            Ok(neighbour) => Step::Found(Location::Edge([triangle.index(), neighbour.index()])),
+            Err(_) => Step::Behind(edge),
+        },
+        _ => Step::Found(Location::Vertex),
+    }
+}
+
+// Splits the `triangle` into three triangles by the point `id` inside it.
+//
+// Returns the index of one of the new triangles.
+fn split_triangle(
+    triangles: &mut Arena<DelaunayTriangle>,
+    points: &Arena<Point>,
+    triangle: Index,
+    id: PointId,
+) -> Index {
+    let (vertices, neighbours) = {
+        let t = triangles.handle::<DelaunayTriangleHandle>(triangle, points);
+        (vertex_ids(&t), t.neighbours().into_iter().map(|n| n.index()).collect::<Vec<_>>())
+    };
+    let [a, b, c] = vertices;
+
+    let new_triangles = [
+        triangles.add(DelaunayTriangle::new([b, c, id])),
+        triangles.add(DelaunayTriangle::new([c, a, id])),
+    ];
+    set_vertices(triangles, points, triangle, [a, b, id]);
+
+    let mut changed = vec![triangle];
+    changed.extend(new_triangles);
+    connect_and_flip(triangles, points, &changed, &neighbours);
+
+    triangle
+}
+
+// Splits the triangles which share an edge into four triangles by the point `id`
+// on the edge.
+//
+// Returns the index of one of the new triangles.
+fn split_edge(
+    triangles: &mut Arena<DelaunayTriangle>,
+    points: &Arena<Point>,
+    edge_triangles: [Index; 2],
+    id: PointId,
+) -> Index {
+    let (shared, opposite, neighbours) = {
+        let [t, u] = edge_triangles.map(|i| triangles.handle::<DelaunayTriangleHandle>(i, points));
+        let ids = |points: SmallVec<[PointHandle; 2]>| {
+            let ids = points
+                .into_iter()
+                .map(|p| p.index().into())
+                .collect::<SmallVec<[PointId; 2]>>();
+            [ids[0], ids[1]]
+        };
+
+        let neighbours = t
+            .neighbours()
+            .into_iter()
+            .chain(u.neighbours())
+            .map(|n| n.index())
+            .filter(|n| !edge_triangles.contains(n))
+            .collect::<Vec<_>>();
+
+        (ids(t.shared_points_with(&u)), ids(t.opposite_points_with(&u)), neighbours)
+    };
+    let ([a, b], [c, d]) = (shared, opposite);
+
+    let new_triangles = [
+        triangles.add(DelaunayTriangle::new([b, d, id])),
+        triangles.add(DelaunayTriangle::new([d, a, id])),
+    ];
+    set_vertices(triangles, points, edge_triangles[0], [a, c, id]);
+    set_vertices(triangles, points, edge_triangles[1], [c, b, id]);
+
+    let mut changed = edge_triangles.to_vec();
+    changed.extend(new_triangles);
+    connect_and_flip(triangles, points, &changed, &neighbours);
+
+    edge_triangles[0]
+}
+
+// Returns the ids of the vertices of the `triangle`.
+fn vertex_ids(triangle: &DelaunayTriangleHandle) -> [PointId; 3] {
+    triangle.points().map(|p| p.index().into())
+}
+
+// Sets the vertices of the triangle with the index `triangle`.
+fn set_vertices(
+    triangles: &Arena<DelaunayTriangle>,
+    points: &Arena<Point>,
+    triangle: Index,
+    vertices: [PointId; 3],
+) {
+    let t = triangles.handle::<DelaunayTriangleHandle>(triangle, points);
+    t.set_points(vertices.map(|v| points.handle(v.into(), Some(triangles))));
+}
+
+// Makes the `changed` triangles counterclockwise, connects them to each other and
+// to their `neighbours` and then flips them until they satisfy the Delaunay condition.
+fn connect_and_flip(
+    triangles: &Arena<DelaunayTriangle>,
+    points: &Arena<Point>,
+    changed: &[Index],
+    neighbours: &[Index],
+) {
+    let handle = |i: &Index| triangles.handle::<DelaunayTriangleHandle>(*i, points);
+    let changed = changed.iter().map(handle).collect::<Vec<_>>();
+    let around = changed
+        .iter()
+        .copied()
+        .chain(neighbours.iter().map(handle))
+        .collect::<Vec<_>>();
+
+    changed.iter().copied().for_each(|mut t| t.make_counterclockwise());
+
+    around.iter().for_each(|t| {
+        let mut connected = SmallVec::<[DelaunayTriangleHandle; 3]>::new();
+        t.neighbours()
+            .into_iter()
+            .chain(around.iter().copied())
+            .filter(|n| n != t && t.is_connected(n))
+            .for_each(|n| {
+                if !connected.contains(&n) {
+                    connected.push(n);
+                }
+            });
+
+        t.set_neighbours(connected);
+    });
+
+    changed.into_iter().for_each(|mut t| {
+        t.flip_iteratively_with_neighbours_except(None);
+    });
+}
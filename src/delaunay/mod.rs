@@ -1,15 +1,22 @@
 mod bounds;
+mod cpu;
 mod edge;
 mod factory;
+mod insertion;
+mod opencl;
 mod polygon;
 mod triangle;
 mod triangle_fan;
+mod validation;
 
 pub use bounds::Bounds;
 pub use edge::Edge;
 pub use factory::DelaunayFactory;
 pub use polygon::Polygon;
 pub use triangle::{DelaunayTriangle, DelaunayTriangleHandle, TriangleId, Visibility};
+pub use validation::{ValidationReport, Violation};
+
+pub(crate) use validation::validate;
 
 use crate::point::*;
 
@@ -46,6 +53,13 @@ impl Delaunay {
         self.bounds
     }
 
+    /// Checks the invariants of the triangulation.
+    ///
+    /// Contour edges are expected to be inserted into the triangulation.
+    pub fn validate(&self) -> ValidationReport {
+        validate(&self.points, &self.triangles, true)
+    }
+
     /// Converts triangulation into raw parts: a dimension, bounds, points and triangles.
     pub fn into_raw_parts(self) -> (usize, Arena<Point>, Arena<DelaunayTriangle>, Bounds) {
         (self.dim, self.points, self.triangles, self.bounds)
@@ -103,7 +117,12 @@ impl Delaunay {
     }
 
     /// Inserts `edge` into the triangulation.
-    pub fn insert_edge(&mut self, edge: [PointId; 2]) {
+    ///
+    /// Returns [`Error::Triangulation`] if the triangles intersected by the `edge`
+    /// can't be found, e.g. the `edge` refers to points outside of the triangulation.
+    ///
+    /// [`Error::Triangulation`]: crate::Error::Triangulation
+    pub fn insert_edge(&mut self, edge: [PointId; 2]) -> crate::Result<()> {
         let edge: Edge = [
             self.points().handle(edge[0].into(), Some(self.triangles())),
             self.points().handle(edge[1].into(), Some(self.triangles())),
@@ -111,7 +130,7 @@ impl Delaunay {
         .into();
 
         // Find edges and triangles which are intersected by the given `edge`.
-        let (edge_track, triangle_track) = edge.find_triangle_track();
+        let (edge_track, triangle_track) = edge.try_find_triangle_track()?;
 
         // Calculate contours around the `edge`.
         let (contour0, is_counterclockwise0) =
@@ -139,10 +158,15 @@ impl Delaunay {
             .for_each(|t| self.remove_triangle(t));
 
         // Insert the triangulations of the contours into the triangulation.
-        triangulation0.into_iter().chain(triangulation1).for_each(|t| {
+        triangulation0.into_iter().chain(triangulation1).for_each(|mut t| {
+            // The contour by one of the sides of the edge is clockwise.
+            t.make_counterclockwise(self.points());
+
             let triangle_index = self.insert_triangle(t, &neighbours);
             neighbours.push(triangle_index);
         });
+
+        Ok(())
     }
 
     // Calculates a contour which starts from the first point of `base_line`, passes
@@ -203,40 +227,41 @@ impl Delaunay {
         mut contour: Vec<PointHandle>,
         is_counterclockwise: bool
     ) -> Vec<DelaunayTriangle> {
-        let mut middle_vertex = 0;
-        let mut smallest_triangle = None;
-        let mut smallest_circle = f32::MAX;
-
-        // Find the triangle with the smallest circumcircle.
-        for (i, points) in contour.windows(3).enumerate() {
-            let t = DelaunayTriangle::new([
-                points[0].index().into(),
-                points[1].index().into(),
-                points[2].index().into(),
-            ]);
-
-            if t.is_counterclockwise(self.points()) == is_counterclockwise {
-                let r = t.circumcircle_radius(self.points());
+        let mut triangulation = vec![];
 
-                if r < smallest_circle {
-                    smallest_circle = r;
-                    smallest_triangle = Some(t);
-                    middle_vertex = i + 1;
+        // Cut triangles from the contour while it has 3 points at least.
+        while contour.len() >= 3 {
+            let mut middle_vertex = 0;
+            let mut smallest_triangle = None;
+            let mut smallest_circle = f32::MAX;
+
+            // Find the triangle with the smallest circumcircle.
+            for (i, points) in contour.windows(3).enumerate() {
+                let t = DelaunayTriangle::new([
+                    points[0].index().into(),
+                    points[1].index().into(),
+                    points[2].index().into(),
+                ]);
+
+                if t.is_counterclockwise(self.points()) == is_counterclockwise {
+                    let r = t.circumcircle_radius(self.points());
+
+                    if r < smallest_circle {
+                        smallest_circle = r;
+                        smallest_triangle = Some(t);
+                        middle_vertex = i + 1;
+                    }
                 }
             }
-        }
 
-        let mut triangulation = vec![];
-        if let Some(smallest_triangle) = smallest_triangle {
+            let Some(smallest_triangle) = smallest_triangle else {
+                break;
+            };
+
             // Update contour after obtaining the triangle from it.
             contour.remove(middle_vertex);
             // Add the obtained triangle to the triangulation.
             triangulation.push(smallest_triangle);
-
-            // Triangulate the new contour if it has 3 points at least.
-            if contour.len() >= 3 {
-                triangulation.append(&mut self.triangulate_hole(contour, is_counterclockwise));
-            }
         }
 
         triangulation
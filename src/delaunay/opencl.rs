@@ -0,0 +1,315 @@
+use super::triangle::{DelaunayTriangle, TriangleId};
+use super::triangle_fan::TriangleFan;
+
+use crate::error::Error;
+use crate::opencl::{self, Buffer, ImageView, ProgramCache};
+use crate::point::{Point, PointId};
+use crate::voronoi::VoronoiImage;
+
+use arena_system::Arena;
+use smallvec::ToSmallVec;
+
+// Runs the steps of the triangulation with the OpenCL kernels from `delaunay.cl`.
+pub(super) struct OpenClDelaunayFactory {
+    count_triangles_kernel: ocl::Kernel,
+    triangle_number_buffer: Buffer<i32>,
+
+    build_triangles_kernel: ocl::Kernel,
+    triangles_buffer: Buffer<DelaunayTriangle>,
+    free_triangle_index_buffer: Buffer<i32>,
+
+    calculate_triangle_neighbours_kernel: ocl::Kernel,
+
+    count_triangles_in_fans_kernel: ocl::Kernel,
+    triangle_fans_buffer: Buffer<TriangleFan>,
+
+    calculate_triangle_offset_in_fans_kernel: ocl::Kernel,
+
+    find_triangles_in_fans_kernel: ocl::Kernel,
+    flatten_triangle_fans_buffer: Buffer<TriangleId>,
+}
+
+impl OpenClDelaunayFactory {
+    // Creates a new [`OpenClDelaunayFactory`].
+    pub(super) fn new(
+        queue: ocl::Queue,
+        program_cache: Option<&ProgramCache>,
+    ) -> crate::Result<Self> {
+        let program = opencl::build_program(&queue, opencl::DELAUNAY_KERNELS_SRC, program_cache)?;
+
+        let count_triangles_kernel = ocl::Kernel::builder()
+            .queue(queue.clone())
+            .program(&program)
+            .name("count_triangles")
+            .arg(None::<&ocl::Image<i32>>)
+            .arg(None::<&ocl::Buffer<i32>>)
+            .build()?;
+        let mut triangle_number_buffer = Buffer::<i32>::new(queue.clone())?;
+        triangle_number_buffer.write(&[0])?;
+
+        let build_triangles_kernel = ocl::Kernel::builder()
+            .queue(queue.clone())
+            .program(&program)
+            .name("build_triangles")
+            .arg(None::<&ocl::Image<i32>>)
+            .arg(None::<&ocl::Buffer<DelaunayTriangle>>)
+            .arg(None::<&ocl::Buffer<TriangleId>>)
+            .build()?;
+        let triangles_buffer = Buffer::<DelaunayTriangle>::new(queue.clone())?;
+        let mut free_triangle_index_buffer = Buffer::<i32>::new(queue.clone())?;
+        free_triangle_index_buffer.write(&[0])?;
+
+        let calculate_triangle_neighbours_kernel = ocl::Kernel::builder()
+            .queue(queue.clone())
+            .program(&program)
+            .name("calculate_triangle_neighbours")
+            .arg(None::<&ocl::Buffer<DelaunayTriangle>>)
+            .build()?;
+
+        let count_triangles_in_fans_kernel = ocl::Kernel::builder()
+            .queue(queue.clone())
+            .program(&program)
+            .name("count_triangles_in_fans")
+            .arg(None::<&ocl::Buffer<DelaunayTriangle>>)
+            .arg(None::<&ocl::Buffer<TriangleFan>>)
+            .build()?;
+        let triangle_fans_buffer = Buffer::<TriangleFan>::new(queue.clone())?;
+
+        let calculate_triangle_offset_in_fans_kernel = ocl::Kernel::builder()
+            .queue(queue.clone())
+            .program(&program)
+            .name("calculate_triangle_offset_in_fans")
+            .arg(None::<&ocl::Buffer<TriangleFan>>)
+            .arg(None::<&ocl::Buffer<i32>>)
+            .build()?;
+
+        let find_triangles_in_fans_kernel = ocl::Kernel::builder()
+            .queue(queue.clone())
+            .program(&program)
+            .name("find_triangles_in_fans")
+            .arg(None::<&ocl::Buffer<DelaunayTriangle>>)
+            .arg(None::<&ocl::Buffer<TriangleFan>>)
+            .arg(None::<&ocl::Buffer<TriangleId>>)
+            .build()?;
+        let flatten_triangle_fans_buffer = Buffer::<TriangleId>::new(queue)?;
+
+        Ok(Self {
+            count_triangles_kernel,
+            triangle_number_buffer,
+
+            build_triangles_kernel,
+            triangles_buffer,
+            free_triangle_index_buffer,
+
+            calculate_triangle_neighbours_kernel,
+
+            count_triangles_in_fans_kernel,
+            triangle_fans_buffer,
+
+            calculate_triangle_offset_in_fans_kernel,
+
+            find_triangles_in_fans_kernel,
+            flatten_triangle_fans_buffer,
+        })
+    }
+
+    // Builds triangles according to data from the [`VoronoiImage`].
+    pub(super) fn build_triangles(
+        &mut self,
+        voronoi_image: &VoronoiImage<'_>,
+        points: &Arena<Point>,
+    ) -> crate::Result<Vec<DelaunayTriangle>> {
+        let image = opencl_image(voronoi_image)?;
+
+        let triangle_number = self.count_triangles(voronoi_image)?;
+        if triangle_number == 0 {
+            return Ok(vec![]);
+        }
+
+        let mut triangles = vec![DelaunayTriangle::default(); triangle_number as usize];
+        self.triangles_buffer.write(&triangles)?;
+
+        self.free_triangle_index_buffer.clear()?;
+
+        self.build_triangles_kernel
+            .set_default_global_work_size((voronoi_image.dim(), voronoi_image.dim()).into())
+            .set_default_local_work_size((8, 8).into());
+
+        self.build_triangles_kernel.set_arg(0, image.ocl_image())?;
+        self.build_triangles_kernel.set_arg(1, self.triangles_buffer.as_raw())?;
+        self.build_triangles_kernel
+            .set_arg(2, self.free_triangle_index_buffer.as_raw())?;
+
+        unsafe {
+            self.build_triangles_kernel.enq()?;
+        }
+
+        self.triangles_buffer.read(&mut triangles)?;
+        triangles.iter_mut().for_each(|t| t.make_counterclockwise(points));
+
+        Ok(triangles)
+    }
+
+    // Counts triangles in triangulation according to data from the [`VoronoiImage`].
+    fn count_triangles(&mut self, voronoi_image: &VoronoiImage<'_>) -> crate::Result<i32> {
+        let image = opencl_image(voronoi_image)?;
+
+        self.count_triangles_kernel
+            .set_default_global_work_size((voronoi_image.dim(), voronoi_image.dim()).into())
+            .set_default_local_work_size((8, 8).into());
+
+        self.count_triangles_kernel.set_arg(0, image.ocl_image())?;
+        self.count_triangles_kernel
+            .set_arg(1, self.triangle_number_buffer.as_raw())?;
+
+        unsafe {
+            self.count_triangles_kernel.enq()?;
+        }
+
+        let triangle_number = self.triangle_number_buffer.first()?;
+
+        Ok(triangle_number)
+    }
+
+    // Connects triangles with their neighbours.
+    pub(super) fn calculate_triangle_neighbours(
+        &mut self,
+        triangles: &mut [DelaunayTriangle],
+    ) -> crate::Result<()> {
+        self.triangles_buffer.write(triangles)?;
+
+        self.calculate_triangle_neighbours_kernel
+            .set_default_global_work_size((triangles.len(), triangles.len()).into())
+            .set_default_local_work_size((1, 1).into());
+
+        self.calculate_triangle_neighbours_kernel
+            .set_arg(0, self.triangles_buffer.as_raw())?;
+
+        unsafe {
+            self.calculate_triangle_neighbours_kernel.enq()?;
+        }
+
+        self.triangles_buffer.read(triangles)?;
+
+        Ok(())
+    }
+
+    // Sets triangle fans for points.
+    pub(super) fn calculate_triangle_fans(
+        &mut self,
+        triangles: &[DelaunayTriangle],
+        points: &mut Arena<Point>,
+    ) -> crate::Result<()> {
+        self.triangles_buffer.write(triangles)?;
+
+        let mut triangle_fans = self.create_triangle_fans(points.len())?;
+        self.count_triangles_in_fans()?;
+        self.calculate_triangle_offset_in_fans()?;
+        let flatten_triangle_fans = self.find_triangles_in_fans()?;
+        self.triangle_fans_buffer.read(&mut triangle_fans)?;
+
+        triangle_fans.into_iter().for_each(|tf| {
+            let start = tf.triangle_offset as usize;
+            let end = tf.triangle_offset as usize + tf.triangle_number as usize;
+            points
+                .lookup_mut(tf.center.into())
+                .unwrap()
+                .set_triangle_fan(flatten_triangle_fans[start..end].to_smallvec());
+        });
+
+        Ok(())
+    }
+    // Creates empty triangle fans.
+    fn create_triangle_fans(&mut self, points_number: usize) -> crate::Result<Vec<TriangleFan>> {
+        let triangle_fans = (0..points_number as PointId)
+            .map(TriangleFan::new)
+            .collect::<Vec<TriangleFan>>();
+
+        self.triangle_fans_buffer.write(&triangle_fans)?;
+
+        Ok(triangle_fans)
+    }
+
+    // Counts triangles in the triangle fan of every point.
+    fn count_triangles_in_fans(&mut self) -> crate::Result<()> {
+        self.count_triangles_in_fans_kernel
+            .set_default_global_work_size(
+                (self.triangles_buffer.len(), self.triangle_fans_buffer.len()).into(),
+            )
+            .set_default_local_work_size((1, 1).into());
+
+        self.count_triangles_in_fans_kernel
+            .set_arg(0, self.triangles_buffer.as_raw())?;
+        self.count_triangles_in_fans_kernel
+            .set_arg(1, self.triangle_fans_buffer.as_raw())?;
+
+        unsafe {
+            self.count_triangles_in_fans_kernel.enq()?;
+        }
+
+        Ok(())
+    }
+
+    // Assigns offset to every triangle fan to use it later
+    // when read from flatten triangle fans list.
+    fn calculate_triangle_offset_in_fans(&mut self) -> crate::Result<()> {
+        self.free_triangle_index_buffer.clear()?;
+
+        self.calculate_triangle_offset_in_fans_kernel
+            .set_default_global_work_size(self.triangle_fans_buffer.len().into())
+            .set_default_local_work_size((1, 1).into());
+
+        self.calculate_triangle_offset_in_fans_kernel
+            .set_arg(0, self.triangle_fans_buffer.as_raw())?;
+        self.calculate_triangle_offset_in_fans_kernel
+            .set_arg(1, self.free_triangle_index_buffer.as_raw())?;
+
+        unsafe {
+            self.calculate_triangle_offset_in_fans_kernel.enq()?;
+        }
+
+        Ok(())
+    }
+
+    // Finds all triangles in triangle fans and writes them to `flatten_triangle_fans`.
+    fn find_triangles_in_fans(&mut self) -> crate::Result<Vec<TriangleId>> {
+        let mut flatten_triangle_fans = vec![-1; self.triangles_buffer.len() * 3];
+        self.flatten_triangle_fans_buffer.write(&flatten_triangle_fans)?;
+
+        self.find_triangles_in_fans_kernel
+            .set_default_global_work_size(
+                (self.triangles_buffer.len(), self.triangle_fans_buffer.len()).into(),
+            )
+            .set_default_local_work_size((1, 1).into());
+
+        self.find_triangles_in_fans_kernel
+            .set_arg(0, self.triangles_buffer.as_raw())?;
+        self.find_triangles_in_fans_kernel
+            .set_arg(1, self.triangle_fans_buffer.as_raw())?;
+        self.find_triangles_in_fans_kernel
+            .set_arg(2, self.flatten_triangle_fans_buffer.as_raw())?;
+
+        unsafe {
+            self.find_triangles_in_fans_kernel.enq()?;
+        }
+
+        self.flatten_triangle_fans_buffer.read(&mut flatten_triangle_fans)?;
+
+        Ok(flatten_triangle_fans)
+    }
+
+    // Clears [`OpenClDelaunayFactory`] state.
+    pub(super) fn reset(&mut self) -> crate::Result<()> {
+        self.triangle_number_buffer.clear()?;
+        self.free_triangle_index_buffer.clear()?;
+
+        Ok(())
+    }
+}
+
+// Returns the OpenCL image of the given `voronoi_image`.
+fn opencl_image<'a>(voronoi_image: &'a VoronoiImage<'_>) -> crate::Result<&'a ImageView<i32>> {
+    voronoi_image.image().ok_or_else(|| {
+        Error::Triangulation("The Voronoi image wasn't drawn by the OpenCL backend".to_string())
+    })
+}
@@ -1,6 +1,8 @@
-use super::{edge::Edge, Polygon};
+use super::edge::Edge;
 
+use crate::error::Error;
 use crate::point::{Point, PointHandle, PointId};
+use crate::predicates;
 
 use std::{fmt, iter, hash, collections::HashSet};
 
@@ -70,32 +72,34 @@ impl DelaunayTriangle {
     /// Returns the radius of the circumcircle around the triangle.
     #[allow(non_snake_case)]
     pub fn circumcircle_radius(&self, points: &Arena<Point>) -> f32 {
-        let A = points.handle::<PointHandle>(self.vertices[0].into(), None);
-        let B = points.handle::<PointHandle>(self.vertices[1].into(), None);
-        let C = points.handle::<PointHandle>(self.vertices[2].into(), None);
+        let [A, B, C] = self
+            .vertices
+            .map(|v| predicates::coords(&points.handle::<PointHandle>(v.into(), None)));
+
+        // The circumcircle doesn't exist if the points are collinear.
+        if predicates::orient2d(A, B, C) == 0.0 {
+            return -1.0;
+        }
 
         let mut a = [0.0; 4];
         let mut b = [0.0; 2];
-        a[0] = (A.x() - B.x()) * 2.0;
-        a[1] = (A.y() - B.y()) * 2.0;
-        a[2] = (B.x() - C.x()) * 2.0;
-        a[3] = (B.y() - C.y()) * 2.0;
-        b[0] = A.x() * A.x() + A.y() * A.y() - B.x() * B.x() - B.y() * B.y();
-        b[1] = B.x() * B.x() + B.y() * B.y() - C.x() * C.x() - C.y() * C.y();
+        a[0] = (A[0] - B[0]) * 2.0;
+        a[1] = (A[1] - B[1]) * 2.0;
+        a[2] = (B[0] - C[0]) * 2.0;
+        a[3] = (B[1] - C[1]) * 2.0;
+        b[0] = A[0] * A[0] + A[1] * A[1] - B[0] * B[0] - B[1] * B[1];
+        b[1] = B[0] * B[0] + B[1] * B[1] - C[0] * C[0] - C[1] * C[1];
 
         let det = a[0] * a[3] - a[1] * a[2];
-        if libm::fabsf(det) <= f32::EPSILON {
-            return -1.0;
-        }
 
         let mut center = [0.0; 2];
         center[0] = (b[0] * a[3] - a[1] * b[1]) / det;
         center[1] = (a[0] * b[1] - b[0] * a[2]) / det;
 
-        let dx = A.x() - center[0];
-        let dy = A.y() - center[1];
+        let dx = A[0] - center[0];
+        let dy = A[1] - center[1];
 
-        libm::sqrtf(dx * dx + dy * dy)
+        (dx * dx + dy * dy).sqrt() as f32
     }
 }
 
@@ -108,6 +112,14 @@ impl std::default::Default for DelaunayTriangle {
 unsafe impl ocl::traits::OclPrm for DelaunayTriangle {}
 
 /// A handle of the [`DelaunayTriangle`] which is used by [`Arena`].
+///
+/// # Panics
+///
+/// Accessors panic if the triangle was removed from the arena or is borrowed mutably
+/// elsewhere at the same time. The triangulation drops the handles of the triangles
+/// which it removes, e.g. while flipping edges, and never holds a borrow across calls,
+/// so the panic is unreachable unless the arena is misused. The `try_` accessors,
+/// e.g. [`DelaunayTriangleHandle::try_points`], return [`Error::Triangulation`] instead.
 #[derive(Clone, Copy)]
 pub struct DelaunayTriangleHandle<'arena> {
     raw: RawHandle<'arena, DelaunayTriangle>,
@@ -117,7 +129,7 @@ pub struct DelaunayTriangleHandle<'arena> {
 impl<'arena> DelaunayTriangleHandle<'arena> {
     /// Returns [`PointHandle`]s for the vertices of the triangle.
     pub fn points(&self) -> [PointHandle<'arena>; 3] {
-        let vertices = self.get().unwrap().vertices;
+        let vertices = self.get().expect("Can't get the triangle").vertices;
 
         [
             self.points.handle(vertices[0].into(), Some(self.arena())),
@@ -126,9 +138,19 @@ impl<'arena> DelaunayTriangleHandle<'arena> {
         ]
     }
 
+    /// Returns [`PointHandle`]s for the vertices of the triangle.
+    ///
+    /// Returns [`Error::Triangulation`] if the triangle can't be borrowed,
+    /// see [`DelaunayTriangleHandle`].
+    pub fn try_points(&self) -> crate::Result<[PointHandle<'arena>; 3]> {
+        let vertices = self.get().map_err(|_| self.stale_error())?.vertices;
+
+        Ok(vertices.map(|vertex| self.points.handle(vertex.into(), Some(self.arena()))))
+    }
+
     /// Sets the given `points` as the vertices of the triangle.
     pub fn set_points(&self, points: [PointHandle; 3]) {
-        let mut this = self.get_mut().unwrap();
+        let mut this = self.get_mut().expect("Can't get the triangle");
 
         this.vertices =
             [points[0].index().into(), points[1].index().into(), points[2].index().into()]
@@ -136,7 +158,7 @@ impl<'arena> DelaunayTriangleHandle<'arena> {
 
     /// Returns [`DelaunayTriangleHandle`]s for the neighbours of the triangle.
     pub fn neighbours(&self) -> SmallVec<[DelaunayTriangleHandle<'arena>; 3]> {
-        let neighbour_ids = self.get().unwrap().neighbours;
+        let neighbour_ids = self.get().expect("Can't get the triangle").neighbours;
         neighbour_ids
             .into_iter()
             .filter(|neighbour_id| *neighbour_id != -1)
@@ -144,9 +166,23 @@ impl<'arena> DelaunayTriangleHandle<'arena> {
             .collect()
     }
 
+    /// Returns the neighbours of the triangle.
+    ///
+    /// Returns [`Error::Triangulation`] if the triangle can't be borrowed,
+    /// see [`DelaunayTriangleHandle`].
+    pub fn try_neighbours(&self) -> crate::Result<SmallVec<[DelaunayTriangleHandle<'arena>; 3]>> {
+        let neighbour_ids = self.get().map_err(|_| self.stale_error())?.neighbours;
+
+        Ok(neighbour_ids
+            .into_iter()
+            .filter(|neighbour_id| *neighbour_id != -1)
+            .map(|neighbour_id| self.arena().handle(neighbour_id.into(), self.points))
+            .collect())
+    }
+
     /// Sets the `new_neighbours` as the neighbours of the triangle.
     pub fn set_neighbours(&self, new_neighbours: SmallVec<[DelaunayTriangleHandle<'arena>; 3]>) {
-        let neighbours = &mut self.get_mut().unwrap().neighbours;
+        let neighbours = &mut self.get_mut().expect("Can't get the triangle").neighbours;
         *neighbours = [-1; 3];
 
         neighbours
@@ -157,12 +193,12 @@ impl<'arena> DelaunayTriangleHandle<'arena> {
 
     /// Checks if the triangle is visible.
     pub fn visibility(&self) -> Visibility {
-        self.get_mut().unwrap().visibility
+        self.get_mut().expect("Can't get the triangle").visibility
     }
 
     /// Sets the visibility of the triangle.
     pub fn set_visibiity(&self, visibility: Visibility) {
-        self.get_mut().unwrap().visibility = visibility;
+        self.get_mut().expect("Can't get the triangle").visibility = visibility;
     }
 
     /// Returns the edges of the triangle.
@@ -197,7 +233,7 @@ impl<'arena> DelaunayTriangleHandle<'arena> {
         index: Index,
         new_neighbour: DelaunayTriangleHandle<'arena>,
     ) {
-        let neighbour_ids = &mut self.get_mut().unwrap().neighbours;
+        let neighbour_ids = &mut self.get_mut().expect("Can't get the triangle").neighbours;
         let position = neighbour_ids.iter().position(|n| *n == index.into());
 
         if let Some(position) = position {
@@ -207,7 +243,7 @@ impl<'arena> DelaunayTriangleHandle<'arena> {
 
     /// Adds `new_neighbour` to the neighbour list of the triangle if it has free space.
     pub fn try_add_neighbour(&self, new_neighbour: DelaunayTriangleHandle<'arena>) -> bool {
-        let neighbour_ids = &mut self.get_mut().unwrap().neighbours;
+        let neighbour_ids = &mut self.get_mut().expect("Can't get the triangle").neighbours;
         if neighbour_ids.contains(&new_neighbour.index().into()) {
             return false;
         }
@@ -226,7 +262,7 @@ impl<'arena> DelaunayTriangleHandle<'arena> {
     /// Removes the neighbour with the index `index` from the neighbour list
     /// of the triangle if it exists there.
     pub fn try_remove_neighbour(&self, index: Index) -> bool {
-        let neighbour_ids = &mut self.get_mut().unwrap().neighbours;
+        let neighbour_ids = &mut self.get_mut().expect("Can't get the triangle").neighbours;
         let position = neighbour_ids.iter().position(|n| *n == index.into());
 
         if let Some(position) = position {
@@ -240,14 +276,14 @@ impl<'arena> DelaunayTriangleHandle<'arena> {
 
     /// Checks if the triangle is counterclockwise.
     pub fn is_counterclockwise(&self) -> bool {
-        let this = self.get().unwrap();
+        let this = self.get().expect("Can't get the triangle");
 
         this.is_counterclockwise(self.points)
     }
 
     /// Makes the triangle counterclockwise.
     pub fn make_counterclockwise(&mut self) {
-        let mut this = self.get_mut().unwrap();
+        let mut this = self.get_mut().expect("Can't get the triangle");
 
         this.make_counterclockwise(self.points);
     }
@@ -300,32 +336,34 @@ impl<'arena> DelaunayTriangleHandle<'arena> {
             .expect("No neighbour which shares the specified edge")
     }
 
-    /// Checks if the triangle is fully in the circle of the other triangle.
+    /// Returns the neighbour of the triangle on the given `edge`.
+    ///
+    /// Returns [`Error::Triangulation`] if the triangle can't be borrowed or has
+    /// no neighbour on the `edge`.
+    pub fn try_neighbour_on_edge(&self, edge: Edge) -> crate::Result<DelaunayTriangleHandle<'arena>> {
+        self.try_neighbours()?
+            .into_iter()
+            .find(|n| edge == self.shared_points_with(n).into())
+            .ok_or_else(|| {
+                let index: TriangleId = self.index().into();
+                Error::Triangulation(format!("The triangle {index} has no neighbour on the edge"))
+            })
+    }
+
+    /// Checks if the opposite point of the other triangle is inside the circumcircle
+    /// of the triangle, so the triangles don't satisfy the Delaunay condition.
     pub fn is_in_circle_with(&self, other: &DelaunayTriangleHandle) -> bool {
         let s = self.shared_points_with(other);
         let o = self.opposite_points_with(other);
 
-        let origin = s[0];
-        let mut polygon = Polygon::new([o[0], s[1], o[1]].to_vec());
-
-        polygon.sort_by_angle(origin);
+        // The first opposite point belongs to the triangle and the second one to the other.
+        let [a, b, c, d] = [s[0], s[1], o[0], o[1]].map(|p| predicates::coords(&p));
 
-        let p = polygon
-            .points()
-            .iter()
-            .map(|p| Point::new(p.x() - origin.x(), p.y() - origin.y()))
-            .rev()
-            .collect::<Vec<Point>>();
+        // The sign of `incircle` is reversed for clockwise points.
+        let orientation = predicates::orient2d(a, b, c);
+        let incircle = predicates::incircle(a, b, c, d);
 
-        let abdet = p[0].x() * p[1].y() - p[1].x() * p[0].y();
-        let bcdet = p[1].x() * p[2].y() - p[2].x() * p[1].y();
-        let cadet = p[2].x() * p[0].y() - p[0].x() * p[2].y();
-
-        let alift = p[0].x() * p[0].x() + p[0].y() * p[0].y();
-        let blift = p[1].x() * p[1].x() + p[1].y() * p[1].y();
-        let clift = p[2].x() * p[2].x() + p[2].y() * p[2].y();
-
-        alift * bcdet + blift * cadet + clift * abdet > 0.0
+        orientation > 0.0 && incircle > 0.0 || orientation < 0.0 && incircle < 0.0
     }
 
     /// Checks if the triangle can be flipped with the other one.
@@ -342,9 +380,8 @@ impl<'arena> DelaunayTriangleHandle<'arena> {
             opposite_points[0].is_bounding() || opposite_points[1].is_bounding();
 
         // Can't be flipped if the triangles will intersect after flip.
-        let sp0 = shared_points[0].skew_product(&opposite_points[0], &opposite_points[1]);
-        let sp1 = shared_points[1].skew_product(&opposite_points[0], &opposite_points[1]);
-        let by_the_same_side_after_flip = sp0.signum() == sp1.signum();
+        let by_the_same_side_after_flip =
+            !are_by_different_sides(shared_points[0], shared_points[1], &opposite_points);
 
         // Can't be flipped if the shared edge is in the contour.
         let has_contour_edge = Edge::from(shared_points).is_contour();
@@ -372,26 +409,113 @@ impl<'arena> DelaunayTriangleHandle<'arena> {
     }
 
     /// Flips the triangle with the other one.
-    pub fn flip_with(
+    ///
+    /// The triangles around them are flipped with their neighbours until all of them
+    /// satisfy the Delaunay condition, but at most `deep` flips away from the first one.
+    ///
+    /// Returns `false` if the triangles can't be flipped.
+    pub fn flip_with(&mut self, other: &mut DelaunayTriangleHandle<'arena>, deep: usize) -> bool {
+        self.flip_with_depth(other, Some(deep))
+    }
+
+    /// Flips the triangle with the other one and then flips the triangles around them
+    /// until all of them satisfy the Delaunay condition.
+    ///
+    /// Unlike [`DelaunayTriangleHandle::flip_with`], the depth of the flips isn't limited.
+    ///
+    /// Returns `false` if the triangles can't be flipped.
+    pub fn flip_iteratively_with(&mut self, other: &mut DelaunayTriangleHandle<'arena>) -> bool {
+        self.flip_with_depth(other, None)
+    }
+
+    // Flips the triangle with the other one and then the triangles around them
+    // which are at most `max_depth` flips away from the first one.
+    fn flip_with_depth(
         &mut self,
         other: &mut DelaunayTriangleHandle<'arena>,
-        mut deep: usize,
+        max_depth: Option<usize>,
     ) -> bool {
-        let is_flippable = self.is_flippable_with(other) && deep != 0;
-        if is_flippable {
-            self.flip_edge(other);
+        if max_depth == Some(0) || !self.is_flippable_with(other) {
+            return false;
+        }
 
-            let neighbourhood = self.neighbourhood(*other);
-            self.update_neighbours(neighbourhood.clone());
-            other.update_neighbours(neighbourhood);
+        self.flip_and_update_neighbours(other);
 
-            deep -= 1;
+        // Triangles which should be flipped with their neighbours except the given ones
+        // with the depth of the flip. The triangles are processed in the same order
+        // as a depth-first traversal would do.
+        let mut stack = vec![(*other, *self, 2), (*self, *other, 2)];
 
-            self.flip_with_neighbours_except(Some(*other), deep);
-            other.flip_with_neighbours_except(Some(*self), deep);
+        while let Some((mut triangle, exception, depth)) = stack.pop() {
+            if max_depth.is_some_and(|max_depth| depth > max_depth) {
+                continue;
+            }
+
+            let neighbour = triangle
+                .neighbours()
+                .into_iter()
+                .filter(|neighbour| *neighbour != exception)
+                .find(|neighbour| triangle.is_flippable_with(neighbour));
+
+            if let Some(mut neighbour) = neighbour {
+                triangle.flip_and_update_neighbours(&mut neighbour);
+
+                stack.push((neighbour, triangle, depth + 1));
+                stack.push((triangle, neighbour, depth + 1));
+            }
         }
 
-        is_flippable
+        true
+    }
+
+    // Flips the shared edge of the triangles and reconnects them with their neighbours.
+    fn flip_and_update_neighbours(&mut self, other: &mut DelaunayTriangleHandle<'arena>) {
+        self.flip_edge(other);
+
+        let neighbourhood = self.neighbourhood(*other);
+        self.update_neighbours(neighbourhood.clone());
+        other.update_neighbours(neighbourhood);
+    }
+
+    /// Repairs the triangle which is inverted after its points were moved
+    /// by flipping it with one of its neighbours.
+    ///
+    /// Returns `false` if no flip can repair the triangle.
+    pub fn repair_inversion(&mut self) -> bool {
+        self.neighbours().into_iter().any(|mut neighbour| {
+            if !self.is_repairable_with(&neighbour) {
+                return false;
+            }
+
+            self.flip_and_update_neighbours(&mut neighbour);
+
+            true
+        })
+    }
+
+    // Checks if flipping the shared edge with the other triangle unfolds the inverted triangle.
+    fn is_repairable_with(&self, other: &DelaunayTriangleHandle) -> bool {
+        let shared_points = self.shared_points_with(other);
+        let opposite_points = self.opposite_points_with(other);
+
+        if !(shared_points.len() == 2 && opposite_points.len() == 2) {
+            return false;
+        }
+
+        // The shared points must be by the different sides of the new edge.
+        if !are_by_different_sides(shared_points[0], shared_points[1], &opposite_points) {
+            return false;
+        }
+
+        // The inverted triangle folds over the other one, so the new triangles must cover
+        // exactly the area of the other triangle which isn't covered by the inverted one.
+        let area = |p: [PointHandle; 3]| p[1].skew_product(&p[0], &p[2]).abs();
+        let folded_area = area(self.points());
+        let other_area = area(other.points());
+        let new_area = area([opposite_points[0], opposite_points[1], shared_points[0]])
+            + area([opposite_points[0], opposite_points[1], shared_points[1]]);
+
+        (new_area - (other_area - folded_area)).abs() <= other_area * 1e-3
     }
 
     // Flips the shared edge of the triangles.
@@ -449,7 +573,10 @@ impl<'arena> DelaunayTriangleHandle<'arena> {
             });
     }
 
-    /// Flips the triangle with its neighbours except the `exception` triangle.
+    /// Flips the triangle with the first of its neighbours except the `exception` triangle
+    /// which can be flipped with it, see [`DelaunayTriangleHandle::flip_with`].
+    ///
+    /// Returns `false` if none of the neighbours can be flipped with the triangle.
     pub fn flip_with_neighbours_except(
         &mut self,
         exception: Option<DelaunayTriangleHandle>,
@@ -457,17 +584,29 @@ impl<'arena> DelaunayTriangleHandle<'arena> {
     ) -> bool {
         self.neighbours()
             .into_iter()
-            .filter(
-                |neighbour| {
-                    if let Some(exception) = exception {
-                        *neighbour != exception
-                    } else {
-                        true
-                    }
-                },
-            )
+            .filter(|neighbour| Some(*neighbour) != exception)
             .any(|mut neighbour| self.flip_with(&mut neighbour, deep))
     }
+
+    /// Flips the triangle with the first of its neighbours except the `exception` triangle
+    /// which can be flipped with it, see [`DelaunayTriangleHandle::flip_iteratively_with`].
+    ///
+    /// Returns `false` if none of the neighbours can be flipped with the triangle.
+    pub fn flip_iteratively_with_neighbours_except(
+        &mut self,
+        exception: Option<DelaunayTriangleHandle>,
+    ) -> bool {
+        self.neighbours()
+            .into_iter()
+            .filter(|neighbour| Some(*neighbour) != exception)
+            .any(|mut neighbour| self.flip_iteratively_with(&mut neighbour))
+    }
+
+    // Returns the error which is reported for the triangle which can't be borrowed.
+    fn stale_error(&self) -> Error {
+        let index: TriangleId = self.index().into();
+        Error::Triangulation(format!("The triangle {index} can't be borrowed"))
+    }
 }
 
 impl<'arena> Handle<'arena> for DelaunayTriangleHandle<'arena> {
@@ -514,4 +653,12 @@ impl hash::Hash for DelaunayTriangleHandle<'_> {
         let index: i64 = self.index().into();
         index.hash(state);
     }
-}
\ No newline at end of file
+}
+
+// Checks if the points `p0` and `p1` are strictly by the different sides of the `line`.
+fn are_by_different_sides(p0: PointHandle, p1: PointHandle, line: &[PointHandle]) -> bool {
+    let sp0 = p0.skew_product(&line[0], &line[1]);
+    let sp1 = p1.skew_product(&line[0], &line[1]);
+
+    sp0 > 0.0 && sp1 < 0.0 || sp0 < 0.0 && sp1 > 0.0
+}
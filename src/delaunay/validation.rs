@@ -0,0 +1,340 @@
+use super::{DelaunayTriangle, DelaunayTriangleHandle, TriangleId};
+
+use crate::point::{Point, PointHandle, PointId};
+use crate::predicates;
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use arena_system::{Arena, Handle};
+use smallvec::SmallVec;
+
+/// A violation of an invariant of a triangulation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Violation {
+    /// The triangle isn't counterclockwise or it is degenerate.
+    NotCounterclockwise { triangle: TriangleId },
+    /// The triangle has the neighbour which doesn't have the triangle as its neighbour.
+    AsymmetricNeighbours { triangle: TriangleId, neighbour: TriangleId },
+    /// The triangle has the neighbour which doesn't share an edge with it.
+    DisconnectedNeighbours { triangle: TriangleId, neighbour: TriangleId },
+    /// The triangle shares an edge with the other one, but they aren't neighbours.
+    MissingNeighbour { triangle: TriangleId, neighbour: TriangleId },
+    /// The triangle fan of the point contains the triangle which doesn't contain the point
+    /// or the triangle contains the point which doesn't have it in the triangle fan.
+    InconsistentTriangleFan { point: PointId, triangle: TriangleId },
+    /// The interiors of the triangles overlap.
+    OverlappingTriangles { triangles: [TriangleId; 2] },
+    /// The edge of the contour isn't an edge of any triangle.
+    MissingContourEdge { edge: [PointId; 2] },
+    /// The edge which isn't in the contour doesn't satisfy the Delaunay condition.
+    NonDelaunayEdge { edge: [PointId; 2] },
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotCounterclockwise { triangle } => {
+                write!(f, "The triangle {triangle} isn't counterclockwise")
+            }
+            Self::AsymmetricNeighbours { triangle, neighbour } => {
+                write!(f, "The triangle {triangle} is not a neighbour of its neighbour {neighbour}")
+            }
+            Self::DisconnectedNeighbours { triangle, neighbour } => write!(
+                f,
+                "The triangle {triangle} doesn't share an edge with its neighbour {neighbour}"
+            ),
+            Self::MissingNeighbour { triangle, neighbour } => write!(
+                f,
+                "The triangle {triangle} shares an edge with {neighbour}, but it isn't a neighbour"
+            ),
+            Self::InconsistentTriangleFan { point, triangle } => write!(
+                f,
+                "The triangle fan of the point {point} is inconsistent with the triangle {triangle}"
+            ),
+            Self::OverlappingTriangles { triangles } => {
+                write!(f, "The triangles {} and {} overlap", triangles[0], triangles[1])
+            }
+            Self::MissingContourEdge { edge } => {
+                write!(f, "The contour edge {:?} is missing", edge)
+            }
+            Self::NonDelaunayEdge { edge } => {
+                write!(f, "The edge {:?} doesn't satisfy the Delaunay condition", edge)
+            }
+        }
+    }
+}
+
+/// A report about the invariants of a triangulation which are violated.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ValidationReport {
+    violations: Vec<Violation>,
+}
+
+impl ValidationReport {
+    /// Checks if no invariant is violated.
+    pub fn is_valid(&self) -> bool {
+        self.violations.is_empty()
+    }
+
+    /// Returns the violations which are found.
+    pub fn violations(&self) -> &[Violation] {
+        &self.violations
+    }
+
+    /// Converts the report into the violations which are found.
+    pub fn into_violations(self) -> Vec<Violation> {
+        self.violations
+    }
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_valid() {
+            return write!(f, "The triangulation is valid");
+        }
+
+        writeln!(f, "The triangulation has {} violations:", self.violations.len())?;
+        self.violations
+            .iter()
+            .try_for_each(|violation| writeln!(f, "- {violation}"))
+    }
+}
+
+// Validates the triangulation which consists of `points` and `triangles`.
+//
+// Neighbours and triangle fans are checked only if `check_topology` is set.
+// Edges with bounding points aren't checked for the Delaunay condition,
+// because the bounds are far away from the points of the triangulation.
+pub(crate) fn validate(
+    points: &Arena<Point>,
+    triangles: &Arena<DelaunayTriangle>,
+    check_topology: bool,
+) -> ValidationReport {
+    let mut violations = vec![];
+
+    let triangle_vertices = triangles
+        .handle_iter::<DelaunayTriangleHandle>(points)
+        .filter_map(|t| t.get().ok().map(|triangle| (t.index().into(), *triangle)))
+        .collect::<HashMap<TriangleId, DelaunayTriangle>>();
+    let mut triangle_ids = triangle_vertices.keys().copied().collect::<Vec<_>>();
+    triangle_ids.sort_unstable();
+
+    let point = |id: PointId| points.handle::<PointHandle>(id.into(), None);
+
+    // Every triangle must be counterclockwise.
+    triangle_ids.iter().for_each(|&id| {
+        if !triangle_vertices[&id].is_counterclockwise(points) {
+            violations.push(Violation::NotCounterclockwise { triangle: id });
+        }
+    });
+
+    // Collect triangles which share every edge.
+    let mut edges = HashMap::<[PointId; 2], SmallVec<[TriangleId; 2]>>::new();
+    triangle_ids.iter().for_each(|&id| {
+        let v = triangle_vertices[&id].vertices;
+        (0..3).for_each(|i| edges.entry(sorted_edge(v[i], v[(i + 1) % 3])).or_default().push(id));
+    });
+
+    if check_topology {
+        validate_neighbours(&triangle_ids, &triangle_vertices, &edges, &mut violations);
+        validate_triangle_fans(points, &triangle_ids, &triangle_vertices, &mut violations);
+    }
+
+    validate_overlaps(points, &triangle_ids, &triangle_vertices, &mut violations);
+
+    // Every edge of the contour must be an edge of a triangle.
+    // Points which were merged with others aren't the vertices of the triangulation,
+    // so the contour edges can be checked only between the vertices.
+    let vertices = triangle_vertices
+        .values()
+        .flat_map(|t| t.vertices)
+        .collect::<HashSet<PointId>>();
+    let mut contour_edges = HashSet::new();
+    (0..points.len() as PointId)
+        .filter(|id| vertices.contains(id))
+        .for_each(|id| {
+            let previous = point(id).get().map(|p| p.previous_in_outline()).unwrap_or(-1);
+            if vertices.contains(&previous) && previous != id {
+                let edge = sorted_edge(previous, id);
+                contour_edges.insert(edge);
+
+                if !edges.contains_key(&edge) {
+                    violations.push(Violation::MissingContourEdge { edge: [previous, id] });
+                }
+            }
+        });
+
+    // Every edge which isn't in the contour must satisfy the Delaunay condition.
+    let mut shared_edges = edges
+        .iter()
+        .filter(|(edge, triangles)| triangles.len() == 2 && !contour_edges.contains(*edge))
+        .collect::<Vec<_>>();
+    shared_edges.sort_unstable_by_key(|(edge, _)| **edge);
+
+    shared_edges.into_iter().for_each(|(edge, edge_triangles)| {
+        let opposite = |triangle: TriangleId| {
+            triangle_vertices[&triangle]
+                .vertices
+                .into_iter()
+                .find(|v| !edge.contains(v))
+                .unwrap()
+        };
+        let quad = [edge[0], edge[1], opposite(edge_triangles[0]), opposite(edge_triangles[1])];
+
+        if quad.iter().any(|&id| point(id).is_bounding()) {
+            return;
+        }
+
+        let [a, b, c, d] = quad.map(|id| predicates::coords(&point(id)));
+        let orientation = predicates::orient2d(a, b, c);
+        let incircle = predicates::incircle(a, b, c, d);
+
+        if orientation > 0.0 && incircle > 0.0 || orientation < 0.0 && incircle < 0.0 {
+            violations.push(Violation::NonDelaunayEdge { edge: *edge });
+        }
+    });
+
+    ValidationReport { violations }
+}
+
+// Checks that the neighbours of the triangles are symmetric and share edges with them.
+fn validate_neighbours(
+    triangle_ids: &[TriangleId],
+    triangle_vertices: &HashMap<TriangleId, DelaunayTriangle>,
+    edges: &HashMap<[PointId; 2], SmallVec<[TriangleId; 2]>>,
+    violations: &mut Vec<Violation>,
+) {
+    let neighbours =
+        |id: TriangleId| triangle_vertices[&id].neighbours.into_iter().filter(|n| *n != -1);
+
+    triangle_ids.iter().for_each(|&triangle| {
+        neighbours(triangle).for_each(|neighbour| {
+            let Some(other) = triangle_vertices.get(&neighbour) else {
+                violations.push(Violation::AsymmetricNeighbours { triangle, neighbour });
+                return;
+            };
+
+            if !other.neighbours.contains(&triangle) {
+                violations.push(Violation::AsymmetricNeighbours { triangle, neighbour });
+            }
+
+            let shared_points = triangle_vertices[&triangle]
+                .vertices
+                .iter()
+                .filter(|v| other.vertices.contains(v))
+                .count();
+            if shared_points != 2 {
+                violations.push(Violation::DisconnectedNeighbours { triangle, neighbour });
+            }
+        });
+    });
+
+    let mut shared_edges = edges.iter().filter(|(_, t)| t.len() >= 2).collect::<Vec<_>>();
+    shared_edges.sort_unstable_by_key(|(edge, _)| **edge);
+
+    shared_edges.into_iter().for_each(|(_, edge_triangles)| {
+        edge_triangles.iter().for_each(|&triangle| {
+            edge_triangles
+                .iter()
+                .filter(|&&neighbour| neighbour != triangle)
+                .filter(|&&neighbour| !neighbours(triangle).any(|n| n == neighbour))
+                .for_each(|&neighbour| {
+                    violations.push(Violation::MissingNeighbour { triangle, neighbour });
+                });
+        });
+    });
+}
+
+// Checks that the triangle fans of the points consist of the triangles which contain them.
+fn validate_triangle_fans(
+    points: &Arena<Point>,
+    triangle_ids: &[TriangleId],
+    triangle_vertices: &HashMap<TriangleId, DelaunayTriangle>,
+    violations: &mut Vec<Violation>,
+) {
+    let triangle_fan = |point: PointId| {
+        points
+            .handle::<PointHandle>(point.into(), None)
+            .get()
+            .map(|p| p.triangle_fan().clone())
+            .unwrap_or_default()
+    };
+
+    (0..points.len() as PointId).for_each(|point| {
+        triangle_fan(point)
+            .into_iter()
+            .filter(|triangle| {
+                !triangle_vertices
+                    .get(triangle)
+                    .is_some_and(|t| t.vertices.contains(&point))
+            })
+            .for_each(|triangle| {
+                violations.push(Violation::InconsistentTriangleFan { point, triangle });
+            });
+    });
+
+    triangle_ids.iter().for_each(|&triangle| {
+        triangle_vertices[&triangle]
+            .vertices
+            .into_iter()
+            .filter(|&point| !triangle_fan(point).contains(&triangle))
+            .for_each(|point| {
+                violations.push(Violation::InconsistentTriangleFan { point, triangle });
+            });
+    });
+}
+
+// Checks that the interiors of the triangles don't overlap.
+//
+// The triangles don't overlap if one of their edges separates them.
+fn validate_overlaps(
+    points: &Arena<Point>,
+    triangle_ids: &[TriangleId],
+    triangle_vertices: &HashMap<TriangleId, DelaunayTriangle>,
+    violations: &mut Vec<Violation>,
+) {
+    // Counterclockwise coordinates of non-degenerate triangles with their bounding boxes.
+    let mut triangles = triangle_ids
+        .iter()
+        .filter_map(|&id| {
+            let mut coords = triangle_vertices[&id]
+                .vertices
+                .map(|v| predicates::coords(&points.handle::<PointHandle>(v.into(), None)));
+
+            let orientation = predicates::orient2d(coords[0], coords[1], coords[2]);
+            if orientation == 0.0 {
+                return None;
+            } else if orientation < 0.0 {
+                coords.swap(1, 2);
+            }
+
+            let min = [0, 1].map(|i| coords.iter().map(|c| c[i]).fold(f64::MAX, f64::min));
+            let max = [0, 1].map(|i| coords.iter().map(|c| c[i]).fold(f64::MIN, f64::max));
+
+            Some((id, coords, min, max))
+        })
+        .collect::<Vec<_>>();
+    triangles.sort_unstable_by(|a, b| a.2[0].total_cmp(&b.2[0]).then(a.0.cmp(&b.0)));
+
+    let is_separated_by = |t0: &[[f64; 2]; 3], t1: &[[f64; 2]; 3]| {
+        (0..3).any(|i| t1.iter().all(|&p| predicates::orient2d(t0[i], t0[(i + 1) % 3], p) <= 0.0))
+    };
+
+    // Sweep the triangles along the x axis.
+    triangles.iter().enumerate().for_each(|(i, (id0, t0, min0, max0))| {
+        triangles[i + 1..]
+            .iter()
+            .take_while(|(_, _, min1, _)| min1[0] < max0[0])
+            .filter(|(_, _, min1, max1)| min1[1] < max0[1] && min0[1] < max1[1])
+            .filter(|(_, t1, _, _)| !is_separated_by(t0, t1) && !is_separated_by(t1, t0))
+            .for_each(|(id1, _, _, _)| {
+                violations.push(Violation::OverlappingTriangles { triangles: [*id0, *id1] });
+            });
+    });
+}
+
+fn sorted_edge(p0: PointId, p1: PointId) -> [PointId; 2] {
+    [p0.min(p1), p0.max(p1)]
+}
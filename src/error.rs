@@ -0,0 +1,101 @@
+use crate::backend::DeviceFilter;
+use crate::font::Glyph;
+
+use std::{error, fmt, io};
+
+/// A specialized [`Result`](std::result::Result) type for the operations of the crate.
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// An error which can occur while parsing fonts or triangulating glyphs.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// The font data can't be parsed.
+    FontParsing(ttfp::FaceParsingError),
+    /// The font doesn't contain the glyph.
+    MissingGlyph(Glyph),
+    /// No OpenCL device matches the filter.
+    DeviceNotFound {
+        /// The filter which was used to select the device.
+        filter: DeviceFilter,
+        /// The number of devices which match the filter regardless of the device index.
+        matching_devices: usize,
+    },
+    /// An OpenCL program can't be built.
+    ProgramBuild(ocl::Error),
+    /// An OpenCL command failed.
+    OpenCl(ocl::Error),
+    /// An invalid argument was passed to the backend, e.g. a dimension which isn't
+    /// the power of two.
+    InvalidArgument(String),
+    /// The triangulation of the glyph can't be built.
+    Triangulation(String),
+    /// The SVG path data can't be parsed.
+    PathParsing {
+        /// The byte offset in the path data where the error occurred.
+        offset: usize,
+        /// The description of the error.
+        message: String,
+    },
+    /// An I/O operation failed, e.g. while storing a program binary in the cache.
+    Io(io::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::FontParsing(err) => write!(f, "Failed to parse the font: {err}"),
+            Self::MissingGlyph(glyph) => {
+                write!(f, "The font doesn't contain the glyph {}", glyph.0)
+            }
+            Self::DeviceNotFound { filter, matching_devices } => write!(
+                f,
+                "No OpenCL device matches {filter:?}: {matching_devices} matching devices found. \
+                You may need to install OpenCL drivers"
+            ),
+            Self::ProgramBuild(err) => write!(f, "Failed to build the OpenCL program: {err}"),
+            Self::OpenCl(err) => write!(f, "OpenCL error: {err}"),
+            Self::InvalidArgument(msg) => write!(f, "Invalid argument: {msg}"),
+            Self::Triangulation(msg) => write!(f, "Failed to triangulate: {msg}"),
+            Self::PathParsing { offset, message } => {
+                write!(f, "Failed to parse the path at {offset}: {message}")
+            }
+            Self::Io(err) => write!(f, "I/O error: {err}"),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::FontParsing(err) => Some(err),
+            Self::ProgramBuild(err) | Self::OpenCl(err) => Some(err),
+            Self::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<ttfp::FaceParsingError> for Error {
+    fn from(err: ttfp::FaceParsingError) -> Self {
+        Self::FontParsing(err)
+    }
+}
+
+impl From<ocl::Error> for Error {
+    fn from(err: ocl::Error) -> Self {
+        Self::OpenCl(err)
+    }
+}
+
+impl From<ocl::core::Error> for Error {
+    fn from(err: ocl::core::Error) -> Self {
+        Self::OpenCl(err.into())
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
@@ -0,0 +1,340 @@
+use super::{FillRule, Flatness, Font, Glyph, TriangulatedGlyph, TriangulationOptions};
+
+use crate::delaunay::{DelaunayTriangle, TriangleId};
+use crate::point::{Point, PointHandle};
+use crate::triangulator::Triangulator;
+
+use std::collections::{BTreeMap, HashMap};
+use std::mem;
+
+use arena_system::Handle;
+
+/// Statistics of lookups in [`GlyphCache`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CacheStats {
+    /// A number of lookups which found the glyph in the cache.
+    pub hits: u64,
+    /// A number of lookups which triangulated the glyph.
+    pub misses: u64,
+    /// A number of glyphs which were evicted to fit into the memory budget.
+    pub evictions: u64,
+}
+
+impl CacheStats {
+    /// Returns a fraction of lookups which found the glyph in the cache,
+    /// or `0.0` if there were no lookups.
+    pub fn hit_rate(&self) -> f32 {
+        let lookups = self.hits + self.misses;
+        if lookups == 0 {
+            return 0.0;
+        }
+
+        self.hits as f32 / lookups as f32
+    }
+}
+
+/// A font with a cache of triangulated glyphs.
+///
+/// Glyphs are cached by their ids, [`TriangulationOptions`] and variation coordinates
+/// of the font, so glyphs of different weights of a variable font don't clash.
+/// When the estimated memory of the cached glyphs exceeds the budget,
+/// the least recently used glyphs are evicted.
+///
+/// ```no_run
+/// use vdtfont::*;
+///
+/// # fn main() -> vdtfont::Result<()> {
+/// let mut triangulator = Triangulator::new(Backend::Cpu)?;
+/// let font_data = include_bytes!("/usr/share/fonts/truetype/open-sans/OpenSans-Regular.ttf");
+/// let font = Font::from_vec(font_data.to_vec())?;
+///
+/// // Keep up to 16 MiB of triangulated glyphs.
+/// let mut cache = GlyphCache::new(font, 16 << 20);
+/// cache.prewarm(&mut triangulator, 'a'..='z', &TriangulationOptions::default())?;
+///
+/// let glyph = cache.font().glyph('a');
+/// let triangulated_glyph = cache.triangulate_glyph(&mut triangulator, glyph)?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct GlyphCache {
+    font: Font,
+
+    memory_budget: usize,
+    memory_usage: usize,
+
+    entries: HashMap<CacheKey, CacheEntry>,
+    // Keys of the entries ordered by their last use.
+    recency: BTreeMap<u64, CacheKey>,
+    clock: u64,
+
+    stats: CacheStats,
+}
+
+impl GlyphCache {
+    /// Creates a new empty [`GlyphCache`] for `font` which keeps up to `memory_budget` bytes
+    /// of triangulated glyphs.
+    pub fn new(font: Font, memory_budget: usize) -> Self {
+        Self {
+            font,
+            memory_budget,
+            memory_usage: 0,
+            entries: HashMap::new(),
+            recency: BTreeMap::new(),
+            clock: 0,
+            stats: CacheStats::default(),
+        }
+    }
+
+    /// Returns the font.
+    pub fn font(&self) -> &Font {
+        &self.font
+    }
+
+    /// Returns the font mutably, e.g. to change its variation coordinates.
+    ///
+    /// Glyphs which were cached with other coordinates stay in the cache.
+    pub fn font_mut(&mut self) -> &mut Font {
+        &mut self.font
+    }
+
+    /// Converts [`GlyphCache`] into the font dropping the cached glyphs.
+    pub fn into_font(self) -> Font {
+        self.font
+    }
+
+    /// Returns the triangulated `glyph` with the default [`TriangulationOptions`].
+    ///
+    /// The glyph is triangulated with `triangulator` only if it isn't in the cache.
+    #[inline]
+    pub fn triangulate_glyph(
+        &mut self,
+        triangulator: &mut Triangulator,
+        glyph: Glyph,
+    ) -> crate::Result<&TriangulatedGlyph> {
+        self.triangulate_glyph_with_options(triangulator, glyph, &TriangulationOptions::default())
+    }
+
+    /// Returns the triangulated `glyph` with the given `options`.
+    ///
+    /// The glyph is triangulated with `triangulator` only if it isn't in the cache.
+    /// Returns [`Error::MissingGlyph`] if the font doesn't contain the glyph.
+    ///
+    /// [`Error::MissingGlyph`]: crate::error::Error::MissingGlyph
+    pub fn triangulate_glyph_with_options(
+        &mut self,
+        triangulator: &mut Triangulator,
+        glyph: Glyph,
+        options: &TriangulationOptions,
+    ) -> crate::Result<&TriangulatedGlyph> {
+        let key = self.key(glyph, options);
+        if self.entries.contains_key(&key) {
+            self.stats.hits += 1;
+        } else {
+            self.stats.misses += 1;
+            self.insert(triangulator, key.clone(), options)?;
+        }
+
+        self.touch(&key);
+        self.evict();
+
+        Ok(&self.entries[&key].glyph)
+    }
+
+    /// Triangulates the glyphs of `chars` which aren't in the cache yet.
+    ///
+    /// Prewarming stops at the first glyph which doesn't fit into the memory budget,
+    /// so it never evicts glyphs, and the rest of `chars` is skipped.
+    /// Prewarming doesn't change the statistics of the cache.
+    pub fn prewarm(
+        &mut self,
+        triangulator: &mut Triangulator,
+        chars: impl IntoIterator<Item = char>,
+        options: &TriangulationOptions,
+    ) -> crate::Result<()> {
+        for c in chars {
+            let key = self.key(self.font.glyph(c), options);
+            if !self.entries.contains_key(&key) {
+                self.insert(triangulator, key.clone(), options)?;
+
+                if self.memory_usage > self.memory_budget {
+                    if let Some(entry) = self.entries.remove(&key) {
+                        self.memory_usage -= entry.size;
+                    }
+
+                    break;
+                }
+            }
+
+            self.touch(&key);
+        }
+
+        Ok(())
+    }
+
+    /// Checks if the triangulated `glyph` with the given `options` is in the cache.
+    pub fn contains(&self, glyph: Glyph, options: &TriangulationOptions) -> bool {
+        self.entries.contains_key(&self.key(glyph, options))
+    }
+
+    /// Returns the statistics of lookups.
+    pub fn stats(&self) -> CacheStats {
+        self.stats
+    }
+
+    /// Resets the statistics of lookups.
+    pub fn reset_stats(&mut self) {
+        self.stats = CacheStats::default();
+    }
+
+    /// Returns the estimated memory in bytes which the cached glyphs take.
+    pub fn memory_usage(&self) -> usize {
+        self.memory_usage
+    }
+
+    /// Returns the memory budget in bytes.
+    pub fn memory_budget(&self) -> usize {
+        self.memory_budget
+    }
+
+    /// Sets the memory budget in bytes and evicts glyphs which don't fit into it.
+    pub fn set_memory_budget(&mut self, memory_budget: usize) {
+        self.memory_budget = memory_budget;
+        self.evict();
+    }
+
+    /// Returns a number of cached glyphs.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Checks if the cache has no glyphs.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Removes all glyphs from the cache.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.recency.clear();
+        self.memory_usage = 0;
+    }
+
+    fn key(&self, glyph: Glyph, options: &TriangulationOptions) -> CacheKey {
+        CacheKey {
+            glyph,
+            options: OptionsKey::new(options),
+            variations: self.font.variation_coordinates().iter().map(|c| c.get()).collect(),
+        }
+    }
+
+    // Triangulates the glyph of `key` and puts it into the cache.
+    fn insert(
+        &mut self,
+        triangulator: &mut Triangulator,
+        key: CacheKey,
+        options: &TriangulationOptions,
+    ) -> crate::Result<()> {
+        let outlined_glyph = self.font.outline_glyph_with_options(key.glyph, options)?;
+        let glyph = triangulator.triangulate_glyph_with_options(outlined_glyph, options)?;
+
+        let size = estimate_size(&glyph);
+        self.memory_usage += size;
+        self.entries.insert(key, CacheEntry { glyph, size, last_use: 0 });
+
+        Ok(())
+    }
+
+    // Marks the entry of `key` as the most recently used one.
+    fn touch(&mut self, key: &CacheKey) {
+        let Some(entry) = self.entries.get_mut(key) else {
+            return;
+        };
+
+        self.recency.remove(&entry.last_use);
+        self.clock += 1;
+        entry.last_use = self.clock;
+        self.recency.insert(self.clock, key.clone());
+    }
+
+    // Evicts the least recently used glyphs until the cache fits into the memory budget.
+    //
+    // The most recently used glyph is kept even if it exceeds the budget alone,
+    // because it is returned to the caller.
+    fn evict(&mut self) {
+        while self.memory_usage > self.memory_budget && self.entries.len() > 1 {
+            let Some((_, key)) = self.recency.pop_first() else {
+                break;
+            };
+
+            if let Some(entry) = self.entries.remove(&key) {
+                self.memory_usage -= entry.size;
+                self.stats.evictions += 1;
+            }
+        }
+    }
+}
+
+// Estimates the memory which the triangulated `glyph` takes.
+//
+// Triangle fans which don't fit into their inline storage are stored on the heap.
+fn estimate_size(glyph: &TriangulatedGlyph) -> usize {
+    let spilled_fans_size: usize = glyph
+        .points()
+        .handle_iter::<PointHandle>(None)
+        .filter_map(|p| {
+            let point = p.get().ok()?;
+            let fan = point.triangle_fan();
+            fan.spilled().then(|| fan.capacity() * mem::size_of::<TriangleId>())
+        })
+        .sum();
+
+    mem::size_of::<TriangulatedGlyph>()
+        + glyph.points().len() * mem::size_of::<Point>()
+        + glyph.triangles().len() * mem::size_of::<DelaunayTriangle>()
+        + spilled_fans_size
+}
+
+struct CacheEntry {
+    glyph: TriangulatedGlyph,
+    // The estimated memory which the glyph takes.
+    size: usize,
+    last_use: u64,
+}
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct CacheKey {
+    glyph: Glyph,
+    options: OptionsKey,
+    variations: Vec<i16>,
+}
+
+// Triangulation options whose numbers are compared bitwise, so they can be hashed.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+struct OptionsKey {
+    flatness: (u8, [u32; 2]),
+    fill_rule: FillRule,
+    min_image_size: usize,
+    max_image_size: usize,
+    min_point_distance: u32,
+}
+
+impl OptionsKey {
+    fn new(options: &TriangulationOptions) -> Self {
+        let flatness = match options.flatness {
+            Flatness::FontUnits(distance) => (0, [distance.to_bits(), 0]),
+            Flatness::Em(distance) => (1, [distance.to_bits(), 0]),
+            Flatness::Pixels { distance, pixels_per_em } => {
+                (2, [distance.to_bits(), pixels_per_em.to_bits()])
+            }
+        };
+
+        Self {
+            flatness,
+            fill_rule: options.fill_rule,
+            min_image_size: options.min_image_size,
+            max_image_size: options.max_image_size,
+            min_point_distance: options.min_point_distance.to_bits(),
+        }
+    }
+}
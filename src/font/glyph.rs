@@ -1,12 +1,14 @@
-use crate::delaunay::DelaunayTriangle;
-use crate::Point;
+use crate::delaunay::{self, DelaunayTriangle, DelaunayTriangleHandle, ValidationReport};
+use crate::mesh::Mesh;
+use crate::point::{Point, PointHandle};
+use crate::voronoi::{DistanceField, MultiDistanceField};
 
 use std::convert;
 
-use arena_system::Arena;
+use arena_system::{Arena, Handle};
 
 /// A glyph with id which correspondes to one of the characters in the font.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Glyph(pub u16);
 
 impl convert::From<u16> for Glyph {
@@ -33,12 +35,90 @@ impl convert::From<Glyph> for ttfp::GlyphId {
     }
 }
 
+/// A placement of a glyph in the image in which the glyph is triangulated.
+///
+/// The image has the y axis pointing down, while font units have it pointing up
+/// with the baseline at the origin. A point `(x, y)` in font units is placed at
+/// `(x * scale + offset[0], offset[1] - y * scale)` in the image.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GlyphPlacement {
+    bounds: ttfp::Rect,
+    units_per_em: f32,
+
+    scale: f32,
+    offset: [f32; 2],
+
+    hor_advance: f32,
+    hor_side_bearing: f32,
+}
+
+impl GlyphPlacement {
+    /// Creates a new [`GlyphPlacement`] without horizontal metrics.
+    pub fn new(bounds: ttfp::Rect, units_per_em: f32, scale: f32, offset: [f32; 2]) -> Self {
+        Self { bounds, units_per_em, scale, offset, hor_advance: 0.0, hor_side_bearing: 0.0 }
+    }
+
+    /// Sets the horizontal advance and side bearing of the glyph in font units.
+    pub fn with_hor_metrics(mut self, hor_advance: f32, hor_side_bearing: f32) -> Self {
+        self.hor_advance = hor_advance;
+        self.hor_side_bearing = hor_side_bearing;
+        self
+    }
+
+    /// Returns bounds of the glyph in font units.
+    pub fn bounds(&self) -> ttfp::Rect {
+        self.bounds
+    }
+
+    /// Returns a number of font units per em.
+    pub fn units_per_em(&self) -> f32 {
+        self.units_per_em
+    }
+
+    /// Returns a horizontal advance of the glyph in font units.
+    pub fn hor_advance(&self) -> f32 {
+        self.hor_advance
+    }
+
+    /// Returns a horizontal side bearing of the glyph in font units.
+    pub fn hor_side_bearing(&self) -> f32 {
+        self.hor_side_bearing
+    }
+
+    /// Returns a number of image pixels per font unit.
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    /// Returns an offset of the origin of font units in the image.
+    pub fn offset(&self) -> [f32; 2] {
+        self.offset
+    }
+
+    /// Converts a point in the image into font units.
+    pub fn to_font_units(&self, p: [f32; 2]) -> [f32; 2] {
+        [(p[0] - self.offset[0]) / self.scale, (self.offset[1] - p[1]) / self.scale]
+    }
+
+    /// Converts a point in font units into the image.
+    pub fn from_font_units(&self, p: [f32; 2]) -> [f32; 2] {
+        [p[0] * self.scale + self.offset[0], self.offset[1] - p[1] * self.scale]
+    }
+
+    // Transforms a mesh in the image into font units scaled by `factor`.
+    fn transform_mesh(&self, mesh: &mut Mesh, factor: f32) {
+        let scale = factor / self.scale;
+        mesh.transform([scale, -scale], [-self.offset[0] * scale, self.offset[1] * scale]);
+    }
+}
+
 /// An outlined glyph.
 pub struct OutlinedGlyph {
     glyph: Glyph,
     dim: usize,
 
     bounds: ttfp::Rect,
+    placement: GlyphPlacement,
     points: Arena<Point>,
 }
 
@@ -58,11 +138,21 @@ impl OutlinedGlyph {
         self.bounds
     }
 
+    /// Returns the placement of the glyph in the image.
+    pub fn placement(&self) -> GlyphPlacement {
+        self.placement
+    }
+
     /// Returns points which the outline of the glyph consists of.
     pub fn points(&self) -> &Arena<Point> {
         &self.points
     }
 
+    /// Checks if the glyph has no outline, e.g. space or control characters.
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
     /// Converts [`OutlinedGlyph`] into raw parts: a glyph, a dimension, bounds and points.
     pub fn into_raw_parts(self) -> (Glyph, usize, ttfp::Rect, Arena<Point>) {
         (self.glyph, self.dim, self.bounds, self.points)
@@ -71,8 +161,21 @@ impl OutlinedGlyph {
     /// Creates a new [`OutlinedGlyph`].
     ///
     /// The validity of the given parameters is ensured by [`Font`].
-    pub(super) fn new(glyph: Glyph, dim: usize, bounds: ttfp::Rect, points: Arena<Point>) -> Self {
-        Self { glyph, dim, bounds, points }
+    pub(super) fn new(
+        glyph: Glyph,
+        dim: usize,
+        bounds: ttfp::Rect,
+        placement: GlyphPlacement,
+        points: Arena<Point>,
+    ) -> Self {
+        Self { glyph, dim, bounds, placement, points }
+    }
+
+    /// Creates a new [`OutlinedGlyph`] without an outline.
+    pub(super) fn empty(glyph: Glyph, dim: usize, placement: GlyphPlacement) -> Self {
+        let bounds = ttfp::Rect { x_min: 0, y_min: 0, x_max: 0, y_max: 0 };
+
+        Self::new(glyph, dim, bounds, placement, Vec::new().into())
     }
 }
 
@@ -81,6 +184,8 @@ pub struct TriangulatedGlyph {
     glyph: Glyph,
     dim: usize,
 
+    placement: GlyphPlacement,
+
     points: Arena<Point>,
     triangles: Arena<DelaunayTriangle>,
 }
@@ -96,6 +201,16 @@ impl TriangulatedGlyph {
         self.dim
     }
 
+    /// Returns the placement of the glyph in the image.
+    pub fn placement(&self) -> GlyphPlacement {
+        self.placement
+    }
+
+    /// Returns bounds of the glyph in font units.
+    pub fn bounds(&self) -> ttfp::Rect {
+        self.placement.bounds()
+    }
+
     /// Returns points which the outline of the glyph consists of.
     pub fn points(&self) -> &Arena<Point> {
         &self.points
@@ -106,6 +221,87 @@ impl TriangulatedGlyph {
         &self.triangles
     }
 
+    /// Checks if the glyph has no triangles, e.g. space or control characters.
+    pub fn is_empty(&self) -> bool {
+        self.triangles.is_empty()
+    }
+
+    /// Checks the invariants of the triangulation of the glyph.
+    ///
+    /// Only the triangles inside the glyph are kept, so their neighbours and triangle fans
+    /// of the points aren't checked.
+    pub fn validate(&self) -> ValidationReport {
+        delaunay::validate(&self.points, &self.triangles, false)
+    }
+
+    /// Converts the triangulation into a compact [`Mesh`] in the image of the glyph.
+    ///
+    /// Points which aren't used by any triangle, including the bounding points,
+    /// are stripped and the remaining ones are renumbered in the order of their first use.
+    pub fn to_mesh(&self) -> Mesh {
+        let mut new_indices = vec![u32::MAX; self.points.len()];
+        let mut positions = vec![];
+        let mut indices = vec![];
+
+        self.triangles
+            .handle_iter::<DelaunayTriangleHandle>(&self.points)
+            .filter_map(|t| t.get().ok().map(|t| t.vertices))
+            .filter(|vertices| {
+                vertices.iter().all(|&v| {
+                    let point = self.points.handle::<PointHandle>(v.into(), None);
+                    point.get().map(|p| !p.is_bounding()).unwrap_or(false)
+                })
+            })
+            .for_each(|vertices| {
+                vertices.into_iter().for_each(|v| {
+                    let new_index = &mut new_indices[v as usize];
+                    if *new_index == u32::MAX {
+                        let point = self.points.handle::<PointHandle>(v.into(), None);
+                        *new_index = positions.len() as u32;
+                        positions.push([point.x(), point.y()]);
+                    }
+
+                    indices.push(*new_index);
+                });
+            });
+
+        Mesh::new(positions, indices)
+    }
+
+    /// Converts the triangulation into a compact [`Mesh`] in font units.
+    ///
+    /// The y axis points up and the origin is on the baseline.
+    pub fn to_mesh_in_font_units(&self) -> Mesh {
+        let mut mesh = self.to_mesh();
+        self.placement.transform_mesh(&mut mesh, 1.0);
+
+        mesh
+    }
+
+    /// Converts the triangulation into a compact [`Mesh`] in the em square,
+    /// in which 1.0 corresponds to one em.
+    ///
+    /// The y axis points up and the origin is on the baseline.
+    pub fn to_em_mesh(&self) -> Mesh {
+        let mut mesh = self.to_mesh();
+        self.placement
+            .transform_mesh(&mut mesh, 1.0 / self.placement.units_per_em());
+
+        mesh
+    }
+
+    /// Converts the triangulation into a compact [`Mesh`] for the text
+    /// with `pixels_per_em` pixels per em.
+    ///
+    /// The y axis points up and the origin is on the baseline.
+    pub fn to_mesh_at_pixel_size(&self, pixels_per_em: f32) -> Mesh {
+        let mut mesh = self.to_mesh();
+        self.placement
+            .transform_mesh(&mut mesh, pixels_per_em / self.placement.units_per_em());
+
+        mesh
+    }
+
     /// Converts [`TriangulatedGlyph`] into raw parts: a glyph, a dimension, points and triangles
     pub fn into_raw_parts(self) -> (Glyph, usize, Arena<Point>, Arena<DelaunayTriangle>) {
         (self.glyph, self.dim, self.points, self.triangles)
@@ -113,15 +309,146 @@ impl TriangulatedGlyph {
 
     /// Creates a new [`TriangulatedGlyph`].
     ///
-    /// The validity of the given parameters is ensured by [`Font`].
-    /// 
-    /// [`Font`]: crate::font::Font
-    pub(super) fn new(
+    /// The validity of the given parameters is ensured by [`Triangulator`].
+    ///
+    /// [`Triangulator`]: crate::triangulator::Triangulator
+    pub(crate) fn new(
         glyph: Glyph,
         dim: usize,
+        placement: GlyphPlacement,
         points: Arena<Point>,
         triangles: Arena<DelaunayTriangle>,
     ) -> Self {
-        Self { glyph, dim, points, triangles }
+        Self { glyph, dim, placement, points, triangles }
+    }
+}
+
+/// A signed distance field of a glyph.
+///
+/// The field extends [`DistanceFieldGlyph::spread`] pixels beyond the outline, so glyphs
+/// can be rendered at any scale by thresholding the interpolated distances.
+pub struct DistanceFieldGlyph {
+    glyph: Glyph,
+
+    placement: GlyphPlacement,
+    spread: f32,
+
+    field: DistanceField,
+}
+
+impl DistanceFieldGlyph {
+    /// Returns a glyph.
+    pub fn glyph(&self) -> Glyph {
+        self.glyph
+    }
+
+    /// Returns the placement of the glyph in the field.
+    pub fn placement(&self) -> GlyphPlacement {
+        self.placement
+    }
+
+    /// Returns the distance in pixels which the field extends beyond the outline.
+    pub fn spread(&self) -> f32 {
+        self.spread
+    }
+
+    /// Returns the signed distance field.
+    pub fn field(&self) -> &DistanceField {
+        &self.field
+    }
+
+    /// Checks if the glyph has no outline, e.g. space or control characters.
+    pub fn is_empty(&self) -> bool {
+        self.field.is_empty()
+    }
+
+    /// Converts the field into a grayscale image in which distances from `-spread`
+    /// to `spread` are mapped onto the whole range, see [`DistanceField::to_image`].
+    pub fn to_image(&self) -> image::GrayImage {
+        self.field.to_image(self.spread)
+    }
+
+    /// Converts [`DistanceFieldGlyph`] into the distance field.
+    pub fn into_field(self) -> DistanceField {
+        self.field
+    }
+
+    /// Creates a new [`DistanceFieldGlyph`].
+    ///
+    /// The validity of the given parameters is ensured by [`Font`].
+    ///
+    /// [`Font`]: crate::font::Font
+    pub(super) fn new(
+        glyph: Glyph,
+        placement: GlyphPlacement,
+        spread: f32,
+        field: DistanceField,
+    ) -> Self {
+        Self { glyph, placement, spread, field }
+    }
+}
+
+/// A multi-channel signed distance field of a glyph.
+///
+/// Unlike [`DistanceFieldGlyph`], it preserves sharp corners of the glyph when it is
+/// rendered by thresholding the median of the interpolated channels.
+pub struct MultiDistanceFieldGlyph {
+    glyph: Glyph,
+
+    placement: GlyphPlacement,
+    spread: f32,
+
+    field: MultiDistanceField,
+}
+
+impl MultiDistanceFieldGlyph {
+    /// Returns a glyph.
+    pub fn glyph(&self) -> Glyph {
+        self.glyph
+    }
+
+    /// Returns the placement of the glyph in the field.
+    pub fn placement(&self) -> GlyphPlacement {
+        self.placement
+    }
+
+    /// Returns the distance in pixels which the field extends beyond the outline.
+    pub fn spread(&self) -> f32 {
+        self.spread
+    }
+
+    /// Returns the multi-channel signed distance field.
+    pub fn field(&self) -> &MultiDistanceField {
+        &self.field
+    }
+
+    /// Checks if the glyph has no outline, e.g. space or control characters.
+    pub fn is_empty(&self) -> bool {
+        self.field.is_empty()
+    }
+
+    /// Converts the field into an RGB image in which distances from `-spread`
+    /// to `spread` are mapped onto the whole range, see [`MultiDistanceField::to_image`].
+    pub fn to_image(&self) -> image::RgbImage {
+        self.field.to_image(self.spread)
+    }
+
+    /// Converts [`MultiDistanceFieldGlyph`] into the distance field.
+    pub fn into_field(self) -> MultiDistanceField {
+        self.field
+    }
+
+    /// Creates a new [`MultiDistanceFieldGlyph`].
+    ///
+    /// The validity of the given parameters is ensured by [`Font`].
+    ///
+    /// [`Font`]: crate::font::Font
+    pub(super) fn new(
+        glyph: Glyph,
+        placement: GlyphPlacement,
+        spread: f32,
+        field: MultiDistanceField,
+    ) -> Self {
+        Self { glyph, placement, spread, field }
     }
 }
@@ -1,35 +1,52 @@
-mod curve;
+mod cache;
 pub mod glyph;
+mod options;
 mod outliner;
+#[cfg(feature = "shaping")]
+mod shaping;
 
-pub use glyph::{Glyph, OutlinedGlyph, TriangulatedGlyph};
-
-use crate::delaunay::{
-    Delaunay, DelaunayFactory, DelaunayTriangle, DelaunayTriangleHandle, Visibility,
+pub use cache::{CacheStats, GlyphCache};
+pub use glyph::{
+    DistanceFieldGlyph, Glyph, GlyphPlacement, MultiDistanceFieldGlyph, OutlinedGlyph,
+    TriangulatedGlyph,
 };
-use crate::point::{Point, PointHandle, PointId};
-use crate::voronoi::VoronoiImageFactory;
+pub use options::{FillRule, Flatness, TriangulationOptions};
+#[cfg(feature = "shaping")]
+pub use shaping::{Direction, ShapedGlyph, ShapingOptions};
+
+use crate::error::Error;
+use crate::path::PathBuilder;
+use crate::point::{Point, PointId};
+use crate::triangulator::Triangulator;
+use crate::voronoi::{self, DistanceField, MultiDistanceField};
 
-use arena_system::{Arena, Handle};
+use arena_system::Arena;
 use ocl::prm::Float2;
-use ttfp::AsFaceRef;
+use ttfp::{AsFaceRef, FaceMut};
 
-const MIN_GLYPH_HEIGHT: usize = 64;
-const MAX_GLYPH_HEIGHT: usize = 2048;
-const MIN_POINT_DISTANCE: f32 = 4.0;
+pub(crate) const MAX_GLYPH_HEIGHT: usize = 2048;
+// The minimal dimension of the Voronoi image, which is the side of the OpenCL work groups.
+pub(crate) const MIN_IMAGE_SIZE: usize = 8;
+
+// The flatness of curves in pixels when glyphs are outlined for distance fields.
+const DISTANCE_FIELD_FLATNESS: f32 = 0.1;
+// The maximal distance in pixels between consecutive sites of the distance field.
+// The distance to the outline is measured near the nearest site, so the error doesn't
+// exceed a half of the distance.
+const DISTANCE_FIELD_SITE_SPACING: f32 = 2.0;
 
 /// A preparsed font data.
+///
+/// Glyphs of the font are triangulated by [`Triangulator`]
+/// which can be shared between several fonts.
 pub struct Font {
     subtables: ttfp::PreParsedSubtables<'static, ttfp::OwnedFace>,
-
-    voronoi_image_factory: VoronoiImageFactory,
-    delaunay_factory: DelaunayFactory,
 }
 
 impl Font {
     /// Creates a new [`Font`] from bytes.
     #[inline]
-    pub fn from_vec(data: Vec<u8>) -> anyhow::Result<Self> {
+    pub fn from_vec(data: Vec<u8>) -> crate::Result<Self> {
         Self::from_vec_and_index(data, 0)
     }
 
@@ -37,20 +54,9 @@ impl Font {
     ///
     /// You can set index for font collections. For simple ttf fonts set index to 0.
     #[inline]
-    pub fn from_vec_and_index(data: Vec<u8>, index: u32) -> anyhow::Result<Self> {
-        let platform = ocl::Platform::default();
-        let device = *ocl::Device::list(platform, Some(ocl::DeviceType::GPU))
-            .expect("OpenCL error occured. You may need to install OpenCL drivers")
-            .get(0)
-            .expect("No GPU was found. You may need to install OpenCL drivers");
-        let context = ocl::Context::builder().platform(platform).devices(device).build()?;
-        let queue =
-            ocl::Queue::new(&context, device, Some(ocl::CommandQueueProperties::PROFILING_ENABLE))?;
-
+    pub fn from_vec_and_index(data: Vec<u8>, index: u32) -> crate::Result<Self> {
         Ok(Self {
             subtables: ttfp::PreParsedSubtables::from(ttfp::OwnedFace::from_vec(data, index)?),
-            voronoi_image_factory: VoronoiImageFactory::new(queue.clone(), MAX_GLYPH_HEIGHT)?,
-            delaunay_factory: DelaunayFactory::new(queue)?,
         })
     }
 
@@ -84,43 +90,41 @@ impl Font {
     }
 
     /// Returns a horizontal advance of the glyph.
+    ///
+    /// Returns `None` if the font doesn't contain the glyph.
     #[inline]
-    pub fn hor_advance(&self, glyph: Glyph) -> f32 {
-        self.subtables
-            .as_face_ref()
-            .glyph_hor_advance(glyph.into())
-            .expect("Invalid glyph_hor_advance")
-            .into()
+    pub fn hor_advance(&self, glyph: Glyph) -> Option<f32> {
+        self.subtables.as_face_ref().glyph_hor_advance(glyph.into()).map(f32::from)
     }
 
     /// Returns a horizontal side bearing of the glyph.
+    ///
+    /// Returns `None` if the font doesn't contain the glyph.
     #[inline]
-    pub fn hor_side_bearing(&self, glyph: Glyph) -> f32 {
+    pub fn hor_side_bearing(&self, glyph: Glyph) -> Option<f32> {
         self.subtables
             .as_face_ref()
             .glyph_hor_side_bearing(glyph.into())
-            .expect("Invalid glyph_hor_side_bearing")
-            .into()
+            .map(f32::from)
     }
 
     /// Returns a vertical advance of the glyph.
+    ///
+    /// Returns `None` if the font doesn't contain the glyph or has no vertical metrics.
     #[inline]
-    pub fn ver_advance(&self, glyph: Glyph) -> f32 {
-        self.subtables
-            .as_face_ref()
-            .glyph_ver_advance(glyph.into())
-            .expect("Invalid glyph_ver_advance")
-            .into()
+    pub fn ver_advance(&self, glyph: Glyph) -> Option<f32> {
+        self.subtables.as_face_ref().glyph_ver_advance(glyph.into()).map(f32::from)
     }
 
     /// Returns a vertical side bearing of the glyph.
+    ///
+    /// Returns `None` if the font doesn't contain the glyph or has no vertical metrics.
     #[inline]
-    pub fn ver_side_bearing(&self, glyph: Glyph) -> f32 {
+    pub fn ver_side_bearing(&self, glyph: Glyph) -> Option<f32> {
         self.subtables
             .as_face_ref()
             .glyph_ver_side_bearing(glyph.into())
-            .expect("Invalid glyph_ver_side_bearing")
-            .into()
+            .map(f32::from)
     }
 
     /// Returns a horizontal kerning of the glyph.
@@ -132,6 +136,24 @@ impl Font {
             .unwrap_or_default()
     }
 
+    /// Sets the coordinate of the variation `axis` of a variable font, e.g. `wght`.
+    ///
+    /// Glyphs which are outlined afterwards use the new coordinates.
+    /// Returns [`Error::InvalidArgument`] if the font doesn't have the axis.
+    pub fn set_variation(&mut self, axis: ttfp::Tag, value: f32) -> crate::Result<()> {
+        self.subtables.set_variation(axis, value).ok_or_else(|| {
+            Error::InvalidArgument(format!("The font doesn't have the variation axis {axis}"))
+        })
+    }
+
+    /// Returns the normalized coordinates of the variation axes.
+    ///
+    /// The coordinates are empty for fonts without variations.
+    #[inline]
+    pub fn variation_coordinates(&self) -> &[ttfp::NormalizedCoordinate] {
+        self.subtables.as_face_ref().variation_coordinates()
+    }
+
     /// Returns a number of glyphs in the font.
     #[inline]
     pub fn glyph_count(&self) -> usize {
@@ -145,27 +167,73 @@ impl Font {
         Glyph(index)
     }
 
-    /// Outlines the given `glyph`.
-    pub fn outline_glyph(&self, glyph: Glyph) -> OutlinedGlyph {
-        let mut outliner = outliner::Outliner::new();
+    /// Outlines the given `glyph` with the default [`TriangulationOptions`].
+    ///
+    /// Returns [`Error::MissingGlyph`] if the font doesn't contain the glyph.
+    #[inline]
+    pub fn outline_glyph(&self, glyph: Glyph) -> crate::Result<OutlinedGlyph> {
+        self.outline_glyph_with_options(glyph, &TriangulationOptions::default())
+    }
 
-        // Outline a glyph.
-        let rect = self
-            .subtables
-            .as_face_ref()
-            .outline_glyph(glyph.into(), &mut outliner)
-            .unwrap();
+    /// Outlines the given `glyph` with the given `options`.
+    ///
+    /// Returns [`Error::MissingGlyph`] if the font doesn't contain the glyph.
+    pub fn outline_glyph_with_options(
+        &self,
+        glyph: Glyph,
+        options: &TriangulationOptions,
+    ) -> crate::Result<OutlinedGlyph> {
+        options.validate()?;
+
+        if glyph.0 as usize >= self.glyph_count() {
+            return Err(Error::MissingGlyph(glyph));
+        }
+
+        let units_per_em = self.units_per_em().unwrap_or(1000.0);
+        let height: f32 = self.ascender() - self.descender();
+        let mut outliner = outliner::Outliner::new(options.flatness.to_font_units(units_per_em));
+
+        // Outline a glyph. Glyphs without an outline, e.g. space, are empty.
+        let outline_rect = self.subtables.as_face_ref().outline_glyph(glyph.into(), &mut outliner);
+
+        // An outline with less than three points doesn't enclose any area.
+        let Some(rect) = outline_rect.filter(|_| outliner.points.len() >= 3) else {
+            let dim = options.min_image_size;
+            let bounds = ttfp::Rect { x_min: 0, y_min: 0, x_max: 0, y_max: 0 };
+            // Fonts with zero height would produce an infinite scale.
+            let scale = if height > 0.0 { dim as f32 / height } else { 1.0 };
+            let placement = self.placement(glyph, bounds, units_per_em, scale, [0.0; 2]);
+
+            return Ok(OutlinedGlyph::empty(glyph, dim, placement));
+        };
+
+        if height <= 0.0 {
+            return Err(Error::InvalidArgument(format!(
+                "The font height {height} must be positive to outline the glyph {}",
+                glyph.0
+            )));
+        }
+
+        // The outliner skips zero distances, so the shortest one isn't found
+        // only if all points of the outline coincide.
+        if outliner.shortest_distance == f32::MAX {
+            return Err(Error::Triangulation(format!(
+                "All points of the outline of the glyph {} coincide",
+                glyph.0
+            )));
+        }
 
         // Find a dimension which is the power of two,
-        // in which the shortest distance between the points is >= `MIN_POINT_DISTANCE`
-        // and `MIN_GLYPH_HEIGHT` < `dim` < `MIN_GLYPH_HEIGHT`.
-        let dim = nearest_power_of_two(
-            (MAX_GLYPH_HEIGHT as f32 * MIN_POINT_DISTANCE / outliner.shortest_distance) as usize,
-        )
-        .clamp(MIN_GLYPH_HEIGHT, MAX_GLYPH_HEIGHT);
+        // in which the shortest distance between the points is >= `min_point_distance`
+        // and `min_image_size` <= `dim` <= `max_image_size`.
+        // The limits are powers of two, so the clamped scale can't be rounded beyond them.
+        let (min_dim, max_dim) = (options.min_image_size, options.max_image_size);
+        let scale = (MAX_GLYPH_HEIGHT as f32 * options.min_point_distance
+            / outliner.shortest_distance)
+            .clamp(min_dim as f32, max_dim as f32);
+        let dim = nearest_power_of_two(scale as usize);
 
         // Scale the glyph.
-        let height: f32 = self.ascender() - self.descender();
         let h_factor = dim as f32 / height;
         let v_factor = dim as f32 / height;
 
@@ -185,103 +253,290 @@ impl Font {
 
         let points: Arena<Point> = outliner.points.into();
 
-        OutlinedGlyph::new(glyph, dim, bounds, points)
+        let offset = [2.0 - bounds.x_min as f32, bounds.y_max as f32 + 2.0];
+        let placement = self.placement(glyph, rect, units_per_em, h_factor, offset);
+
+        Ok(OutlinedGlyph::new(glyph, dim, bounds, placement, points))
     }
 
-    /// Triangulates the given `outlined_glyph`.
+    /// Triangulates the given `outlined_glyph` with `triangulator`.
+    #[inline]
     pub fn triangulate_glyph(
-        &mut self,
+        &self,
+        triangulator: &mut Triangulator,
         outlined_glyph: OutlinedGlyph,
-    ) -> anyhow::Result<TriangulatedGlyph> {
-        let (glyph, dim, _, points) = outlined_glyph.into_raw_parts();
-        // Triangulate the points in the outline of the glyph.
-        let voronoi_image = self.voronoi_image_factory.construct_borrowed(points, dim)?;
-        let mut delaunay = self.delaunay_factory.construct(&voronoi_image)?;
-
-        // Insert constraint edges which are missing in the triangulation.
-        self.insert_constraint_edges(&mut delaunay);
-
-        // Hide triangles which are outside the contour.
-        let bounding_point_ids: [PointId; 4] = delaunay.bounds().into();
-        let bounding_triangle = delaunay
-            .points()
-            .handle::<PointHandle>(bounding_point_ids[0].into(), Some(delaunay.triangles()))
-            .triangle_fan()[0];
-        self.remove_excess_triangles(bounding_triangle, Visibility::Invisible);
-
-        let (dim, points, triangles, _) = delaunay.into_raw_parts();
-
-        // Remove the invisible triangles.
-        let triangles = triangles
-            .handle_iter::<DelaunayTriangleHandle>(&points)
-            .filter(|t| t.get().is_ok())
-            //.map(|t| { t.set_visibiity(Visibility::Visible); t })
-            .filter(|t| matches!(t.visibility(), Visibility::Visible))
-            .map(|t| *t.get().unwrap())
-            .collect::<Arena<DelaunayTriangle>>();
-
-        Ok(TriangulatedGlyph::new(glyph, dim, points, triangles))
+    ) -> crate::Result<TriangulatedGlyph> {
+        triangulator.triangulate_glyph(outlined_glyph)
     }
 
-    // Inserts missing edges into the given `delaunay` triangulation.
-    fn insert_constraint_edges(&self, delaunay: &mut Delaunay) {
-        let mut edges: Vec<[i64; 2]> = vec![];
-        delaunay
-            .points()
-            .handle_iter::<PointHandle>(Some(delaunay.triangles()))
-            .for_each(|p| {
-                let pp = p.previous_in_outline();
-                if !p.is_connected_to(pp)
-                    && !p.index().is_invalid()
-                    && !pp.index().is_invalid()
-                    && !p.triangle_fan().is_empty()
-                    && !pp.triangle_fan().is_empty()
-                {
-                    edges.push([p.index().into(), pp.index().into()]);
-                }
-            });
-
-        edges.into_iter().for_each(|e| {
-            delaunay.insert_edge(e);
-        });
+    /// Triangulates the given `outlined_glyph` with `triangulator` and the given `options`.
+    #[inline]
+    pub fn triangulate_glyph_with_options(
+        &self,
+        triangulator: &mut Triangulator,
+        outlined_glyph: OutlinedGlyph,
+        options: &TriangulationOptions,
+    ) -> crate::Result<TriangulatedGlyph> {
+        triangulator.triangulate_glyph_with_options(outlined_glyph, options)
     }
 
-    // Recursively hides triangles which are outside the contour.
-    #[allow(clippy::only_used_in_recursion)]
-    fn remove_excess_triangles(
+    /// Triangulates the given `outlined_glyphs` together with `triangulator`,
+    /// see [`Triangulator::triangulate_glyphs_with_options`].
+    #[inline]
+    pub fn triangulate_glyphs(
         &self,
-        starting_triangle: DelaunayTriangleHandle,
-        visibility: Visibility,
-    ) {
-        if !matches!(starting_triangle.visibility(), Visibility::Unknown) {
-            return;
+        triangulator: &mut Triangulator,
+        outlined_glyphs: &[OutlinedGlyph],
+    ) -> crate::Result<Vec<TriangulatedGlyph>> {
+        triangulator.triangulate_glyphs(outlined_glyphs)
+    }
+
+    /// Triangulates the given `outlined_glyphs` together with `triangulator`
+    /// and the given `options`, see [`Triangulator::triangulate_glyphs_with_options`].
+    #[inline]
+    pub fn triangulate_glyphs_with_options(
+        &self,
+        triangulator: &mut Triangulator,
+        outlined_glyphs: &[OutlinedGlyph],
+        options: &TriangulationOptions,
+    ) -> crate::Result<Vec<TriangulatedGlyph>> {
+        triangulator.triangulate_glyphs_with_options(outlined_glyphs, options)
+    }
+
+    /// Computes the signed distance field of the given `glyph` with `triangulator`.
+    ///
+    /// The glyph is rendered with `pixels_per_em` pixels per em and the field extends
+    /// `spread` pixels beyond its bounds. The distances are measured to the outline
+    /// and are positive inside the glyph.
+    ///
+    /// Returns [`Error::MissingGlyph`] if the font doesn't contain the glyph.
+    pub fn glyph_sdf(
+        &self,
+        triangulator: &mut Triangulator,
+        glyph: Glyph,
+        pixels_per_em: f32,
+        spread: f32,
+    ) -> crate::Result<DistanceFieldGlyph> {
+        let max_dim = triangulator.max_image_size();
+        let Some(outline) = self.outline_distance_field(glyph, pixels_per_em, spread, max_dim)?
+        else {
+            let placement = self.empty_placement(glyph, pixels_per_em);
+            let field = DistanceField::new(0, 0, Vec::new());
+
+            return Ok(DistanceFieldGlyph::new(glyph, placement, spread, field));
+        };
+
+        let [width, height] = outline.size;
+        let field = triangulator
+            .draw_voronoi_image(outline.sites.into(), outline.dim)?
+            .to_distance_field(FillRule::NonZero)?
+            .crop(width, height);
+
+        Ok(DistanceFieldGlyph::new(glyph, outline.placement, spread, field))
+    }
+
+    /// Computes the multi-channel signed distance field of the given `glyph` with `triangulator`.
+    ///
+    /// Edges of the outline are colored, so the edges meeting at sharp corners are stored
+    /// in different channels. The median of the channels is the signed distance to
+    /// the outline with sharp corners. The parameters are the same as for [`Font::glyph_sdf`].
+    ///
+    /// Returns [`Error::MissingGlyph`] if the font doesn't contain the glyph.
+    pub fn glyph_msdf(
+        &self,
+        triangulator: &mut Triangulator,
+        glyph: Glyph,
+        pixels_per_em: f32,
+        spread: f32,
+    ) -> crate::Result<MultiDistanceFieldGlyph> {
+        let max_dim = triangulator.max_image_size();
+        let Some(outline) = self.outline_distance_field(glyph, pixels_per_em, spread, max_dim)?
+        else {
+            let placement = self.empty_placement(glyph, pixels_per_em);
+            let field = MultiDistanceField::new(0, 0, Vec::new());
+
+            return Ok(MultiDistanceFieldGlyph::new(glyph, placement, spread, field));
+        };
+
+        // Every channel is drawn in its own Voronoi image.
+        let channels = voronoi::color_edges(&outline.sites, &outline.corners);
+        let mut pseudo_distances: [Vec<f32>; 3] = Default::default();
+        for (sites, pseudo_distances) in channels.into_iter().zip(&mut pseudo_distances) {
+            *pseudo_distances = triangulator
+                .draw_voronoi_image(sites.into(), outline.dim)?
+                .to_pseudo_distances()?;
         }
 
-        starting_triangle.set_visibiity(visibility);
-
-        starting_triangle.neighbours().into_iter().for_each(|n| {
-            let has_contour_edge = starting_triangle.shared_edge_with(&n).is_contour();
-            // When cross the contour edge, invert the `visibility` parameter.
-            let visibility = if has_contour_edge {
-                match visibility {
-                    Visibility::Invisible => Visibility::Visible,
-                    Visibility::Visible => Visibility::Invisible,
-                    _ => unreachable!(),
-                }
-            } else {
-                visibility
-            };
+        let field = triangulator
+            .draw_voronoi_image(outline.sites.into(), outline.dim)?
+            .to_distance_field(FillRule::NonZero)?;
+
+        let [width, height] = outline.size;
+        let field = MultiDistanceField::combine(&field, pseudo_distances).crop(width, height);
+
+        Ok(MultiDistanceFieldGlyph::new(glyph, outline.placement, spread, field))
+    }
+
+    // Outlines the `glyph` in the image of the distance field.
+    //
+    // Returns `None` if the glyph has no outline.
+    fn outline_distance_field(
+        &self,
+        glyph: Glyph,
+        pixels_per_em: f32,
+        spread: f32,
+        max_dim: usize,
+    ) -> crate::Result<Option<DistanceFieldOutline>> {
+        if !(pixels_per_em.is_finite() && pixels_per_em > 0.0) {
+            return Err(Error::InvalidArgument(format!(
+                "The number of pixels per em {pixels_per_em} must be positive"
+            )));
+        }
+
+        if !(spread.is_finite() && spread > 0.0) {
+            return Err(Error::InvalidArgument(format!("The spread {spread} must be positive")));
+        }
 
-            self.remove_excess_triangles(n, visibility);
+        if glyph.0 as usize >= self.glyph_count() {
+            return Err(Error::MissingGlyph(glyph));
+        }
+
+        let units_per_em = self.units_per_em().unwrap_or(1000.0);
+        let scale = pixels_per_em / units_per_em;
+        let mut builder = PathBuilder::new(DISTANCE_FIELD_FLATNESS / scale)?;
+
+        let outline_rect = self.subtables.as_face_ref().outline_glyph(glyph.into(), &mut builder);
+        let path = builder.build();
+        let Some(rect) = outline_rect.filter(|_| !path.is_empty()) else {
+            return Ok(None);
+        };
+
+        let width = ((rect.x_max - rect.x_min) as f32 * scale + spread * 2.0).ceil() as usize;
+        let height = ((rect.y_max - rect.y_min) as f32 * scale + spread * 2.0).ceil() as usize;
+        let dim = width.max(height).next_power_of_two().max(MIN_IMAGE_SIZE);
+        if dim > max_dim {
+            return Err(Error::InvalidArgument(format!(
+                "The dimension of the distance field {dim} exceeds the maximal image size {max_dim}"
+            )));
+        }
+
+        let offset = [spread - rect.x_min as f32 * scale, spread + rect.y_max as f32 * scale];
+        let placement = self.placement(glyph, rect, units_per_em, scale, offset);
+
+        let mut points = path.points().to_vec();
+        points.iter_mut().for_each(|p| {
+            let [x, y] = placement.from_font_units([p.x(), p.y()]);
+            p.set_coords(Float2::new(x, y));
         });
+        let (sites, corners) =
+            subdivide_outline(&points, path.corners(), DISTANCE_FIELD_SITE_SPACING);
+
+        Ok(Some(DistanceFieldOutline { placement, size: [width, height], dim, sites, corners }))
+    }
+
+    // Returns the placement of a glyph without an outline.
+    fn empty_placement(&self, glyph: Glyph, pixels_per_em: f32) -> GlyphPlacement {
+        let units_per_em = self.units_per_em().unwrap_or(1000.0);
+        let bounds = ttfp::Rect { x_min: 0, y_min: 0, x_max: 0, y_max: 0 };
+
+        self.placement(glyph, bounds, units_per_em, pixels_per_em / units_per_em, [0.0; 2])
+    }
+
+    // Returns the placement of the `glyph` with its horizontal metrics.
+    fn placement(
+        &self,
+        glyph: Glyph,
+        bounds: ttfp::Rect,
+        units_per_em: f32,
+        scale: f32,
+        offset: [f32; 2],
+    ) -> GlyphPlacement {
+        GlyphPlacement::new(bounds, units_per_em, scale, offset).with_hor_metrics(
+            self.hor_advance(glyph).unwrap_or_default(),
+            self.hor_side_bearing(glyph).unwrap_or_default(),
+        )
     }
 }
 
+// An outline of a glyph in the image of the distance field.
+struct DistanceFieldOutline {
+    placement: GlyphPlacement,
+    // The size of the field which covers the glyph and the spread around it.
+    size: [usize; 2],
+    // The dimension of the Voronoi image which contains the field.
+    dim: usize,
+    sites: Vec<Point>,
+    // The flags of the sites at which the outline has sharp corners.
+    corners: Vec<bool>,
+}
+
+// Splits segments of the outline which are longer than `max_length`.
+//
+// Every point is preceded by the points which split the segment ending at it,
+// so the links are remapped to the new indices. The splitting points aren't corners.
+fn subdivide_outline(
+    points: &[Point],
+    corners: &[bool],
+    max_length: f32,
+) -> (Vec<Point>, Vec<bool>) {
+    let previous = |p: &Point| {
+        let previous = p.previous_in_outline();
+        (0..points.len() as PointId)
+            .contains(&previous)
+            .then(|| &points[previous as usize])
+    };
+    let splits = points
+        .iter()
+        .map(|p| {
+            previous(p).map_or(0, |q| (p.distance(q) / max_length).ceil().max(1.0) as usize - 1)
+        })
+        .collect::<Vec<_>>();
+
+    let mut new_indices = Vec::with_capacity(points.len());
+    let mut len = 0;
+    splits.iter().for_each(|splits| {
+        len += splits + 1;
+        new_indices.push(len as PointId - 1);
+    });
+
+    let mut subdivided = Vec::with_capacity(len);
+    let mut subdivided_corners = Vec::with_capacity(len);
+    points
+        .iter()
+        .zip(corners)
+        .zip(&splits)
+        .for_each(|((p, &is_corner), &splits)| {
+            if let Some(q) = previous(p) {
+                let mut last = new_indices[p.previous_in_outline() as usize];
+                (1..=splits).for_each(|i| {
+                    let t = i as f32 / (splits + 1) as f32;
+                    let x = q.x() + (p.x() - q.x()) * t;
+                    let y = q.y() + (p.y() - q.y()) * t;
+
+                    subdivided.push(Point::with_previous(x, y, last));
+                    subdivided_corners.push(false);
+                    last = subdivided.len() as PointId - 1;
+                });
+
+                subdivided.push(Point::with_previous(p.x(), p.y(), last));
+            } else {
+                subdivided.push(Point::new(p.x(), p.y()));
+            }
+            subdivided_corners.push(is_corner);
+        });
+
+    (subdivided, subdivided_corners)
+}
+
 fn is_power_of_two(n: usize) -> bool {
     2usize.pow(n.ilog2()) == n
 }
 
 fn nearest_power_of_two(n: usize) -> usize {
+    if n == 0 {
+        return 1;
+    }
+
     if is_power_of_two(n) {
         return n;
     }
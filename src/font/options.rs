@@ -0,0 +1,155 @@
+use super::MIN_IMAGE_SIZE;
+
+use crate::error::Error;
+
+/// The maximal distance between a curve and the line segments which approximate it.
+///
+/// The smaller the flatness is, the more points are used to outline curves of a glyph.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Flatness {
+    /// The distance in font units.
+    FontUnits(f32),
+    /// The distance in fractions of the em square.
+    Em(f32),
+    /// The distance in pixels when the glyph is rendered with `pixels_per_em` pixels per em.
+    Pixels {
+        /// The distance in pixels.
+        distance: f32,
+        /// The size of the em square in pixels.
+        pixels_per_em: f32,
+    },
+}
+
+impl Flatness {
+    // Converts the flatness into font units.
+    pub(super) fn to_font_units(self, units_per_em: f32) -> f32 {
+        match self {
+            Self::FontUnits(distance) => distance,
+            Self::Em(distance) => distance * units_per_em,
+            Self::Pixels { distance, pixels_per_em } => distance * units_per_em / pixels_per_em,
+        }
+    }
+}
+
+impl Default for Flatness {
+    fn default() -> Self {
+        Self::FontUnits(0.35)
+    }
+}
+
+/// A rule which determines which parts of the glyph are filled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum FillRule {
+    /// Fills areas with a nonzero winding number, so overlapping contours are filled.
+    ///
+    /// It is the rule used by TrueType and CFF fonts.
+    #[default]
+    NonZero,
+    /// Fills areas with an odd winding number, so overlapping contours form holes.
+    EvenOdd,
+}
+
+impl FillRule {
+    /// Checks if the area with the given `winding` number is filled.
+    pub fn is_filled(self, winding: i32) -> bool {
+        match self {
+            Self::NonZero => winding != 0,
+            Self::EvenOdd => winding % 2 != 0,
+        }
+    }
+}
+
+/// Options which control how glyphs are outlined and triangulated.
+///
+/// Small text can use coarse meshes with fewer points and smaller Voronoi images,
+/// while large text can use fine ones.
+///
+/// ```
+/// use vdtfont::{Flatness, TriangulationOptions};
+///
+/// // Options for text which is rendered at 16 pixels per em.
+/// let options = TriangulationOptions {
+///     flatness: Flatness::Pixels { distance: 0.25, pixels_per_em: 16.0 },
+///     max_image_size: 256,
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TriangulationOptions {
+    /// The flatness of curves in the outline.
+    pub flatness: Flatness,
+    /// The rule which determines which parts of the glyph are filled.
+    pub fill_rule: FillRule,
+    /// The minimal dimension of the Voronoi image. It must be the power of two
+    /// which isn't less than 8.
+    pub min_image_size: usize,
+    /// The maximal dimension of the Voronoi image. It must be the power of two
+    /// which doesn't exceed the maximal dimension supported by [`Triangulator`].
+    ///
+    /// [`Triangulator`]: crate::triangulator::Triangulator
+    pub max_image_size: usize,
+    /// The minimal distance in pixels between points of the outline in the Voronoi image.
+    ///
+    /// Greater distances preserve more details but require larger images.
+    pub min_point_distance: f32,
+}
+
+impl TriangulationOptions {
+    /// Creates new [`TriangulationOptions`] with default values.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Checks if the options are valid.
+    pub fn validate(&self) -> crate::Result<()> {
+        if !self.min_image_size.is_power_of_two() || !self.max_image_size.is_power_of_two() {
+            return Err(Error::InvalidArgument(format!(
+                "The image sizes {} and {} must be powers of two",
+                self.min_image_size, self.max_image_size
+            )));
+        }
+
+        if self.min_image_size < MIN_IMAGE_SIZE {
+            return Err(Error::InvalidArgument(format!(
+                "The minimal image size {} is less than {MIN_IMAGE_SIZE}",
+                self.min_image_size
+            )));
+        }
+
+        if self.min_image_size > self.max_image_size {
+            return Err(Error::InvalidArgument(format!(
+                "The minimal image size {} exceeds the maximal image size {}",
+                self.min_image_size, self.max_image_size
+            )));
+        }
+
+        if !(self.min_point_distance.is_finite() && self.min_point_distance > 0.0) {
+            return Err(Error::InvalidArgument(format!(
+                "The minimal point distance {} must be positive",
+                self.min_point_distance
+            )));
+        }
+
+        let flatness = self.flatness.to_font_units(1.0);
+        if !(flatness.is_finite() && flatness > 0.0) {
+            return Err(Error::InvalidArgument(format!(
+                "The flatness {:?} must be positive",
+                self.flatness
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for TriangulationOptions {
+    fn default() -> Self {
+        Self {
+            flatness: Flatness::default(),
+            fill_rule: FillRule::default(),
+            min_image_size: 64,
+            max_image_size: 2048,
+            min_point_distance: 4.0,
+        }
+    }
+}
@@ -1,9 +1,6 @@
-use super::curve::*;
-
+use crate::path::curve::*;
 use crate::point::{Point, PointId};
 
-use owned_ttf_parser as ttfp;
-
 // A glyph outliner.
 pub(super) struct Outliner {
     pub(super) last: PointId,
@@ -11,17 +8,15 @@ pub(super) struct Outliner {
     pub(super) points: Vec<Point>,
 
     pub(super) shortest_distance: f32,
-}
 
-impl Outliner {
-    pub(super) fn new() -> Self {
-        Self { last: -1, last_move: -1, points: Vec::new(), shortest_distance: f32::MAX }
-    }
+    flatness: f32,
 }
 
-impl Default for Outliner {
-    fn default() -> Self {
-        Self::new()
+impl Outliner {
+    // Creates a new [`Outliner`] which tesselates curves with the given `flatness`
+    // in font units.
+    pub(super) fn new(flatness: f32) -> Self {
+        Self { last: -1, last_move: -1, points: Vec::new(), shortest_distance: f32::MAX, flatness }
     }
 }
 
@@ -42,7 +37,7 @@ impl ttfp::OutlineBuilder for Outliner {
         let last = self.points.get(self.last as usize).unwrap().clone();
         let p1 = Point::with_previous(x1, y1, self.last);
 
-        self.shortest_distance = self.shortest_distance.min(last.distance(&p1));
+        self.shortest_distance = shortest_distance(self.shortest_distance, last.distance(&p1));
 
         self.points.push(p1);
         self.last = self.points.len() as i64 - 1;
@@ -55,13 +50,13 @@ impl ttfp::OutlineBuilder for Outliner {
         let p2 = Point::new(x2, y2);
 
         let last = self.points.get(self.last as usize).unwrap().clone();
-        tesselate_quadric_curve((last, p1, p2), &mut self.points);
+        tesselate_quadric_curve((last, p1, p2), self.flatness, &mut self.points);
 
         (self.last + 1..self.points.len() as i64).for_each(|i| {
             let p1 = self.points.get((i - 1) as usize).unwrap().clone();
             let p0 = self.points.get_mut(i as usize).unwrap();
 
-            self.shortest_distance = self.shortest_distance.min(p0.distance(&p1));
+            self.shortest_distance = shortest_distance(self.shortest_distance, p0.distance(&p1));
 
             p0.set_previous_in_outline(i - 1);
         });
@@ -77,13 +72,13 @@ impl ttfp::OutlineBuilder for Outliner {
         let p3 = Point::new(x3, y3);
 
         let last = self.points.get(self.last as usize).unwrap().clone();
-        tesselate_cubic_curve((last, p1, p2, p3), &mut self.points);
+        tesselate_cubic_curve((last, p1, p2, p3), self.flatness, &mut self.points);
 
         (self.last + 1..self.points.len() as i64).for_each(|i| {
             let p1 = self.points.get((i - 1) as usize).unwrap().clone();
             let p0 = self.points.get_mut(i as usize).unwrap();
 
-            self.shortest_distance = self.shortest_distance.min(p0.distance(&p1));
+            self.shortest_distance = shortest_distance(self.shortest_distance, p0.distance(&p1));
 
             p0.set_previous_in_outline(i - 1);
         });
@@ -102,3 +97,16 @@ impl ttfp::OutlineBuilder for Outliner {
         }
     }
 }
+
+// Returns the shortest of the `shortest` distance and the `distance` between
+// consecutive points.
+//
+// Repeated points, which some fonts contain, don't make the outline finer,
+// so zero distances are skipped.
+fn shortest_distance(shortest: f32, distance: f32) -> f32 {
+    if distance > 0.0 {
+        shortest.min(distance)
+    } else {
+        shortest
+    }
+}
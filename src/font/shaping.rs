@@ -0,0 +1,149 @@
+use super::{Font, Glyph};
+
+use crate::error::Error;
+
+use std::str::FromStr;
+use ttfp::AsFaceRef;
+
+/// A direction of the text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    /// The text is read from left to right, e.g. Latin.
+    LeftToRight,
+    /// The text is read from right to left, e.g. Arabic or Hebrew.
+    RightToLeft,
+}
+
+impl From<Direction> for rustybuzz::Direction {
+    fn from(direction: Direction) -> Self {
+        match direction {
+            Direction::LeftToRight => Self::LeftToRight,
+            Direction::RightToLeft => Self::RightToLeft,
+        }
+    }
+}
+
+/// Options of the text shaping.
+///
+/// Properties which are `None` are guessed from the text.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ShapingOptions {
+    /// A direction of the text.
+    pub direction: Option<Direction>,
+    /// An ISO 15924 tag of the script, e.g. `Arab` or `Deva`.
+    pub script: Option<ttfp::Tag>,
+    /// A BCP 47 tag of the language, e.g. `ar` or `hi`.
+    pub language: Option<String>,
+    /// OpenType features in the HarfBuzz syntax, e.g. `-liga` or `smcp`.
+    pub features: Vec<String>,
+}
+
+/// A glyph which is produced by the text shaping.
+///
+/// Advances and offsets are in font units.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShapedGlyph {
+    glyph: Glyph,
+    cluster: usize,
+
+    advance: [f32; 2],
+    offset: [f32; 2],
+}
+
+impl ShapedGlyph {
+    /// Returns a glyph.
+    pub fn glyph(&self) -> Glyph {
+        self.glyph
+    }
+
+    /// Returns the byte offset in the text of the first character which the glyph
+    /// was produced from.
+    ///
+    /// Several glyphs have the same cluster if they were produced from one character,
+    /// and a ligature has the cluster of the first of its characters.
+    pub fn cluster(&self) -> usize {
+        self.cluster
+    }
+
+    /// Returns how much the pen moves after the glyph as `[x, y]`.
+    pub fn advance(&self) -> [f32; 2] {
+        self.advance
+    }
+
+    /// Returns how much the glyph is moved from the pen as `[x, y]`,
+    /// e.g. to place a mark above its base.
+    pub fn offset(&self) -> [f32; 2] {
+        self.offset
+    }
+}
+
+impl Font {
+    /// Shapes the `text` with the default [`ShapingOptions`].
+    #[inline]
+    pub fn shape(&self, text: &str) -> Vec<ShapedGlyph> {
+        self.shape_with_options(text, &ShapingOptions::default())
+            .expect("The default shaping options are valid")
+    }
+
+    /// Shapes the `text` with the given `options`.
+    ///
+    /// Unlike [`Font::glyph`], the shaping substitutes ligatures and contextual forms
+    /// and positions marks with the OpenType layout tables of the font. Glyphs are returned
+    /// in the visual order, so the glyphs of the right-to-left text are reversed.
+    ///
+    /// Returns [`Error::InvalidArgument`] if the script, the language or one of
+    /// the features of `options` can't be parsed.
+    pub fn shape_with_options(
+        &self,
+        text: &str,
+        options: &ShapingOptions,
+    ) -> crate::Result<Vec<ShapedGlyph>> {
+        // The shaping face borrows the parsed font data and its variation coordinates.
+        let face = rustybuzz::Face::from_face(self.subtables.as_face_ref().clone());
+
+        let mut buffer = rustybuzz::UnicodeBuffer::new();
+        buffer.push_str(text);
+
+        if let Some(direction) = options.direction {
+            buffer.set_direction(direction.into());
+        }
+
+        if let Some(tag) = options.script {
+            let script = rustybuzz::Script::from_iso15924_tag(tag)
+                .ok_or_else(|| Error::InvalidArgument(format!("The script {tag} is invalid")))?;
+            buffer.set_script(script);
+        }
+
+        if let Some(language) = &options.language {
+            let language = rustybuzz::Language::from_str(language).map_err(|_| {
+                Error::InvalidArgument(format!("The language {language} can't be parsed"))
+            })?;
+            buffer.set_language(language);
+        }
+
+        let features = options
+            .features
+            .iter()
+            .map(|feature| {
+                rustybuzz::Feature::from_str(feature).map_err(|_| {
+                    Error::InvalidArgument(format!("The feature {feature} can't be parsed"))
+                })
+            })
+            .collect::<crate::Result<Vec<_>>>()?;
+
+        let glyph_buffer = rustybuzz::shape(&face, &features, buffer);
+        let shaped_glyphs = glyph_buffer
+            .glyph_infos()
+            .iter()
+            .zip(glyph_buffer.glyph_positions())
+            .map(|(info, position)| ShapedGlyph {
+                glyph: Glyph(info.glyph_id as u16),
+                cluster: info.cluster as usize,
+                advance: [position.x_advance as f32, position.y_advance as f32],
+                offset: [position.x_offset as f32, position.y_offset as f32],
+            })
+            .collect();
+
+        Ok(shaped_glyphs)
+    }
+}
@@ -0,0 +1,742 @@
+use crate::atlas::MeshAtlas;
+use crate::error::Error;
+#[cfg(feature = "shaping")]
+use crate::font::{Direction, ShapingOptions};
+use crate::font::{Font, Glyph};
+use crate::mesh::Mesh;
+
+use std::ops::Range;
+use unicode_bidi::BidiInfo;
+use unicode_linebreak::{BreakClass, BreakOpportunity};
+
+/// A glyph which is positioned in [`TextLayout`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PositionedGlyph {
+    glyph: Glyph,
+    cluster: usize,
+
+    position: [f32; 2],
+    advance: f32,
+    pixels_per_em: f32,
+    is_rtl: bool,
+}
+
+impl PositionedGlyph {
+    /// Returns a glyph.
+    pub fn glyph(&self) -> Glyph {
+        self.glyph
+    }
+
+    /// Returns the byte offset of the character of the glyph in the text.
+    pub fn cluster(&self) -> usize {
+        self.cluster
+    }
+
+    /// Checks if the glyph belongs to a right-to-left run of the text.
+    pub fn is_rtl(&self) -> bool {
+        self.is_rtl
+    }
+
+    /// Returns the position of the origin of the glyph on the baseline in pixels.
+    pub fn position(&self) -> [f32; 2] {
+        self.position
+    }
+
+    /// Returns the horizontal advance of the glyph in pixels including the kerning
+    /// with the following glyph and the stretching of justified spaces.
+    pub fn advance(&self) -> f32 {
+        self.advance
+    }
+
+    /// Returns the transformation `p' = p * scale + offset` as `(scale, offset)`,
+    /// which places the mesh of the glyph in the em square into the layout,
+    /// see [`TriangulatedGlyph::to_em_mesh`].
+    ///
+    /// [`TriangulatedGlyph::to_em_mesh`]: crate::font::TriangulatedGlyph::to_em_mesh
+    pub fn transform(&self) -> ([f32; 2], [f32; 2]) {
+        ([self.pixels_per_em; 2], self.position)
+    }
+}
+
+/// An alignment of lines in [`TextLayout`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Alignment {
+    /// Lines start at the left edge.
+    #[default]
+    Left,
+    /// Lines are centered.
+    Center,
+    /// Lines end at the right edge.
+    Right,
+    /// Spaces of wrapped lines are stretched, so the lines fill the whole width.
+    ///
+    /// The last lines of paragraphs are aligned to the left.
+    Justify,
+}
+
+/// Options which control how lines of [`TextLayout`] are broken and aligned.
+///
+/// ```
+/// use vdtfont::{Alignment, LayoutOptions};
+///
+/// // A label which wraps at 200 pixels.
+/// let options = LayoutOptions { max_width: Some(200.0), alignment: Alignment::Center };
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct LayoutOptions {
+    /// The maximal width of lines in pixels, or `None` if lines are broken only
+    /// by line breaks.
+    ///
+    /// Lines are broken at the line break opportunities of the Unicode Line Breaking
+    /// Algorithm (UAX #14), so a word which is wider than `max_width` overflows its line.
+    pub max_width: Option<f32>,
+    /// The alignment of lines within `max_width` or within the widest line
+    /// if there is no maximal width.
+    pub alignment: Alignment,
+}
+
+impl LayoutOptions {
+    /// Creates new [`LayoutOptions`] with default values.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Checks if the options are valid.
+    pub fn validate(&self) -> crate::Result<()> {
+        if let Some(max_width) = self.max_width.filter(|w| !(w.is_finite() && *w > 0.0)) {
+            return Err(Error::InvalidArgument(format!(
+                "The maximal width {max_width} must be positive"
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+/// A line of [`TextLayout`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineBox {
+    glyphs: Range<usize>,
+    text: Range<usize>,
+
+    baseline: f32,
+    bounds: [f32; 4],
+}
+
+impl LineBox {
+    /// Returns the range of the glyphs of the line in [`TextLayout::glyphs`].
+    pub fn glyphs(&self) -> Range<usize> {
+        self.glyphs.clone()
+    }
+
+    /// Returns the byte range of the line in the text without the line break.
+    pub fn text(&self) -> Range<usize> {
+        self.text.clone()
+    }
+
+    /// Returns `y` of the baseline of the line in pixels.
+    pub fn baseline(&self) -> f32 {
+        self.baseline
+    }
+
+    /// Returns bounds of the line in pixels as `[x_min, y_min, x_max, y_max]`.
+    ///
+    /// Vertically, the bounds span from the descender to the ascender of the font.
+    /// Horizontally, they span the glyphs of the line without trailing whitespace.
+    pub fn bounds(&self) -> [f32; 4] {
+        self.bounds
+    }
+
+    /// Returns the width of the line without trailing whitespace in pixels.
+    pub fn width(&self) -> f32 {
+        self.bounds[2] - self.bounds[0]
+    }
+}
+
+/// A layout of the text with a single font.
+///
+/// Glyphs are placed along the baselines by their advances and kerning. Lines start
+/// at `x = 0`, unless they are aligned otherwise, and the first baseline is at `y = 0`.
+/// The y axis points up, like in the meshes of glyphs, so the following lines are below
+/// at the negative `y`.
+///
+/// Runs of every line are reordered visually by the Unicode Bidirectional Algorithm
+/// (UAX #9), so right-to-left scripts, e.g. Arabic or Hebrew, can be mixed with
+/// left-to-right ones. Characters of right-to-left runs which have mirrored forms,
+/// e.g. parentheses, are replaced with them.
+///
+/// ```no_run
+/// use vdtfont::*;
+///
+/// # fn main() -> vdtfont::Result<()> {
+/// let mut triangulator = Triangulator::new(Backend::Cpu)?;
+/// let font_data = include_bytes!("/usr/share/fonts/truetype/open-sans/OpenSans-Regular.ttf");
+/// let font = Font::from_vec(font_data.to_vec())?;
+///
+/// let layout = TextLayout::new(&font, "Hello,\nWorld!", 32.0)?;
+///
+/// // Triangulate the glyphs of the text which aren't in the atlas yet.
+/// let mut atlas = MeshAtlas::new();
+/// let outlined_glyphs = layout
+///     .missing_glyphs(&atlas)
+///     .into_iter()
+///     .map(|glyph| font.outline_glyph(glyph))
+///     .collect::<vdtfont::Result<Vec<_>>>()?;
+/// atlas.extend(&triangulator.triangulate_glyphs(&outlined_glyphs)?);
+///
+/// let mesh = layout.to_mesh(&atlas)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextLayout {
+    glyphs: Vec<PositionedGlyph>,
+    lines: Vec<LineBox>,
+
+    pixels_per_em: f32,
+    // The ascender and the descender of the font in pixels.
+    ascender: f32,
+    descender: f32,
+    line_height: f32,
+    width: f32,
+}
+
+impl TextLayout {
+    /// Lays out the `text` with `font` at `pixels_per_em` pixels per em
+    /// with the default [`LayoutOptions`].
+    ///
+    /// Lines are separated by line breaks, e.g. `\n`, and the distance between
+    /// their baselines is `ascender - descender + line_gap` of the font.
+    ///
+    /// Returns [`Error::InvalidArgument`] if `pixels_per_em` isn't positive.
+    #[inline]
+    pub fn new(font: &Font, text: &str, pixels_per_em: f32) -> crate::Result<Self> {
+        Self::with_options(font, text, pixels_per_em, &LayoutOptions::default())
+    }
+
+    /// Lays out the `text` with `font` at `pixels_per_em` pixels per em
+    /// with the given `options`.
+    ///
+    /// Returns [`Error::InvalidArgument`] if `pixels_per_em` isn't positive
+    /// or `options` are invalid.
+    pub fn with_options(
+        font: &Font,
+        text: &str,
+        pixels_per_em: f32,
+        options: &LayoutOptions,
+    ) -> crate::Result<Self> {
+        validate_pixels_per_em(pixels_per_em)?;
+        options.validate()?;
+
+        Ok(Self::layout(font, text, pixels_per_em, options))
+    }
+
+    /// Lays out the `text` shaped by `font` with `options` at `pixels_per_em` pixels per em.
+    ///
+    /// Every visual run of every line is shaped separately, see [`Font::shape_with_options`],
+    /// so glyphs are placed by the advances and offsets of the shaping instead of
+    /// the kerning. The directions of the runs of the bidirectional text are determined
+    /// by the Unicode Bidirectional Algorithm instead of `options`.
+    /// Lines are separated by `\n` only and aligned to the left.
+    ///
+    /// Returns [`Error::InvalidArgument`] if `pixels_per_em` isn't positive
+    /// or `options` are invalid.
+    #[cfg(feature = "shaping")]
+    pub fn shaped(
+        font: &Font,
+        text: &str,
+        pixels_per_em: f32,
+        options: &ShapingOptions,
+    ) -> crate::Result<Self> {
+        validate_pixels_per_em(pixels_per_em)?;
+
+        let mut layout = Self::empty(font, pixels_per_em);
+        let scale = pixels_per_em / font.units_per_em().unwrap_or(1000.0);
+
+        let mut line_start = 0;
+        for raw_line in text.split('\n') {
+            let line = raw_line.trim_end_matches('\r');
+
+            let bidi = BidiInfo::new(line, None);
+            let runs = if bidi.has_rtl() {
+                visual_runs(&bidi, 0..line.len())
+                    .into_iter()
+                    .map(|(run, is_rtl)| {
+                        let direction =
+                            if is_rtl { Direction::RightToLeft } else { Direction::LeftToRight };
+                        (run, Some(direction))
+                    })
+                    .collect()
+            } else {
+                vec![(0..line.len(), options.direction)]
+            };
+
+            let mut glyphs = vec![];
+            let mut pen = [0.0, 0.0];
+            for (run, direction) in runs {
+                let options = ShapingOptions { direction, ..options.clone() };
+                let is_rtl = direction == Some(Direction::RightToLeft);
+
+                for shaped_glyph in font.shape_with_options(&line[run.clone()], &options)? {
+                    let [advance_x, advance_y] = shaped_glyph.advance().map(|a| a * scale);
+                    let [offset_x, offset_y] = shaped_glyph.offset().map(|o| o * scale);
+
+                    glyphs.push(PositionedGlyph {
+                        glyph: shaped_glyph.glyph(),
+                        cluster: line_start + run.start + shaped_glyph.cluster(),
+                        position: [pen[0] + offset_x, pen[1] + offset_y],
+                        advance: advance_x,
+                        pixels_per_em,
+                        is_rtl,
+                    });
+                    pen = [pen[0] + advance_x, pen[1] + advance_y];
+                }
+            }
+
+            let width = hang_trailing_whitespace(&mut glyphs, line_start + line.trim_end().len());
+            let line = PlacedLine::new(glyphs, line_start..line_start + line.len(), width, pen[0]);
+            layout.push_line(line, Alignment::Left, 0.0);
+            line_start += raw_line.len() + 1;
+        }
+
+        Ok(layout)
+    }
+
+    /// Returns the positioned glyphs in the visual order of every line from left to right.
+    pub fn glyphs(&self) -> &[PositionedGlyph] {
+        &self.glyphs
+    }
+
+    /// Returns the lines from top to bottom.
+    ///
+    /// There is always at least one line, even if the text is empty.
+    pub fn lines(&self) -> &[LineBox] {
+        &self.lines
+    }
+
+    /// Returns a number of pixels per em.
+    pub fn pixels_per_em(&self) -> f32 {
+        self.pixels_per_em
+    }
+
+    /// Returns the distance between the baselines of consecutive lines in pixels.
+    pub fn line_height(&self) -> f32 {
+        self.line_height
+    }
+
+    /// Returns a number of lines.
+    pub fn line_count(&self) -> usize {
+        self.lines.len()
+    }
+
+    /// Returns the width of the widest line without trailing whitespace in pixels.
+    pub fn width(&self) -> f32 {
+        self.width
+    }
+
+    /// Checks if the layout has no glyphs.
+    pub fn is_empty(&self) -> bool {
+        self.glyphs.is_empty()
+    }
+
+    /// Returns the index of the line which contains the byte `offset` in the text.
+    ///
+    /// An offset at the boundary of two wrapped lines belongs to the second one.
+    pub fn line_at(&self, offset: usize) -> usize {
+        self.lines
+            .partition_point(|line| line.text.start <= offset)
+            .saturating_sub(1)
+    }
+
+    /// Returns the byte offset in the text of the caret which is the closest to `point`.
+    ///
+    /// The point is matched to the line whose bounds contain its `y` and then
+    /// to the half of the glyph which contains its `x`, so the caret is placed
+    /// before or after the glyph in the reading direction.
+    pub fn hit_test(&self, point: [f32; 2]) -> usize {
+        let Some(line) = self
+            .lines
+            .iter()
+            .find(|line| point[1] >= line.bounds[1])
+            .or(self.lines.last())
+        else {
+            return 0;
+        };
+
+        let glyphs = &self.glyphs[line.glyphs()];
+        let Some(glyph) = glyphs
+            .iter()
+            .find(|g| point[0] < g.position[0] + g.advance)
+            .or(glyphs.last())
+        else {
+            return line.text.end;
+        };
+
+        // The left half of the right-to-left glyph is after its character.
+        let is_left_half = point[0] < glyph.position[0] + glyph.advance / 2.0;
+        if is_left_half != glyph.is_rtl {
+            return glyph.cluster;
+        }
+
+        glyphs
+            .iter()
+            .map(|g| g.cluster)
+            .filter(|&cluster| cluster > glyph.cluster)
+            .min()
+            .unwrap_or(line.text.end)
+    }
+
+    /// Returns the position of the caret before the character at the byte `offset`
+    /// in the text as `[x, baseline]` in pixels.
+    ///
+    /// The caret is on the left side of left-to-right glyphs and on the right side of
+    /// right-to-left ones. The caret inside a cluster, e.g. a ligature, is placed before
+    /// the next cluster.
+    pub fn caret_position(&self, offset: usize) -> [f32; 2] {
+        let Some(line) = self.lines.get(self.line_at(offset)) else {
+            return [0.0; 2];
+        };
+
+        let glyphs = &self.glyphs[line.glyphs()];
+        let x = match glyphs.iter().filter(|g| g.cluster >= offset).min_by_key(|g| g.cluster) {
+            Some(g) if g.is_rtl => g.position[0] + g.advance,
+            Some(g) => g.position[0],
+            // The caret at the end of the line is after the last glyph in the logical order.
+            None => match glyphs.iter().max_by_key(|g| g.cluster) {
+                Some(g) if g.is_rtl => g.position[0],
+                Some(g) => g.position[0] + g.advance,
+                None => line.bounds[0],
+            },
+        };
+
+        [x, line.baseline]
+    }
+
+    /// Returns the glyphs of the layout which aren't in `atlas` without repetitions.
+    pub fn missing_glyphs(&self, atlas: &MeshAtlas) -> Vec<Glyph> {
+        let mut glyphs = self
+            .glyphs
+            .iter()
+            .map(|g| g.glyph)
+            .filter(|&glyph| !atlas.contains(glyph))
+            .collect::<Vec<_>>();
+        glyphs.sort_unstable();
+        glyphs.dedup();
+
+        glyphs
+    }
+
+    /// Merges the meshes of all glyphs from `atlas` into one mesh of the whole text.
+    ///
+    /// Returns [`Error::InvalidArgument`] if `atlas` doesn't contain one of the glyphs,
+    /// see [`TextLayout::missing_glyphs`].
+    pub fn to_mesh(&self, atlas: &MeshAtlas) -> crate::Result<Mesh> {
+        let mut mesh = Mesh::default();
+        for positioned_glyph in &self.glyphs {
+            let entry = atlas.entry(positioned_glyph.glyph).ok_or_else(|| {
+                Error::InvalidArgument(format!(
+                    "The atlas doesn't contain the glyph {}",
+                    positioned_glyph.glyph.0
+                ))
+            })?;
+
+            let vertices = entry.vertices();
+            let positions = atlas.mesh().positions()[vertices.clone()].to_vec();
+            let indices = atlas.mesh().indices()[entry.indices()]
+                .iter()
+                .map(|&i| i - vertices.start as u32)
+                .collect();
+
+            let mut glyph_mesh = Mesh::new(positions, indices);
+            let (scale, offset) = positioned_glyph.transform();
+            glyph_mesh.transform(scale, offset);
+            mesh.append(&glyph_mesh);
+        }
+
+        Ok(mesh)
+    }
+
+    fn empty(font: &Font, pixels_per_em: f32) -> Self {
+        let scale = pixels_per_em / font.units_per_em().unwrap_or(1000.0);
+
+        Self {
+            glyphs: vec![],
+            lines: vec![],
+            pixels_per_em,
+            ascender: font.ascender() * scale,
+            descender: font.descender() * scale,
+            line_height: (font.height() + font.line_gap()) * scale,
+            width: 0.0,
+        }
+    }
+
+    fn layout(font: &Font, text: &str, pixels_per_em: f32, options: &LayoutOptions) -> Self {
+        let mut layout = Self::empty(font, pixels_per_em);
+
+        // Lines are broken in the logical order and then reordered visually.
+        let bidi = BidiInfo::new(text, None);
+        let bidi = bidi.has_rtl().then_some(&bidi);
+        let lines = break_lines(font, text, pixels_per_em, options.max_width)
+            .into_iter()
+            .map(|(range, is_last)| {
+                PlacedLine::place(font, text, range, is_last, pixels_per_em, bidi)
+            })
+            .collect::<Vec<_>>();
+
+        // Without the maximal width, lines are aligned within the widest one.
+        let width = options
+            .max_width
+            .unwrap_or_else(|| lines.iter().fold(0.0, |width, line| line.width.max(width)));
+        for line in lines {
+            layout.push_line(line, options.alignment, width);
+        }
+
+        layout
+    }
+
+    // Aligns the line within `width` and places it below the previous lines.
+    fn push_line(&mut self, mut line: PlacedLine, alignment: Alignment, width: f32) {
+        let free_width = (width - line.width).max(0.0);
+        let (shift, stretch) = match alignment {
+            Alignment::Left => (0.0, 0.0),
+            Alignment::Center => (free_width / 2.0, 0.0),
+            Alignment::Right => (free_width, 0.0),
+            Alignment::Justify if !line.is_last && !line.spaces.is_empty() => {
+                (0.0, free_width / line.spaces.len() as f32)
+            }
+            Alignment::Justify => (0.0, 0.0),
+        };
+
+        let baseline = -self.line_height * self.lines.len() as f32;
+        let mut offset = shift;
+        // The indices of the spaces are sorted, so they are visited along with the glyphs.
+        let mut spaces = line.spaces.iter().peekable();
+        for (i, glyph) in line.glyphs.iter_mut().enumerate() {
+            glyph.position = [glyph.position[0] + offset, glyph.position[1] + baseline];
+
+            if spaces.next_if_eq(&&i).is_some() {
+                glyph.advance += stretch;
+                offset += stretch;
+            }
+        }
+
+        let line_width = line.width + stretch * line.spaces.len() as f32;
+        self.width = self.width.max(line_width);
+        self.lines.push(LineBox {
+            glyphs: self.glyphs.len()..self.glyphs.len() + line.glyphs.len(),
+            text: line.text,
+            baseline,
+            bounds: [
+                shift,
+                baseline + self.descender,
+                shift + line_width,
+                baseline + self.ascender,
+            ],
+        });
+        self.glyphs.append(&mut line.glyphs);
+    }
+}
+
+// Glyphs of a line which are placed from `x = 0` on the baseline `y = 0`.
+struct PlacedLine {
+    glyphs: Vec<PositionedGlyph>,
+    text: Range<usize>,
+
+    // The width without trailing whitespace.
+    width: f32,
+    // The width with trailing whitespace.
+    advance: f32,
+    // Checks if the line ends the paragraph rather than is wrapped.
+    is_last: bool,
+    // Indices of the glyphs of spaces between words, which are stretched by justification.
+    spaces: Vec<usize>,
+}
+
+impl PlacedLine {
+    #[cfg(feature = "shaping")]
+    fn new(glyphs: Vec<PositionedGlyph>, text: Range<usize>, width: f32, advance: f32) -> Self {
+        Self { glyphs, text, width, advance, is_last: true, spaces: vec![] }
+    }
+
+    // Places the glyphs of the characters of `text[range]` by their advances and kerning.
+    //
+    // The characters are reordered visually by `bidi`, or kept in the logical order
+    // if it is `None`.
+    fn place(
+        font: &Font,
+        text: &str,
+        range: Range<usize>,
+        is_last: bool,
+        pixels_per_em: f32,
+        bidi: Option<&BidiInfo>,
+    ) -> Self {
+        let content = range.start..range.start + text[range].trim_end_matches(is_line_break).len();
+        // The trailing whitespace is found in the logical order, because it can be
+        // at the visual left of the right-to-left paragraph.
+        let trimmed_end = content.start + text[content.clone()].trim_end().len();
+        let scale = pixels_per_em / font.units_per_em().unwrap_or(1000.0);
+
+        let runs = match bidi {
+            Some(bidi) => visual_runs(bidi, content.clone()),
+            None => vec![(content.clone(), false)],
+        };
+
+        let mut chars = vec![];
+        for (run, is_rtl) in runs {
+            let run_start = chars.len();
+            chars.extend(text[run.clone()].char_indices().map(|(i, c)| (run.start + i, c, is_rtl)));
+
+            if is_rtl {
+                chars[run_start..].reverse();
+            }
+        }
+
+        let mut glyphs: Vec<PositionedGlyph> = vec![];
+        let mut whitespace = vec![];
+        let mut pen = 0.0;
+        for (cluster, c, is_rtl) in chars {
+            let mirrored = if is_rtl { unicode_bidi_mirroring::get_mirrored(c) } else { None };
+            let glyph = font.glyph(mirrored.unwrap_or(c));
+
+            // The kerning is a part of the advance of the previous glyph.
+            if let Some(previous) = glyphs.last_mut() {
+                let kerning = font.hor_kerning(previous.glyph, glyph) * scale;
+                previous.advance += kerning;
+                pen += kerning;
+            }
+
+            if c.is_whitespace() {
+                whitespace.push(glyphs.len());
+            }
+
+            let advance = font.hor_advance(glyph).unwrap_or_default() * scale;
+            glyphs.push(PositionedGlyph {
+                glyph,
+                cluster,
+                position: [pen, 0.0],
+                advance,
+                pixels_per_em,
+                is_rtl,
+            });
+            pen += advance;
+        }
+
+        let width = hang_trailing_whitespace(&mut glyphs, trimmed_end);
+
+        // Trailing whitespace isn't stretched.
+        whitespace.retain(|&i| glyphs[i].cluster < trimmed_end);
+
+        Self { glyphs, text: content, width, advance: pen, is_last, spaces: whitespace }
+    }
+}
+
+// Checks if the glyphs can be scaled to `pixels_per_em` pixels per em.
+fn validate_pixels_per_em(pixels_per_em: f32) -> crate::Result<()> {
+    if !(pixels_per_em.is_finite() && pixels_per_em > 0.0) {
+        return Err(Error::InvalidArgument(format!(
+            "The number of pixels per em {pixels_per_em} must be positive"
+        )));
+    }
+
+    Ok(())
+}
+
+// Moves the glyphs, so the glyphs of the text before the byte offset `end` start at `x = 0`,
+// and returns their width. The glyphs of the trailing whitespace after `end` hang outside
+// the line, e.g. at the left of right-to-left paragraphs.
+fn hang_trailing_whitespace(glyphs: &mut [PositionedGlyph], end: usize) -> f32 {
+    let (start, width) = glyphs
+        .iter()
+        .filter(|glyph| glyph.cluster < end)
+        .map(|glyph| (glyph.position[0], glyph.position[0] + glyph.advance))
+        .reduce(|(start, width), (left, right)| (start.min(left), width.max(right)))
+        .unwrap_or_default();
+
+    glyphs.iter_mut().for_each(|glyph| glyph.position[0] -= start);
+
+    width - start
+}
+
+// Breaks the text into lines which are narrower than `max_width` at the line break
+// opportunities and returns their byte ranges with the line breaks. Every range is
+// accompanied by the flag which checks if the line ends a paragraph.
+fn break_lines(
+    font: &Font,
+    text: &str,
+    pixels_per_em: f32,
+    max_width: Option<f32>,
+) -> Vec<(Range<usize>, bool)> {
+    let mut lines = vec![];
+    let (mut line_start, mut segment_start, mut width) = (0, 0, 0.0);
+    let scale = pixels_per_em / font.units_per_em().unwrap_or(1000.0);
+    // The last glyph of the line, which is kerned with the first glyph of the next segment.
+    let mut last_glyph = None;
+
+    for (end, opportunity) in unicode_linebreak::linebreaks(text) {
+        if let Some(max_width) = max_width {
+            let segment =
+                PlacedLine::place(font, text, segment_start..end, false, pixels_per_em, None);
+            let kerning = last_glyph
+                .zip(segment.glyphs.first())
+                .map_or(0.0, |(last, first)| font.hor_kerning(last, first.glyph) * scale);
+
+            if segment_start > line_start && width + kerning + segment.width > max_width {
+                lines.push((line_start..segment_start, false));
+                (line_start, width) = (segment_start, 0.0);
+            } else {
+                width += kerning;
+            }
+
+            width += segment.advance;
+            last_glyph = segment.glyphs.last().map(|glyph| glyph.glyph).or(last_glyph);
+        }
+
+        if opportunity == BreakOpportunity::Mandatory {
+            lines.push((line_start..end, true));
+            (line_start, width, last_glyph) = (end, 0.0, None);
+        }
+
+        segment_start = end;
+    }
+
+    // The text which ends with a line break has an empty last line.
+    if lines
+        .last()
+        .is_none_or(|(range, _)| text[range.clone()].ends_with(is_line_break))
+    {
+        lines.push((text.len()..text.len(), true));
+    }
+
+    lines
+}
+
+// Returns the runs of `text[range]` with the same direction in the visual order
+// and checks if they are right-to-left.
+fn visual_runs(bidi: &BidiInfo, range: Range<usize>) -> Vec<(Range<usize>, bool)> {
+    let mut runs = vec![];
+    for paragraph in &bidi.paragraphs {
+        let line = range.start.max(paragraph.range.start)..range.end.min(paragraph.range.end);
+        if line.is_empty() {
+            continue;
+        }
+
+        let (levels, level_runs) = bidi.visual_runs(paragraph, line);
+        runs.extend(level_runs.into_iter().map(|run| {
+            let is_rtl = levels[run.start].is_rtl();
+            (run, is_rtl)
+        }));
+    }
+
+    runs
+}
+
+// Checks if the character causes a mandatory line break after it.
+fn is_line_break(c: char) -> bool {
+    matches!(
+        unicode_linebreak::break_property(c as u32),
+        BreakClass::Mandatory
+            | BreakClass::CarriageReturn
+            | BreakClass::LineFeed
+            | BreakClass::NextLine
+    )
+}
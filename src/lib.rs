@@ -4,16 +4,19 @@
 //! ```
 //! use vdtfont::{*, delaunay::*};
 //!
+//! // Create a triangulator which can be shared between fonts
+//! let mut triangulator = Triangulator::builder().build().unwrap();
+//!
 //! // Create a font
 //! let font_data = include_bytes!("/usr/share/fonts/truetype/open-sans/OpenSans-Regular.ttf");
-//! let mut font = Font::from_vec(font_data.to_vec()).unwrap();
+//! let font = Font::from_vec(font_data.to_vec()).unwrap();
 //!
 //! // Obtain a glyph
 //! let glyph = font.glyph('a');
 //! // Outline the glyph
-//! let outlined_glyph = font.outline_glyph(glyph);
+//! let outlined_glyph = font.outline_glyph(glyph).unwrap();
 //! // Triangulate th glyph
-//! let triangulated_glyph = font.triangulate_glyph(outlined_glyph).unwrap();
+//! let triangulated_glyph = font.triangulate_glyph(&mut triangulator, outlined_glyph).unwrap();
 //!
 //! // Use the resulting triangulation
 //! triangulated_glyph
@@ -28,11 +31,32 @@ pub extern crate arena_system;
 pub extern crate ocl;
 pub extern crate owned_ttf_parser as ttfp;
 
+pub mod atlas;
+pub mod backend;
 pub mod delaunay;
+pub mod error;
 pub mod font;
+pub mod layout;
+pub mod mesh;
 pub mod opencl;
+pub mod path;
 pub mod point;
+pub mod predicates;
+pub mod triangulator;
 pub mod voronoi;
 
-pub use font::{Font, Glyph, OutlinedGlyph, TriangulatedGlyph};
+pub use atlas::{AtlasChanges, AtlasEntry, MeshAtlas};
+pub use backend::Backend;
+pub use error::{Error, Result};
+pub use font::{
+    CacheStats, DistanceFieldGlyph, FillRule, Flatness, Font, Glyph, GlyphCache, GlyphPlacement,
+    MultiDistanceFieldGlyph, OutlinedGlyph, TriangulatedGlyph, TriangulationOptions,
+};
+#[cfg(feature = "shaping")]
+pub use font::{Direction, ShapedGlyph, ShapingOptions};
+pub use layout::{Alignment, LayoutOptions, LineBox, PositionedGlyph, TextLayout};
+pub use mesh::Mesh;
+pub use path::{Path, PathBuilder};
 pub use point::{Point, PointHandle, PointId};
+pub use triangulator::{Triangulator, TriangulatorBuilder};
+pub use voronoi::{DistanceField, MultiDistanceField};
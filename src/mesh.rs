@@ -0,0 +1,84 @@
+/// A compact triangle mesh which can be uploaded directly to vertex and index buffers.
+///
+/// Every three consecutive indices form a counterclockwise triangle.
+/// Positions are `[f32; 2]` and indices are `u32`, so both are `#[repr(C)]` plain data.
+/// With the `bytemuck` feature, they can be viewed as bytes without copying.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Mesh {
+    positions: Vec<[f32; 2]>,
+    indices: Vec<u32>,
+}
+
+impl Mesh {
+    /// Creates a new [`Mesh`] from `positions` and `indices`.
+    pub fn new(positions: Vec<[f32; 2]>, indices: Vec<u32>) -> Self {
+        Self { positions, indices }
+    }
+
+    /// Returns positions of the vertices.
+    pub fn positions(&self) -> &[[f32; 2]] {
+        &self.positions
+    }
+
+    /// Returns indices of the vertices which form triangles.
+    pub fn indices(&self) -> &[u32] {
+        &self.indices
+    }
+
+    /// Returns indices as `u16`, if every index fits into it.
+    pub fn indices_u16(&self) -> Option<Vec<u16>> {
+        self.indices.iter().map(|&i| u16::try_from(i).ok()).collect()
+    }
+
+    /// Returns a number of triangles in the mesh.
+    pub fn triangle_count(&self) -> usize {
+        self.indices.len() / 3
+    }
+
+    /// Checks if the mesh has no triangles.
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    /// Transforms positions of the vertices, so that `p' = p * scale + offset`.
+    ///
+    /// If the transformation mirrors the mesh, the order of the vertices in triangles is
+    /// reversed, so the triangles stay counterclockwise.
+    pub fn transform(&mut self, scale: [f32; 2], offset: [f32; 2]) {
+        self.positions.iter_mut().for_each(|p| {
+            *p = [p[0] * scale[0] + offset[0], p[1] * scale[1] + offset[1]];
+        });
+
+        if scale[0] * scale[1] < 0.0 {
+            self.indices.chunks_exact_mut(3).for_each(|t| t.swap(1, 2));
+        }
+    }
+
+    /// Appends the vertices and triangles of `other` to the mesh.
+    ///
+    /// The indices of `other` are offset by the number of vertices in the mesh,
+    /// so they keep referring to the same vertices.
+    pub fn append(&mut self, other: &Mesh) {
+        let base = self.positions.len() as u32;
+
+        self.positions.extend_from_slice(&other.positions);
+        self.indices.extend(other.indices.iter().map(|&i| i + base));
+    }
+
+    /// Converts [`Mesh`] into raw parts: positions and indices.
+    pub fn into_raw_parts(self) -> (Vec<[f32; 2]>, Vec<u32>) {
+        (self.positions, self.indices)
+    }
+
+    /// Returns positions of the vertices as bytes.
+    #[cfg(feature = "bytemuck")]
+    pub fn position_bytes(&self) -> &[u8] {
+        bytemuck::cast_slice(&self.positions)
+    }
+
+    /// Returns indices of the vertices as bytes.
+    #[cfg(feature = "bytemuck")]
+    pub fn index_bytes(&self) -> &[u8] {
+        bytemuck::cast_slice(&self.indices)
+    }
+}
@@ -1,3 +1,5 @@
+use crate::error::Error;
+
 pub struct Buffer<T: ocl::OclPrm> {
     inner: ocl::Buffer<T>,
     len: usize,
@@ -5,7 +7,7 @@ pub struct Buffer<T: ocl::OclPrm> {
 }
 
 impl<T: ocl::OclPrm> Buffer<T> {
-    pub fn new(queue: ocl::Queue) -> anyhow::Result<Self> {
+    pub fn new(queue: ocl::Queue) -> crate::Result<Self> {
         let inner = ocl::Buffer::<T>::builder().queue(queue).len(1).build()?;
 
         Ok(Self { inner, len: 0, cap: 1 })
@@ -23,9 +25,9 @@ impl<T: ocl::OclPrm> Buffer<T> {
         &self.inner
     }
 
-    pub fn read(&mut self, dst: &mut [T]) -> anyhow::Result<()> {
+    pub fn read(&mut self, dst: &mut [T]) -> crate::Result<()> {
         if self.is_empty() {
-            anyhow::bail!("Buffer is empty");
+            return Err(Error::InvalidArgument("Buffer is empty".to_string()));
         }
 
         self.inner.read(dst).enq()?;
@@ -33,7 +35,7 @@ impl<T: ocl::OclPrm> Buffer<T> {
         Ok(())
     }
 
-    pub fn write(&mut self, src: &[T]) -> anyhow::Result<()> {
+    pub fn write(&mut self, src: &[T]) -> crate::Result<()> {
         if src.len() > self.cap {
             self.realloc(src.len())?;
         }
@@ -44,9 +46,9 @@ impl<T: ocl::OclPrm> Buffer<T> {
         Ok(())
     }
 
-    pub fn first(&self) -> anyhow::Result<T> {
+    pub fn first(&self) -> crate::Result<T> {
         if self.is_empty() {
-            anyhow::bail!("Buffer is empty");
+            return Err(Error::InvalidArgument("Buffer is empty".to_string()));
         }
 
         let mut first = [T::default()];
@@ -55,9 +57,9 @@ impl<T: ocl::OclPrm> Buffer<T> {
         Ok(first[0])
     }
 
-    pub fn clear(&mut self) -> anyhow::Result<()> {
+    pub fn clear(&mut self) -> crate::Result<()> {
         if self.is_empty() {
-            anyhow::bail!("Buffer is empty");
+            return Err(Error::InvalidArgument("Buffer is empty".to_string()));
         }
 
         let cleared_data = vec![T::default(); self.len()];
@@ -66,7 +68,7 @@ impl<T: ocl::OclPrm> Buffer<T> {
         Ok(())
     }
 
-    fn realloc(&mut self, cap: usize) -> anyhow::Result<()> {
+    fn realloc(&mut self, cap: usize) -> crate::Result<()> {
         if cap > self.cap {
             self.inner = ocl::Buffer::<T>::builder()
                 .queue(self.inner.default_queue().unwrap().clone())
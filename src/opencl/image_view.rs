@@ -1,6 +1,6 @@
 use std::{convert, ops};
 
-use anyhow::Context;
+use crate::error::Error;
 
 #[derive(Debug, Clone)]
 pub struct ImageView<T: ocl::OclPrm> {
@@ -15,20 +15,18 @@ impl<T: ocl::OclPrm> ImageView<T> {
         ocl_image: ocl::Image<T>,
         origin: Option<D>,
         region: Option<D>,
-    ) -> anyhow::Result<Self> {
+    ) -> crate::Result<Self> {
         let full_image_region: Dims = ocl_image.dims().into();
 
         let origin: Dims = origin.map(D::into).unwrap_or([0; 3].into());
         let region: Dims = region.map(D::into).unwrap_or(full_image_region);
 
         if !is_region_suitable_for_image(&ocl_image, origin, region) {
-            anyhow::bail!(
-                "The given region {:?} which starts at the origin {:?}
+            return Err(Error::InvalidArgument(format!(
+                "The given region {:?} which starts at the origin {:?} \
                 exceeds the size of the full image {:?}",
-                region,
-                origin,
-                full_image_region
-            );
+                region, origin, full_image_region
+            )));
         }
 
         Ok(Self { ocl_image, origin, region })
@@ -46,11 +44,14 @@ impl<T: ocl::OclPrm> ImageView<T> {
         self.region
     }
 
-    pub fn set_origin<O: convert::Into<Dims>>(&mut self, origin: O) -> anyhow::Result<()> {
+    pub fn set_origin<O: convert::Into<Dims>>(&mut self, origin: O) -> crate::Result<()> {
         let origin: Dims = origin.into();
 
         if !is_region_suitable_for_image(&self.ocl_image, origin, self.region) {
-            anyhow::bail!("The given origin {:?} isn't suitable for image", origin);
+            return Err(Error::InvalidArgument(format!(
+                "The given origin {:?} isn't suitable for image",
+                origin
+            )));
         }
 
         self.origin = origin;
@@ -58,11 +59,14 @@ impl<T: ocl::OclPrm> ImageView<T> {
         Ok(())
     }
 
-    pub fn set_region<R: convert::Into<Dims>>(&mut self, region: R) -> anyhow::Result<()> {
+    pub fn set_region<R: convert::Into<Dims>>(&mut self, region: R) -> crate::Result<()> {
         let region = region.into();
 
         if !is_region_suitable_for_image(&self.ocl_image, self.origin, region) {
-            anyhow::bail!("The given region {:?} isn't suitable for image", region);
+            return Err(Error::InvalidArgument(format!(
+                "The given region {:?} isn't suitable for image",
+                region
+            )));
         }
 
         self.region = region;
@@ -70,7 +74,7 @@ impl<T: ocl::OclPrm> ImageView<T> {
         Ok(())
     }
 
-    pub fn read(&self, dst_data: &mut [T]) -> anyhow::Result<()> {
+    pub fn read(&self, dst_data: &mut [T]) -> crate::Result<()> {
         self.ocl_image
             .cmd()
             .origin(self.origin)
@@ -81,7 +85,7 @@ impl<T: ocl::OclPrm> ImageView<T> {
         Ok(())
     }
 
-    pub fn write(&self, src_data: &[T]) -> anyhow::Result<()> {
+    pub fn write(&self, src_data: &[T]) -> crate::Result<()> {
         self.ocl_image
             .cmd()
             .origin(self.origin)
@@ -92,11 +96,15 @@ impl<T: ocl::OclPrm> ImageView<T> {
         Ok(())
     }
 
-    pub fn deepcopy(&self) -> anyhow::Result<Self> {
+    pub fn deepcopy(&self) -> crate::Result<Self> {
         let queue = self
             .ocl_image
             .default_queue()
-            .context("The original image is expected to have a default queue")?
+            .ok_or_else(|| {
+                Error::InvalidArgument(
+                    "The original image is expected to have a default queue".to_string(),
+                )
+            })?
             .clone();
 
         let copied_image = ocl::Image::builder()
@@ -111,7 +119,7 @@ impl<T: ocl::OclPrm> ImageView<T> {
         Ok(Self { ocl_image: copied_image, origin: self.origin, region: self.region })
     }
 
-    pub fn to_vec(&self) -> anyhow::Result<Vec<T>> {
+    pub fn to_vec(&self) -> crate::Result<Vec<T>> {
         let mut data = vec![
             T::default();
             self.region[0]
@@ -1,5 +1,7 @@
 mod buffer;
 mod image_view;
+mod program;
 
 pub use buffer::Buffer;
 pub use image_view::{Dims, ImageView};
+pub use program::{build_program, ProgramCache, DELAUNAY_KERNELS_SRC, VORONOI_KERNELS_SRC};
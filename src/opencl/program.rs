@@ -0,0 +1,142 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use ocl::enums::{DeviceInfo, ProgramInfo, ProgramInfoResult};
+
+/// The source code of the kernels which draw Voronoi diagrams.
+pub const VORONOI_KERNELS_SRC: &str = include_str!("kernels/voronoi.cl");
+
+/// The source code of the kernels which build Delaunay triangulations.
+pub const DELAUNAY_KERNELS_SRC: &str = include_str!("kernels/delaunay.cl");
+
+/// An on-disk cache of compiled OpenCL program binaries.
+///
+/// Binaries are keyed by the source code of the program, the device and the driver version,
+/// so updating drivers or the library invalidates the cache automatically.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProgramCache {
+    dir: PathBuf,
+}
+
+impl ProgramCache {
+    /// Creates a new [`ProgramCache`] which stores binaries in the directory `dir`.
+    ///
+    /// The directory is created when the first binary is stored.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Returns the directory of the cache.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    // Returns the path to the binary of the program with the source `src`
+    // which was compiled for `device`.
+    fn binary_path(&self, device: &ocl::Device, src: &str) -> crate::Result<PathBuf> {
+        let mut hasher = StableHasher::new();
+        hasher.write(src);
+        hasher.write(&device.name()?);
+        hasher.write(&device.vendor()?);
+        for info in [DeviceInfo::Version, DeviceInfo::DriverVersion] {
+            hasher.write(&device.info(info)?.to_string());
+        }
+
+        Ok(self.dir.join(format!("{:016x}.bin", hasher.finish())))
+    }
+
+    // Loads the binary from `path` and builds a program from it.
+    fn load(&self, queue: &ocl::Queue, path: &Path) -> Option<ocl::Program> {
+        let binary = fs::read(path).ok()?;
+        let binaries = [binary.as_slice()];
+
+        ocl::Program::builder()
+            .devices(queue.device())
+            .binaries(&binaries)
+            .build(&queue.context())
+            .ok()
+    }
+
+    // Stores the binary of the `program` at `path`.
+    fn store(&self, program: &ocl::Program, path: &Path) -> crate::Result<()> {
+        let binary = match program.info(ProgramInfo::Binaries)? {
+            ProgramInfoResult::Binaries(binaries) => binaries.into_iter().next(),
+            _ => None,
+        };
+
+        if let Some(binary) = binary {
+            fs::create_dir_all(&self.dir)?;
+
+            // Write into the temporary file first, so the other processes
+            // never read a partially written binary.
+            let tmp_path = path.with_extension(format!("{}.tmp", std::process::id()));
+            fs::write(&tmp_path, binary)?;
+            fs::rename(&tmp_path, path)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Builds an OpenCL program from the source code `src` for the device of `queue`.
+///
+/// If `cache` is given, the program is loaded from the cached binary when it is possible,
+/// otherwise the compiled binary is stored in the cache.
+pub fn build_program(
+    queue: &ocl::Queue,
+    src: &str,
+    cache: Option<&ProgramCache>,
+) -> crate::Result<ocl::Program> {
+    let Some(cache) = cache else {
+        return compile_program(queue, src);
+    };
+
+    let binary_path = cache.binary_path(&queue.device(), src)?;
+    if let Some(program) = cache.load(queue, &binary_path) {
+        return Ok(program);
+    }
+
+    let program = compile_program(queue, src)?;
+    // The cache is only an optimization, so failing to store the binary isn't an error.
+    let _ = cache.store(&program, &binary_path);
+
+    Ok(program)
+}
+
+// Compiles an OpenCL program from the source code `src` for the device of `queue`.
+fn compile_program(queue: &ocl::Queue, src: &str) -> crate::Result<ocl::Program> {
+    let program = ocl::Program::builder()
+        .devices(queue.device())
+        .src(src)
+        .build(&queue.context())
+        .map_err(crate::Error::ProgramBuild)?;
+
+    Ok(program)
+}
+
+// The 64-bit FNV-1a hash, which unlike `DefaultHasher` doesn't change between Rust releases,
+// so the keys of the cache stay valid after toolchain upgrades.
+struct StableHasher {
+    state: u64,
+}
+
+impl StableHasher {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    fn new() -> Self {
+        Self { state: Self::OFFSET_BASIS }
+    }
+
+    // Hashes `s` prefixed by its length, so consecutive strings can't be confused.
+    fn write(&mut self, s: &str) {
+        let len = s.len() as u64;
+        len.to_le_bytes().iter().chain(s.as_bytes()).for_each(|&byte| {
+            self.state = (self.state ^ byte as u64).wrapping_mul(Self::PRIME);
+        });
+    }
+
+    fn finish(&self) -> u64 {
+        self.state
+    }
+}
@@ -0,0 +1,75 @@
+use crate::point::Point;
+
+// The maximal depth of the curve subdivision, so that curves with non-finite
+// or degenerate control points produce at most 2^16 segments.
+const MAX_SUBDIVISION_DEPTH: u32 = 16;
+
+// Tesselates a quadric curve, so that the distance between the curve and the line segments
+// doesn't exceed `flatness`.
+//
+// Adapted from stb_truetype: https://github.com/nothings/stb
+pub(crate) fn tesselate_quadric_curve(
+    p: (Point, Point, Point),
+    flatness: f32,
+    points: &mut Vec<Point>,
+) {
+    // Parts of the curve which are waiting for tesselation with their subdivision depths.
+    // The first part of the curve is on the top of the stack.
+    let mut parts = vec![(p, 0)];
+
+    while let Some((p, depth)) = parts.pop() {
+        let mp01 = p.0.midpoint(&p.1);
+        let mp12 = p.1.midpoint(&p.2);
+        let midpoint = mp01.midpoint(&mp12);
+        let distance_squared = p.0.midpoint(&p.2).distance_squared(&midpoint);
+
+        if distance_squared > flatness * flatness && depth < MAX_SUBDIVISION_DEPTH {
+            let p0 = (p.0, mp01, midpoint.clone());
+            let p1 = (midpoint, mp12, p.2);
+
+            parts.push((p1, depth + 1));
+            parts.push((p0, depth + 1));
+        } else {
+            points.push(p.2);
+        }
+    }
+}
+
+// Tesselates a cubic curve, so that the distance between the curve and the line segments
+// doesn't exceed `flatness`.
+//
+// Adapted from stb_truetype: https://github.com/nothings/stb
+pub(crate) fn tesselate_cubic_curve(
+    p: (Point, Point, Point, Point),
+    flatness: f32,
+    points: &mut Vec<Point>,
+) {
+    // Parts of the curve which are waiting for tesselation with their subdivision depths.
+    // The first part of the curve is on the top of the stack.
+    let mut parts = vec![(p, 0)];
+
+    while let Some((p, depth)) = parts.pop() {
+        let longlen = p.0.distance(&p.1) + p.1.distance(&p.2) + p.2.distance(&p.3);
+        let shortlen = p.0.distance(&p.3);
+        let flatness_squared = longlen.powi(2) - shortlen.powi(2);
+
+        let mp01 = p.0.midpoint(&p.1);
+        let mp12 = p.1.midpoint(&p.2);
+        let mp23 = p.2.midpoint(&p.3);
+
+        let mp012 = mp01.midpoint(&mp12);
+        let mp123 = mp12.midpoint(&mp23);
+
+        let midpoint = mp012.midpoint(&mp123);
+
+        if flatness_squared > flatness * flatness && depth < MAX_SUBDIVISION_DEPTH {
+            let p0 = (p.0, mp01, mp012, midpoint.clone());
+            let p1 = (midpoint, mp123, mp23, p.3);
+
+            parts.push((p1, depth + 1));
+            parts.push((p0, depth + 1));
+        } else {
+            points.push(p.3);
+        }
+    }
+}
@@ -0,0 +1,441 @@
+//! Vector paths which can be triangulated like glyphs.
+//!
+//! A [`Path`] is built from lines, curves and arcs with a [`PathBuilder`]
+//! or parsed from the SVG path data:
+//!
+//! ```
+//! use vdtfont::{Path, PathBuilder};
+//!
+//! // A square with a triangular hole.
+//! let mut builder = PathBuilder::new(0.1).unwrap();
+//! builder.move_to(0.0, 0.0);
+//! builder.line_to(10.0, 0.0);
+//! builder.line_to(10.0, 10.0);
+//! builder.line_to(0.0, 10.0);
+//! builder.close();
+//! builder.move_to(2.0, 2.0);
+//! builder.line_to(5.0, 8.0);
+//! builder.line_to(8.0, 2.0);
+//! builder.close();
+//! let path = builder.build();
+//!
+//! // The same path in the SVG syntax.
+//! let svg_path = Path::from_svg("M0 0H10V10H0Z m2 2 3 6 3-6z", 0.1).unwrap();
+//! assert_eq!(path, svg_path);
+//! ```
+//!
+//! Paths are triangulated with [`Triangulator::triangulate_path`].
+//!
+//! [`Triangulator::triangulate_path`]: crate::triangulator::Triangulator::triangulate_path
+
+pub(crate) mod curve;
+mod svg;
+
+use curve::{tesselate_cubic_curve, tesselate_quadric_curve};
+
+use crate::error::Error;
+use crate::point::{Point, PointId};
+
+use std::f32::consts::{FRAC_PI_2, PI};
+
+/// A path which consists of closed contours of line segments.
+///
+/// Curves and arcs are tesselated when the path is built.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Path {
+    points: Vec<Point>,
+    corners: Vec<bool>,
+}
+
+impl Path {
+    /// Parses the SVG path data, e.g. the `d` attribute of the `<path>` element.
+    ///
+    /// All commands of the SVG path syntax are supported together with their relative forms.
+    /// Curves and arcs are tesselated with the given `flatness` in path units,
+    /// see [`PathBuilder::new`].
+    pub fn from_svg(data: &str, flatness: f32) -> crate::Result<Self> {
+        let mut builder = PathBuilder::new(flatness)?;
+        svg::parse(data, &mut builder)?;
+
+        Ok(builder.build())
+    }
+
+    /// Returns points of the path.
+    ///
+    /// Every point is linked with the previous one in its contour, and the first point
+    /// of the contour is linked with the last one.
+    pub fn points(&self) -> &[Point] {
+        &self.points
+    }
+
+    /// Returns flags which are set for the points at which the outline has sharp corners.
+    ///
+    /// The flags are determined by the tangents of the lines and curves which meet
+    /// at the points, so the points which only split curves are never corners.
+    pub fn corners(&self) -> &[bool] {
+        &self.corners
+    }
+
+    /// Checks if the path doesn't contain any contours.
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+}
+
+/// A builder of [`Path`].
+///
+/// Every contour is closed when the path is built, even if [`PathBuilder::close`]
+/// isn't called, because only closed contours enclose an area.
+/// Contours which consist of less than three points are skipped.
+///
+/// The builder implements [`ttfp::OutlineBuilder`], so outlines of glyphs can be
+/// converted into paths too.
+#[derive(Debug, Clone)]
+pub struct PathBuilder {
+    points: Vec<Point>,
+    corners: Vec<bool>,
+
+    // The index of the first point of the current contour.
+    contour_start: usize,
+    // If the current contour accepts new segments.
+    is_open: bool,
+
+    current: [f32; 2],
+    subpath_start: [f32; 2],
+
+    // The tangents at the start of the current contour and at the end of its last segment.
+    first_tangent: Option<[f32; 2]>,
+    last_tangent: [f32; 2],
+
+    flatness: f32,
+}
+
+impl PathBuilder {
+    /// Creates a new [`PathBuilder`] which tesselates curves and arcs with the given `flatness`.
+    ///
+    /// The flatness is the maximal distance in path units between a curve and the line segments
+    /// which approximate it. It must be positive.
+    pub fn new(flatness: f32) -> crate::Result<Self> {
+        if !(flatness.is_finite() && flatness > 0.0) {
+            return Err(Error::InvalidArgument(format!(
+                "The flatness {flatness} must be positive"
+            )));
+        }
+
+        Ok(Self {
+            points: Vec::new(),
+            corners: Vec::new(),
+            contour_start: 0,
+            is_open: false,
+            current: [0.0; 2],
+            subpath_start: [0.0; 2],
+            first_tangent: None,
+            last_tangent: [0.0; 2],
+            flatness,
+        })
+    }
+
+    /// Returns the current point, i.e. the end of the last segment.
+    pub fn current(&self) -> [f32; 2] {
+        self.current
+    }
+
+    /// Starts a new contour at `(x; y)`.
+    pub fn move_to(&mut self, x: f32, y: f32) {
+        self.finish_contour();
+
+        self.contour_start = self.points.len();
+        self.points.push(Point::new(x, y));
+        self.corners.push(false);
+        self.is_open = true;
+        self.first_tangent = None;
+
+        self.current = [x, y];
+        self.subpath_start = [x, y];
+    }
+
+    /// Adds a line segment from the current point to `(x; y)`.
+    pub fn line_to(&mut self, x: f32, y: f32) {
+        self.open();
+
+        let p0 = self.current;
+        let Some(tangent) = tangent([p0, [x, y]]) else {
+            return;
+        };
+
+        self.begin_segment(tangent);
+        self.push_point(x, y);
+        self.last_tangent = tangent;
+    }
+
+    /// Adds a quadratic Bézier curve from the current point to `(x; y)`
+    /// with the control point `(x1; y1)`.
+    pub fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        self.open();
+
+        let control_points = [self.current, [x1, y1], [x, y]];
+        let (Some(start_tangent), Some(end_tangent)) =
+            (tangent(control_points), tangent(reversed(control_points)))
+        else {
+            return;
+        };
+        self.begin_segment(start_tangent);
+
+        let p0 = Point::new(self.current[0], self.current[1]);
+        let mut points = Vec::new();
+        tesselate_quadric_curve(
+            (p0, Point::new(x1, y1), Point::new(x, y)),
+            self.flatness,
+            &mut points,
+        );
+
+        points.iter().for_each(|p| self.push_point(p.x(), p.y()));
+        self.last_tangent = end_tangent.map(|t| -t);
+    }
+
+    /// Adds a cubic Bézier curve from the current point to `(x; y)`
+    /// with the control points `(x1; y1)` and `(x2; y2)`.
+    pub fn cubic_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        self.open();
+
+        let control_points = [self.current, [x1, y1], [x2, y2], [x, y]];
+        let (Some(start_tangent), Some(end_tangent)) =
+            (tangent(control_points), tangent(reversed(control_points)))
+        else {
+            return;
+        };
+        self.begin_segment(start_tangent);
+
+        let p0 = Point::new(self.current[0], self.current[1]);
+        let mut points = Vec::new();
+        tesselate_cubic_curve(
+            (p0, Point::new(x1, y1), Point::new(x2, y2), Point::new(x, y)),
+            self.flatness,
+            &mut points,
+        );
+
+        points.iter().for_each(|p| self.push_point(p.x(), p.y()));
+        self.last_tangent = end_tangent.map(|t| -t);
+    }
+
+    /// Adds an elliptical arc from the current point to `(x; y)`.
+    ///
+    /// The arguments have the same meaning as in the SVG arc command: the ellipse has
+    /// the radii `rx` and `ry` and is rotated by `x_axis_rotation` degrees, while
+    /// `large_arc` and `sweep` select one of four arcs which connect the points.
+    /// The radii are scaled up if they are too small to connect the points, and the arc
+    /// is a line segment if any radius is zero.
+    #[allow(clippy::too_many_arguments)]
+    pub fn arc_to(
+        &mut self,
+        rx: f32,
+        ry: f32,
+        x_axis_rotation: f32,
+        large_arc: bool,
+        sweep: bool,
+        x: f32,
+        y: f32,
+    ) {
+        let [x0, y0] = self.current;
+        if x0 == x && y0 == y {
+            return;
+        }
+
+        let (mut rx, mut ry) = (rx.abs(), ry.abs());
+        if rx == 0.0 || ry == 0.0 {
+            self.line_to(x, y);
+            return;
+        }
+
+        // Convert the endpoint parameterization into the center parameterization.
+        // See https://www.w3.org/TR/SVG11/implnote.html#ArcConversionEndpointToCenter
+        let (sin, cos) = x_axis_rotation.to_radians().sin_cos();
+        let (dx, dy) = ((x0 - x) / 2.0, (y0 - y) / 2.0);
+        let x1 = cos * dx + sin * dy;
+        let y1 = -sin * dx + cos * dy;
+
+        let lambda = (x1 / rx).powi(2) + (y1 / ry).powi(2);
+        if lambda > 1.0 {
+            rx *= lambda.sqrt();
+            ry *= lambda.sqrt();
+        }
+
+        let numerator = (rx * ry).powi(2) - (rx * y1).powi(2) - (ry * x1).powi(2);
+        let denominator = (rx * y1).powi(2) + (ry * x1).powi(2);
+        let sign = if large_arc != sweep { 1.0 } else { -1.0 };
+        let coefficient = sign * (numerator / denominator).max(0.0).sqrt();
+
+        let cx1 = coefficient * rx * y1 / ry;
+        let cy1 = -coefficient * ry * x1 / rx;
+        let cx = cos * cx1 - sin * cy1 + (x0 + x) / 2.0;
+        let cy = sin * cx1 + cos * cy1 + (y0 + y) / 2.0;
+
+        let start_angle = f32::atan2((y1 - cy1) / ry, (x1 - cx1) / rx);
+        let end_angle = f32::atan2((-y1 - cy1) / ry, (-x1 - cx1) / rx);
+        let mut sweep_angle = end_angle - start_angle;
+        if sweep && sweep_angle < 0.0 {
+            sweep_angle += 2.0 * PI;
+        } else if !sweep && sweep_angle > 0.0 {
+            sweep_angle -= 2.0 * PI;
+        }
+
+        // Approximate the arc with cubic curves which span at most a quarter of the ellipse.
+        let segments = (sweep_angle.abs() / FRAC_PI_2).ceil().max(1.0) as usize;
+        let delta = sweep_angle / segments as f32;
+        let k = 4.0 / 3.0 * (delta / 4.0).tan();
+
+        // Maps a point of the unit circle onto the ellipse.
+        let map = |ux: f32, uy: f32| {
+            [cx + rx * cos * ux - ry * sin * uy, cy + rx * sin * ux + ry * cos * uy]
+        };
+
+        for i in 0..segments {
+            let a0 = start_angle + delta * i as f32;
+            let a1 = a0 + delta;
+            let (sin0, cos0) = a0.sin_cos();
+            let (sin1, cos1) = a1.sin_cos();
+
+            let c1 = map(cos0 - k * sin0, sin0 + k * cos0);
+            let c2 = map(cos1 + k * sin1, sin1 - k * cos1);
+            // The last curve ends exactly at the endpoint.
+            let end = if i + 1 == segments { [x, y] } else { map(cos1, sin1) };
+
+            self.cubic_to(c1[0], c1[1], c2[0], c2[1], end[0], end[1]);
+        }
+    }
+
+    /// Closes the current contour, so the current point becomes its first point.
+    pub fn close(&mut self) {
+        self.finish_contour();
+        self.current = self.subpath_start;
+    }
+
+    /// Builds the [`Path`] closing the current contour.
+    pub fn build(mut self) -> Path {
+        self.finish_contour();
+
+        Path { points: self.points, corners: self.corners }
+    }
+
+    // Starts a new contour at the current point if there isn't an open one,
+    // e.g. when segments follow the closed contour.
+    fn open(&mut self) {
+        if !self.is_open {
+            let [x, y] = self.subpath_start;
+            self.move_to(x, y);
+        }
+    }
+
+    // Checks if the segment which starts with `tangent` forms a corner
+    // with the previous one.
+    fn begin_segment(&mut self, tangent: [f32; 2]) {
+        if self.first_tangent.is_none() {
+            self.first_tangent = Some(tangent);
+        } else {
+            *self.corners.last_mut().unwrap() = is_corner(self.last_tangent, tangent);
+        }
+    }
+
+    // Adds the point to the current contour skipping the repeated ones.
+    fn push_point(&mut self, x: f32, y: f32) {
+        if self.current != [x, y] {
+            let previous = self.points.len() as PointId - 1;
+            self.points.push(Point::with_previous(x, y, previous));
+            self.corners.push(false);
+        }
+
+        self.current = [x, y];
+    }
+
+    // Links the first point of the current contour with the last one.
+    fn finish_contour(&mut self) {
+        if !self.is_open {
+            return;
+        }
+        self.is_open = false;
+
+        // The contour can return to its first point explicitly.
+        let contour = &self.points[self.contour_start..];
+        let [first, last] = [contour.first(), contour.last()].map(|p| p.unwrap().coords());
+        let is_returned = contour.len() > 1 && first == last;
+        if is_returned {
+            self.points.pop();
+            self.corners.pop();
+        }
+
+        // Check the corners at the ends of the closing segment.
+        if let Some(first_tangent) = self.first_tangent {
+            let last_tangent = self.last_tangent;
+            let closing_tangent = tangent([[last[0], last[1]], [first[0], first[1]]]);
+
+            match closing_tangent.filter(|_| !is_returned) {
+                Some(closing_tangent) => {
+                    *self.corners.last_mut().unwrap() = is_corner(last_tangent, closing_tangent);
+                    self.corners[self.contour_start] = is_corner(closing_tangent, first_tangent);
+                }
+                None => self.corners[self.contour_start] = is_corner(last_tangent, first_tangent),
+            }
+        }
+
+        if self.points.len() - self.contour_start < 3 {
+            self.points.truncate(self.contour_start);
+            self.corners.truncate(self.contour_start);
+        } else {
+            let last = self.points.len() as PointId - 1;
+            self.points[self.contour_start].set_previous_in_outline(last);
+        }
+    }
+}
+
+// The maximal angle in radians by which the direction of a segment can deviate from
+// the direction of the previous one, so that they are still joined smoothly.
+const MAX_SMOOTH_DEVIATION: f32 = 8.0 * PI / 180.0;
+
+// Checks if the segments with the directions `a` and `b` form a corner.
+fn is_corner(a: [f32; 2], b: [f32; 2]) -> bool {
+    let (length_a, length_b) = (a[0].hypot(a[1]), b[0].hypot(b[1]));
+    let dot = (a[0] * b[0] + a[1] * b[1]) / (length_a * length_b);
+    let cross = (a[0] * b[1] - a[1] * b[0]) / (length_a * length_b);
+
+    // The cross product is the sine of the deviation, which is below the right angle
+    // if the dot product is positive.
+    dot <= 0.0 || cross.abs() > MAX_SMOOTH_DEVIATION.sin()
+}
+
+// Returns the direction of the curve with the given control points at its start.
+//
+// The direction is given by the first control point which differs from the start one.
+fn tangent<const N: usize>(control_points: [[f32; 2]; N]) -> Option<[f32; 2]> {
+    let [x0, y0] = control_points[0];
+    control_points[1..]
+        .iter()
+        .map(|&[x, y]| [x - x0, y - y0])
+        .find(|&d| d != [0.0; 2])
+}
+
+fn reversed<const N: usize>(mut control_points: [[f32; 2]; N]) -> [[f32; 2]; N] {
+    control_points.reverse();
+    control_points
+}
+
+impl ttfp::OutlineBuilder for PathBuilder {
+    fn move_to(&mut self, x: f32, y: f32) {
+        PathBuilder::move_to(self, x, y);
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        PathBuilder::line_to(self, x, y);
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        PathBuilder::quad_to(self, x1, y1, x, y);
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        PathBuilder::cubic_to(self, x1, y1, x2, y2, x, y);
+    }
+
+    fn close(&mut self) {
+        PathBuilder::close(self);
+    }
+}
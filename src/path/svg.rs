@@ -0,0 +1,236 @@
+use super::PathBuilder;
+
+use crate::error::Error;
+
+// Parses the SVG path data and passes its segments to the `builder`.
+//
+// See https://www.w3.org/TR/SVG11/paths.html#PathDataBNF
+pub(super) fn parse(data: &str, builder: &mut PathBuilder) -> crate::Result<()> {
+    let mut parser = Parser { data: data.as_bytes(), offset: 0 };
+
+    // The last command which is repeated if its arguments follow.
+    let mut command: Option<u8> = None;
+    // The last control points of cubic and quadratic curves which are reflected
+    // by the smooth curve commands.
+    let mut cubic_control: Option<[f32; 2]> = None;
+    let mut quad_control: Option<[f32; 2]> = None;
+
+    loop {
+        parser.skip_separators();
+        let Some(byte) = parser.peek() else {
+            break;
+        };
+
+        let offset = parser.offset;
+        let current_command = if byte.is_ascii_alphabetic() {
+            parser.offset += 1;
+            byte
+        } else {
+            match command {
+                Some(b'Z' | b'z') => return Err(parser.error("Unexpected number after Z")),
+                Some(command) => command,
+                None => return Err(parser.error("Expected a move command")),
+            }
+        };
+
+        if command.is_none() && !matches!(current_command, b'M' | b'm') {
+            return Err(Error::PathParsing {
+                offset,
+                message: "The path must start with a move command".to_owned(),
+            });
+        }
+
+        let origin =
+            if current_command.is_ascii_lowercase() { builder.current() } else { [0.0; 2] };
+        let current = builder.current();
+
+        let (mut next_cubic_control, mut next_quad_control) = (None, None);
+        command = Some(current_command);
+
+        match current_command.to_ascii_uppercase() {
+            b'M' => {
+                let [x, y] = parser.point(origin)?;
+                builder.move_to(x, y);
+
+                // The following pairs of coordinates are line segments.
+                command = Some(if current_command == b'm' { b'l' } else { b'L' });
+            }
+            b'L' => {
+                let [x, y] = parser.point(origin)?;
+                builder.line_to(x, y);
+            }
+            b'H' => {
+                let x = parser.number()? + origin[0];
+                builder.line_to(x, current[1]);
+            }
+            b'V' => {
+                let y = parser.number()? + origin[1];
+                builder.line_to(current[0], y);
+            }
+            b'C' => {
+                let c1 = parser.point(origin)?;
+                let c2 = parser.point(origin)?;
+                let [x, y] = parser.point(origin)?;
+                builder.cubic_to(c1[0], c1[1], c2[0], c2[1], x, y);
+
+                next_cubic_control = Some(c2);
+            }
+            b'S' => {
+                let c1 = cubic_control.map_or(current, |c| reflect(c, current));
+                let c2 = parser.point(origin)?;
+                let [x, y] = parser.point(origin)?;
+                builder.cubic_to(c1[0], c1[1], c2[0], c2[1], x, y);
+
+                next_cubic_control = Some(c2);
+            }
+            b'Q' => {
+                let c1 = parser.point(origin)?;
+                let [x, y] = parser.point(origin)?;
+                builder.quad_to(c1[0], c1[1], x, y);
+
+                next_quad_control = Some(c1);
+            }
+            b'T' => {
+                let c1 = quad_control.map_or(current, |c| reflect(c, current));
+                let [x, y] = parser.point(origin)?;
+                builder.quad_to(c1[0], c1[1], x, y);
+
+                next_quad_control = Some(c1);
+            }
+            b'A' => {
+                let rx = parser.number()?;
+                let ry = parser.number()?;
+                let x_axis_rotation = parser.number()?;
+                let large_arc = parser.flag()?;
+                let sweep = parser.flag()?;
+                let [x, y] = parser.point(origin)?;
+                builder.arc_to(rx, ry, x_axis_rotation, large_arc, sweep, x, y);
+            }
+            b'Z' => builder.close(),
+            _ => {
+                return Err(Error::PathParsing {
+                    offset,
+                    message: format!("Unknown command {}", current_command as char),
+                })
+            }
+        }
+
+        cubic_control = next_cubic_control;
+        quad_control = next_quad_control;
+    }
+
+    Ok(())
+}
+
+// Reflects the control point `c` about the point `p`.
+fn reflect(c: [f32; 2], p: [f32; 2]) -> [f32; 2] {
+    [2.0 * p[0] - c[0], 2.0 * p[1] - c[1]]
+}
+
+// A tokenizer of the SVG path data.
+struct Parser<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<u8> {
+        self.data.get(self.offset).copied()
+    }
+
+    // Skips whitespaces and commas between commands and numbers.
+    fn skip_separators(&mut self) {
+        while self.peek().is_some_and(|b| b.is_ascii_whitespace() || b == b',') {
+            self.offset += 1;
+        }
+    }
+
+    // Parses a point whose coordinates are relative to `origin`.
+    fn point(&mut self, origin: [f32; 2]) -> crate::Result<[f32; 2]> {
+        let x = self.number()?;
+        let y = self.number()?;
+
+        Ok([x + origin[0], y + origin[1]])
+    }
+
+    // Parses a number, e.g. `-1.5e3` or `.5`.
+    //
+    // Numbers don't need separators if they can be told apart, e.g. `1.5.5` is `1.5` and `.5`.
+    fn number(&mut self) -> crate::Result<f32> {
+        self.skip_separators();
+        let start = self.offset;
+
+        if matches!(self.peek(), Some(b'+' | b'-')) {
+            self.offset += 1;
+        }
+
+        let integer_digits = self.skip_digits();
+        let mut fraction_digits = 0;
+        if self.peek() == Some(b'.') {
+            self.offset += 1;
+            fraction_digits = self.skip_digits();
+        }
+
+        if integer_digits + fraction_digits == 0 {
+            self.offset = start;
+            return Err(self.error("Expected a number"));
+        }
+
+        // The exponent is parsed only if it has digits.
+        if matches!(self.peek(), Some(b'e' | b'E')) {
+            let mantissa_end = self.offset;
+            self.offset += 1;
+            if matches!(self.peek(), Some(b'+' | b'-')) {
+                self.offset += 1;
+            }
+
+            if self.skip_digits() == 0 {
+                self.offset = mantissa_end;
+            }
+        }
+
+        // The slice consists of ASCII characters, so it is valid UTF-8.
+        let number = std::str::from_utf8(&self.data[start..self.offset]).unwrap();
+        let number = number.parse::<f32>().map_err(|err| Error::PathParsing {
+            offset: start,
+            message: format!("Invalid number {number}: {err}"),
+        })?;
+
+        if !number.is_finite() {
+            return Err(Error::PathParsing {
+                offset: start,
+                message: format!("The number {number} isn't finite"),
+            });
+        }
+
+        Ok(number)
+    }
+
+    // Parses a flag of the arc command which is `0` or `1`.
+    fn flag(&mut self) -> crate::Result<bool> {
+        self.skip_separators();
+
+        let flag = match self.peek() {
+            Some(b'0') => false,
+            Some(b'1') => true,
+            _ => return Err(self.error("Expected a flag")),
+        };
+        self.offset += 1;
+
+        Ok(flag)
+    }
+
+    // Skips ASCII digits and returns their number.
+    fn skip_digits(&mut self) -> usize {
+        let start = self.offset;
+        while self.peek().is_some_and(|b| b.is_ascii_digit()) {
+            self.offset += 1;
+        }
+
+        self.offset - start
+    }
+
+    fn error(&self, message: &str) -> Error {
+        Error::PathParsing { offset: self.offset, message: message.to_owned() }
+    }
+}
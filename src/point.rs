@@ -1,5 +1,7 @@
 use crate::delaunay::{DelaunayTriangle, DelaunayTriangleHandle, TriangleId};
+use crate::error::Error;
 use crate::ocl::prm::Float2;
+use crate::predicates;
 
 use std::fmt;
 use std::hash;
@@ -138,6 +140,16 @@ impl Point {
 }
 
 /// A handle of the [`Point`] which is used by [`Arena`].
+///
+/// # Panics
+///
+/// Accessors panic if the point was removed from the arena or is borrowed mutably
+/// elsewhere at the same time. Handles are only created by the arena for existing points,
+/// points are never removed while their handles are alive and the triangulation never
+/// holds a borrow across calls, so the panic is unreachable unless the arena is misused.
+/// Accessors of the triangle fan also panic if the handle was created without the arena
+/// of triangles. The `try_` accessors, e.g. [`PointHandle::try_coords`], return
+/// [`Error::Triangulation`] instead.
 #[derive(Clone, Copy)]
 pub struct PointHandle<'arena> {
     raw: RawHandle<'arena, Point>,
@@ -147,27 +159,34 @@ pub struct PointHandle<'arena> {
 impl<'arena> PointHandle<'arena> {
     /// Returns the `x` coordinate of the point.
     pub fn x(&self) -> f32 {
-        self.get().unwrap().coords[0]
+        self.get().expect("Can't get the point").coords[0]
     }
 
     /// Returns the `y` coordinate of the point.
     pub fn y(&self) -> f32 {
-        self.get().unwrap().coords[1]
+        self.get().expect("Can't get the point").coords[1]
     }
 
     /// Returns coordinates of the point.
     pub fn coords(&self) -> Float2 {
-        self.get().unwrap().coords
+        self.get().expect("Can't get the point").coords
+    }
+
+    /// Returns coordinates of the point.
+    ///
+    /// Returns [`Error::Triangulation`] if the point can't be borrowed, see [`PointHandle`].
+    pub fn try_coords(&self) -> crate::Result<Float2> {
+        Ok(self.get().map_err(|_| self.stale_error())?.coords)
     }
 
     /// Sets the coordinates of the point.
     pub fn set_coords(&mut self, coords: Float2) {
-        self.get_mut().unwrap().coords = coords;
+        self.get_mut().expect("Can't get the point").coords = coords;
     }
 
     /// Checks if the point is bounding.
     pub fn is_bounding(&self) -> bool {
-        self.get().unwrap().is_bounding
+        self.get().expect("Can't get the point").is_bounding
     }
 
     /// Returns the previous point in the outline.
@@ -177,37 +196,66 @@ impl<'arena> PointHandle<'arena> {
         self.arena().handle(this.previous_in_outline().into(), self.triangles)
     }
 
+    /// Returns the previous point in the outline.
+    ///
+    /// Returns [`Error::Triangulation`] if the point can't be borrowed, see [`PointHandle`].
+    pub fn try_previous_in_outline(&self) -> crate::Result<PointHandle<'arena>> {
+        let previous = self.get().map_err(|_| self.stale_error())?.previous_in_outline();
+
+        Ok(self.arena().handle(previous.into(), self.triangles))
+    }
+
     /// Returns the triangle fan of the point.
     pub fn triangle_fan(&self) -> SmallVec<[DelaunayTriangleHandle<'arena>; 6]> {
         self.get()
-            .unwrap()
+            .expect("Can't get the point")
             .triangle_fan
             .iter()
             .copied()
             .map(|i| i.into())
-            .map(|i| self.triangles.unwrap().handle(i, self.arena()))
+            .map(|i| {
+                self.triangles
+                    .expect("The handle has no triangles")
+                    .handle(i, self.arena())
+            })
             .collect()
     }
 
+    /// Returns the triangle fan of the point.
+    ///
+    /// Returns [`Error::Triangulation`] if the point can't be borrowed or the handle
+    /// was created without the arena of triangles.
+    pub fn try_triangle_fan(
+        &self,
+    ) -> crate::Result<SmallVec<[DelaunayTriangleHandle<'arena>; 6]>> {
+        let index: PointId = self.index().into();
+        let triangles = self.triangles.ok_or_else(|| {
+            Error::Triangulation(format!("The handle of the point {index} has no triangles"))
+        })?;
+        let this = self.get().map_err(|_| self.stale_error())?;
+
+        Ok(this.triangle_fan.iter().map(|&i| triangles.handle(i.into(), self.arena())).collect())
+    }
+
     /// Sets the triangle fan of the point.
     pub fn set_triangle_fan(
         &mut self,
         triangle_fan: SmallVec<[DelaunayTriangleHandle<'arena>; 6]>,
     ) {
-        self.get_mut().unwrap().triangle_fan =
+        self.get_mut().expect("Can't get the point").triangle_fan =
             triangle_fan.into_iter().map(|h| h.index().into()).collect();
     }
 
     /// Adds `triangle` to the triangle fan of the point.
     pub fn add_triangle_to_fan(&self, triangle: DelaunayTriangleHandle) {
-        let triangle_fan = &mut self.get_mut().unwrap().triangle_fan;
+        let triangle_fan = &mut self.get_mut().expect("Can't get the point").triangle_fan;
 
         triangle_fan.push(triangle.index().into());
     }
 
     /// Removes `triangle` from the triangle fan of the point.
     pub fn remove_triangle_from_fan(&self, triangle_index: Index) {
-        let triangle_fan = &mut self.get_mut().unwrap().triangle_fan;
+        let triangle_fan = &mut self.get_mut().expect("Can't get the point").triangle_fan;
         let position = triangle_fan.iter().position(|t| *t == triangle_index.into());
 
         if let Some(position) = position {
@@ -221,12 +269,18 @@ impl<'arena> PointHandle<'arena> {
     }
 
     /// Calculates skew product of `self` and `other` points
-    /// with the origin of coordinates at `origin`
+    /// with the origin of coordinates at `origin`.
+    ///
+    /// The product is calculated by [`orient2d`] and rounded to `f32`,
+    /// use [`orient2d`] itself to get the exact sign for nearly collinear points.
+    ///
+    /// [`orient2d`]: crate::predicates::orient2d
     pub fn skew_product(&self, origin: &Self, other: &Self) -> f32 {
-        let a = Point::new(self.x() - origin.x(), self.y() - origin.y());
-        let b = Point::new(other.x() - origin.x(), other.y() - origin.y());
-
-        a.x() * b.y() - a.y() * b.x()
+        predicates::orient2d(
+            predicates::coords(origin),
+            predicates::coords(self),
+            predicates::coords(other),
+        ) as f32
     }
 
     /// Returns the distance between two points.
@@ -236,6 +290,12 @@ impl<'arena> PointHandle<'arena> {
 
         this.distance(&other)
     }
+
+    // Returns the error which is reported for the point which can't be borrowed.
+    fn stale_error(&self) -> Error {
+        let index: PointId = self.index().into();
+        Error::Triangulation(format!("The point {index} can't be borrowed"))
+    }
 }
 
 impl<'arena> Handle<'arena> for PointHandle<'arena> {
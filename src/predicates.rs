@@ -0,0 +1,206 @@
+//! Robust geometric predicates.
+//!
+//! The predicates are adaptive: they are evaluated in floating-point arithmetic first
+//! and fall back to exact arithmetic only if the sign of the result can't be determined
+//! by the approximation. The exact arithmetic is based on floating-point expansions from
+//! ["Adaptive Precision Floating-Point Arithmetic and Fast Robust Geometric Predicates"](https://www.cs.cmu.edu/~quake/robust.html)
+//! by Jonathan Richard Shewchuk.
+//!
+//! ```
+//! use vdtfont::predicates::{incircle, orient2d};
+//!
+//! // The points are counterclockwise.
+//! assert!(orient2d([0.0, 0.0], [1.0, 0.0], [0.0, 1.0]) > 0.0);
+//! // The points are collinear.
+//! assert_eq!(orient2d([0.0, 0.0], [1.0, 1.0], [3.0, 3.0]), 0.0);
+//! // The last point is inside the circle.
+//! assert!(incircle([0.0, 0.0], [1.0, 0.0], [0.0, 1.0], [0.5, 0.5]) > 0.0);
+//! ```
+
+use crate::point::PointHandle;
+
+// Half of the machine epsilon which bounds the relative error of floating-point operations.
+const EPSILON: f64 = f64::EPSILON / 2.0;
+
+// Bounds of the relative error of the approximations of the determinants.
+const ORIENT2D_ERROR_BOUND: f64 = (3.0 + 16.0 * EPSILON) * EPSILON;
+const INCIRCLE_ERROR_BOUND: f64 = (10.0 + 96.0 * EPSILON) * EPSILON;
+
+/// Returns a positive value if `a`, `b` and `c` are in counterclockwise order,
+/// a negative value if they are in clockwise order and zero if they are collinear.
+///
+/// The orientation is given for the coordinate system with the `y` axis directed up.
+/// The result approximates twice the signed area of the triangle and its sign is exact.
+pub fn orient2d(a: [f64; 2], b: [f64; 2], c: [f64; 2]) -> f64 {
+    let det_left = (a[0] - c[0]) * (b[1] - c[1]);
+    let det_right = (a[1] - c[1]) * (b[0] - c[0]);
+    let det = det_left - det_right;
+
+    let error_bound = ORIENT2D_ERROR_BOUND * (det_left.abs() + det_right.abs());
+    if det.abs() >= error_bound {
+        return det;
+    }
+
+    let acx = two_diff(a[0], c[0]);
+    let acy = two_diff(a[1], c[1]);
+    let bcx = two_diff(b[0], c[0]);
+    let bcy = two_diff(b[1], c[1]);
+
+    let det = expansion_diff(&expansion_product(&acx, &bcy), &expansion_product(&acy, &bcx));
+
+    estimate(&det)
+}
+
+/// Returns a positive value if `d` is inside the circle through `a`, `b` and `c`,
+/// a negative value if it is outside and zero if the points are cocircular.
+///
+/// The points `a`, `b` and `c` must be in counterclockwise order according to [`orient2d`],
+/// otherwise the sign of the result is reversed. The sign of the result is exact.
+pub fn incircle(a: [f64; 2], b: [f64; 2], c: [f64; 2], d: [f64; 2]) -> f64 {
+    let adx = a[0] - d[0];
+    let ady = a[1] - d[1];
+    let bdx = b[0] - d[0];
+    let bdy = b[1] - d[1];
+    let cdx = c[0] - d[0];
+    let cdy = c[1] - d[1];
+
+    let bdxcdy = bdx * cdy;
+    let cdxbdy = cdx * bdy;
+    let alift = adx * adx + ady * ady;
+
+    let cdxady = cdx * ady;
+    let adxcdy = adx * cdy;
+    let blift = bdx * bdx + bdy * bdy;
+
+    let adxbdy = adx * bdy;
+    let bdxady = bdx * ady;
+    let clift = cdx * cdx + cdy * cdy;
+
+    let det = alift * (bdxcdy - cdxbdy) + blift * (cdxady - adxcdy) + clift * (adxbdy - bdxady);
+
+    let permanent = (bdxcdy.abs() + cdxbdy.abs()) * alift
+        + (cdxady.abs() + adxcdy.abs()) * blift
+        + (adxbdy.abs() + bdxady.abs()) * clift;
+    let error_bound = INCIRCLE_ERROR_BOUND * permanent;
+    if det.abs() >= error_bound {
+        return det;
+    }
+
+    let adx = two_diff(a[0], d[0]);
+    let ady = two_diff(a[1], d[1]);
+    let bdx = two_diff(b[0], d[0]);
+    let bdy = two_diff(b[1], d[1]);
+    let cdx = two_diff(c[0], d[0]);
+    let cdy = two_diff(c[1], d[1]);
+
+    let lift =
+        |x: &[f64], y: &[f64]| expansion_sum(&expansion_product(x, x), &expansion_product(y, y));
+    let cross = |x0: &[f64], y0: &[f64], x1: &[f64], y1: &[f64]| {
+        expansion_diff(&expansion_product(x0, y1), &expansion_product(x1, y0))
+    };
+
+    let a_term = expansion_product(&lift(&adx, &ady), &cross(&bdx, &bdy, &cdx, &cdy));
+    let b_term = expansion_product(&lift(&bdx, &bdy), &cross(&cdx, &cdy, &adx, &ady));
+    let c_term = expansion_product(&lift(&cdx, &cdy), &cross(&adx, &ady, &bdx, &bdy));
+
+    let det = expansion_sum(&expansion_sum(&a_term, &b_term), &c_term);
+
+    estimate(&det)
+}
+
+// Returns coordinates of the point in double precision.
+pub(crate) fn coords(point: &PointHandle) -> [f64; 2] {
+    let coords = point.coords();
+
+    [coords[0] as f64, coords[1] as f64]
+}
+
+// Calculates `a + b` as a sum of the rounded result and its rounding error.
+fn two_sum(a: f64, b: f64) -> (f64, f64) {
+    let sum = a + b;
+    let b_virtual = sum - a;
+    let a_virtual = sum - b_virtual;
+    let error = (a - a_virtual) + (b - b_virtual);
+
+    (sum, error)
+}
+
+// Calculates `a - b` exactly as an expansion.
+fn two_diff(a: f64, b: f64) -> [f64; 2] {
+    let (diff, error) = two_sum(a, -b);
+
+    [error, diff]
+}
+
+// Calculates `a * b` as a product of the rounded result and its rounding error.
+fn two_product(a: f64, b: f64) -> (f64, f64) {
+    let product = a * b;
+    let error = a.mul_add(b, -product);
+
+    (product, error)
+}
+
+// Adds `b` to the nonoverlapping expansion `e` which is sorted by magnitude.
+//
+// The resulting expansion is nonoverlapping and sorted by magnitude too.
+// Zero components are eliminated to keep the expansion short.
+fn grow_expansion(e: &mut Vec<f64>, b: f64) {
+    let mut q = b;
+
+    for component in e.iter_mut() {
+        let (sum, error) = two_sum(q, *component);
+        *component = error;
+        q = sum;
+    }
+
+    e.push(q);
+    e.retain(|component| *component != 0.0);
+}
+
+// Calculates the sum of expansions.
+fn expansion_sum(e: &[f64], f: &[f64]) -> Vec<f64> {
+    let mut sum = Vec::with_capacity(e.len() + f.len());
+    e.iter()
+        .chain(f)
+        .for_each(|&component| grow_expansion(&mut sum, component));
+
+    sum
+}
+
+// Calculates the difference of expansions.
+fn expansion_diff(e: &[f64], f: &[f64]) -> Vec<f64> {
+    let mut diff = Vec::with_capacity(e.len() + f.len());
+    e.iter().for_each(|&component| grow_expansion(&mut diff, component));
+    f.iter().for_each(|&component| grow_expansion(&mut diff, -component));
+
+    diff
+}
+
+// Calculates the product of expansions.
+fn expansion_product(e: &[f64], f: &[f64]) -> Vec<f64> {
+    let mut product = Vec::with_capacity(e.len() * f.len() * 2);
+    e.iter().for_each(|&a| {
+        f.iter().for_each(|&b| {
+            let (p, error) = two_product(a, b);
+            grow_expansion(&mut product, error);
+            grow_expansion(&mut product, p);
+        });
+    });
+
+    product
+}
+
+// Approximates the value of the expansion.
+//
+// The components of the expansion don't overlap, so the most significant component
+// has the sign of the whole expansion.
+fn estimate(e: &[f64]) -> f64 {
+    let sum: f64 = e.iter().sum();
+    let most_significant = e.iter().rev().copied().find(|c| *c != 0.0).unwrap_or(0.0);
+
+    if sum != 0.0 && sum.signum() == most_significant.signum() {
+        sum
+    } else {
+        most_significant
+    }
+}
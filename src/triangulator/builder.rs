@@ -0,0 +1,62 @@
+use super::Triangulator;
+
+use crate::backend::{Backend, DeviceFilter};
+use crate::opencl::ProgramCache;
+
+/// A builder for [`Triangulator`] which allows to choose the backend of triangulation.
+///
+/// ```no_run
+/// use vdtfont::{backend::DeviceFilter, ocl::DeviceType, Triangulator};
+///
+/// let triangulator = Triangulator::builder()
+///     .device_filter(DeviceFilter::new().device_type(DeviceType::CPU))
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct TriangulatorBuilder {
+    backend: Option<Backend>,
+    device_filter: Option<DeviceFilter>,
+
+    program_cache: Option<ProgramCache>,
+}
+
+impl TriangulatorBuilder {
+    /// Creates a new [`TriangulatorBuilder`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the `backend` which is used to triangulate glyphs.
+    ///
+    /// It takes precedence over [`TriangulatorBuilder::device_filter`].
+    pub fn backend(mut self, backend: Backend) -> Self {
+        self.backend = Some(backend);
+        self
+    }
+
+    /// Sets the `filter` which selects the OpenCL device for triangulation.
+    pub fn device_filter(mut self, filter: DeviceFilter) -> Self {
+        self.device_filter = Some(filter);
+        self
+    }
+
+    /// Sets the `cache` of compiled OpenCL programs, so they aren't recompiled on every start.
+    pub fn program_cache(mut self, cache: ProgramCache) -> Self {
+        self.program_cache = Some(cache);
+        self
+    }
+
+    /// Builds a new [`Triangulator`].
+    ///
+    /// If neither the backend nor the device filter is set, the first available GPU is used.
+    pub fn build(self) -> crate::Result<Triangulator> {
+        let backend = match (self.backend, self.device_filter) {
+            (Some(backend), _) => backend,
+            (None, Some(filter)) => Backend::opencl(&filter)?,
+            (None, None) => Backend::default_opencl()?,
+        };
+
+        Triangulator::with_program_cache(backend, self.program_cache.as_ref())
+    }
+}
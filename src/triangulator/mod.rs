@@ -0,0 +1,517 @@
+mod builder;
+mod normalization;
+mod packing;
+
+pub use builder::TriangulatorBuilder;
+
+use normalization::Normalization;
+use packing::Packing;
+
+use crate::backend::Backend;
+use crate::delaunay::{
+    Delaunay, DelaunayFactory, DelaunayTriangle, DelaunayTriangleHandle, Visibility,
+};
+use crate::error::Error;
+use crate::font::{
+    FillRule, OutlinedGlyph, TriangulatedGlyph, TriangulationOptions, MAX_GLYPH_HEIGHT,
+};
+use crate::mesh::Mesh;
+use crate::ocl::prm::Float2;
+use crate::opencl::ProgramCache;
+use crate::path::Path;
+use crate::point::{Point, PointHandle, PointId};
+use crate::voronoi::{VoronoiImage, VoronoiImageFactory};
+
+use std::cmp::Reverse;
+
+use arena_system::{Arena, Handle};
+
+/// A triangulation engine which owns the backend and the factories of triangulation.
+///
+/// It doesn't depend on font data, so one [`Triangulator`] can be shared
+/// between any number of [`Font`]s.
+///
+/// [`Font`]: crate::font::Font
+pub struct Triangulator {
+    backend: Backend,
+
+    voronoi_image_factory: VoronoiImageFactory,
+    delaunay_factory: DelaunayFactory,
+}
+
+impl Triangulator {
+    /// Creates a new [`TriangulatorBuilder`].
+    #[inline]
+    pub fn builder() -> TriangulatorBuilder {
+        TriangulatorBuilder::new()
+    }
+
+    /// Creates a new [`Triangulator`] which runs on the given `backend`.
+    #[inline]
+    pub fn new(backend: Backend) -> crate::Result<Self> {
+        Self::with_program_cache(backend, None)
+    }
+
+    /// Creates a new [`Triangulator`] which runs on the given `backend`
+    /// and caches compiled OpenCL programs in `program_cache`.
+    pub fn with_program_cache(
+        backend: Backend,
+        program_cache: Option<&ProgramCache>,
+    ) -> crate::Result<Self> {
+        Ok(Self {
+            voronoi_image_factory: VoronoiImageFactory::new(
+                backend.clone(),
+                MAX_GLYPH_HEIGHT,
+                program_cache,
+            )?,
+            delaunay_factory: DelaunayFactory::new(backend.clone(), program_cache)?,
+
+            backend,
+        })
+    }
+
+    /// Returns the backend of the triangulator.
+    pub fn backend(&self) -> &Backend {
+        &self.backend
+    }
+
+    /// Returns the maximal dimension of the Voronoi image which the triangulator supports.
+    pub fn max_image_size(&self) -> usize {
+        MAX_GLYPH_HEIGHT
+    }
+
+    /// Triangulates the given `outlined_glyph` with the default [`TriangulationOptions`].
+    #[inline]
+    pub fn triangulate_glyph(
+        &mut self,
+        outlined_glyph: OutlinedGlyph,
+    ) -> crate::Result<TriangulatedGlyph> {
+        self.triangulate_glyph_with_options(outlined_glyph, &TriangulationOptions::default())
+    }
+
+    /// Triangulates the given `outlined_glyph` with the given `options`.
+    ///
+    /// The glyph must be outlined with the same `options`.
+    pub fn triangulate_glyph_with_options(
+        &mut self,
+        outlined_glyph: OutlinedGlyph,
+        options: &TriangulationOptions,
+    ) -> crate::Result<TriangulatedGlyph> {
+        options.validate()?;
+
+        let placement = outlined_glyph.placement();
+        let (glyph, dim, _, points) = outlined_glyph.into_raw_parts();
+        if dim > options.max_image_size.min(self.max_image_size()) {
+            return Err(Error::InvalidArgument(format!(
+                "The dimension of the glyph {dim} exceeds the maximal image size {}",
+                options.max_image_size.min(self.max_image_size())
+            )));
+        }
+
+        // Empty glyphs don't have anything to triangulate.
+        if points.is_empty() {
+            return Ok(TriangulatedGlyph::new(glyph, dim, placement, points, Vec::new().into()));
+        }
+
+        // Triangulate the points in the outline of the glyph.
+        let (points, triangles) = self.triangulate_sites(points, dim, Some(options.fill_rule))?;
+
+        Ok(TriangulatedGlyph::new(glyph, dim, placement, points, triangles))
+    }
+
+    /// Triangulates the given `outlined_glyphs` with the default [`TriangulationOptions`].
+    #[inline]
+    pub fn triangulate_glyphs(
+        &mut self,
+        outlined_glyphs: &[OutlinedGlyph],
+    ) -> crate::Result<Vec<TriangulatedGlyph>> {
+        self.triangulate_glyphs_with_options(outlined_glyphs, &TriangulationOptions::default())
+    }
+
+    /// Triangulates the given `outlined_glyphs` together with the given `options`.
+    ///
+    /// The outlines of the glyphs are packed into separate regions of one Voronoi image,
+    /// so several glyphs are triangulated in a single pass instead of a pass per glyph.
+    /// Glyphs which don't fit into the image with the dimension `options.max_image_size`
+    /// are triangulated in the following passes.
+    ///
+    /// The triangulated glyphs are returned in the order of `outlined_glyphs` and have
+    /// the same placements as the ones triangulated one by one. Their points are the points
+    /// of the outlines without the bounding points, and their triangles don't keep
+    /// neighbours, because the triangulation is split between the glyphs.
+    ///
+    /// The glyphs must be outlined with the same `options`.
+    pub fn triangulate_glyphs_with_options(
+        &mut self,
+        outlined_glyphs: &[OutlinedGlyph],
+        options: &TriangulationOptions,
+    ) -> crate::Result<Vec<TriangulatedGlyph>> {
+        options.validate()?;
+
+        let max_dim = options.max_image_size.min(self.max_image_size());
+        if let Some(glyph) = outlined_glyphs.iter().find(|g| g.dim() > max_dim) {
+            return Err(Error::InvalidArgument(format!(
+                "The dimension of the glyph {} exceeds the maximal image size {max_dim}",
+                glyph.dim()
+            )));
+        }
+
+        // Empty glyphs don't have anything to triangulate.
+        let mut triangulated_glyphs = outlined_glyphs
+            .iter()
+            .map(|g| g.is_empty().then(|| split_glyph(g, Vec::new())))
+            .collect::<Vec<_>>();
+
+        // Pack the highest glyphs first, so the shelves of the image are filled densely.
+        let extents = outlined_glyphs.iter().map(packing::extent).collect::<Vec<_>>();
+        let mut pending = (0..outlined_glyphs.len())
+            .filter(|&i| !outlined_glyphs[i].is_empty())
+            .collect::<Vec<_>>();
+        pending.sort_by_key(|&i| Reverse(extents[i][1]));
+
+        while !pending.is_empty() {
+            let packing = Packing::new(&extents, &pending, options.min_image_size, max_dim);
+            pending.retain(|i| packing.placements().iter().all(|(j, _)| i != j));
+
+            let glyph_triangles = self.triangulate_packed(outlined_glyphs, &packing, options)?;
+            packing
+                .placements()
+                .iter()
+                .zip(glyph_triangles)
+                .for_each(|(&(i, _), triangles)| {
+                    triangulated_glyphs[i] = Some(split_glyph(&outlined_glyphs[i], triangles));
+                });
+        }
+
+        Ok(triangulated_glyphs.into_iter().flatten().collect())
+    }
+
+    /// Computes the Delaunay triangulation of `points` with the default
+    /// [`TriangulationOptions`].
+    #[inline]
+    pub fn triangulate_points(&mut self, points: &[Point]) -> crate::Result<Mesh> {
+        self.triangulate_points_with_options(points, &TriangulationOptions::default())
+    }
+
+    /// Computes the Delaunay triangulation of `points` with the given `options`.
+    ///
+    /// The points are scaled into the Voronoi image automatically, while the positions
+    /// of the resulting [`Mesh`] are the given `points` in the same order.
+    /// Triangles are counterclockwise when the y axis points up. Points which are closer
+    /// to each other than a pixel of the image can be merged, so they aren't used by triangles.
+    pub fn triangulate_points_with_options(
+        &mut self,
+        points: &[Point],
+        options: &TriangulationOptions,
+    ) -> crate::Result<Mesh> {
+        let points = points.iter().map(|p| Point::new(p.x(), p.y())).collect::<Vec<_>>();
+
+        self.triangulate_normalized(points, options, None)
+    }
+
+    /// Computes the constrained triangulation of the polygon with the default
+    /// [`TriangulationOptions`].
+    #[inline]
+    pub fn triangulate_polygon<C: AsRef<[Point]>>(
+        &mut self,
+        contours: &[C],
+    ) -> crate::Result<Mesh> {
+        self.triangulate_polygon_with_options(contours, &TriangulationOptions::default())
+    }
+
+    /// Computes the constrained triangulation of the polygon with the given `options`.
+    ///
+    /// The polygon consists of closed `contours`, so holes are contours which are directed
+    /// opposite to the outer ones when the fill rule is [`FillRule::NonZero`]. Only triangles
+    /// inside the polygon according to the fill rule are kept.
+    ///
+    /// The positions of the resulting [`Mesh`] are the points of all `contours`
+    /// in the same order, see [`Triangulator::triangulate_points_with_options`].
+    pub fn triangulate_polygon_with_options<C: AsRef<[Point]>>(
+        &mut self,
+        contours: &[C],
+        options: &TriangulationOptions,
+    ) -> crate::Result<Mesh> {
+        let mut points = vec![];
+        for contour in contours.iter().map(|c| c.as_ref()) {
+            if contour.len() < 3 {
+                return Err(Error::InvalidArgument(format!(
+                    "The contour with {} points doesn't enclose any area",
+                    contour.len()
+                )));
+            }
+
+            // Close the contour, so its first point follows the last one.
+            let first = points.len() as PointId;
+            let last = first + contour.len() as PointId - 1;
+            points.extend(contour.iter().enumerate().map(|(i, p)| {
+                let previous = if i == 0 { last } else { first + i as PointId - 1 };
+                Point::with_previous(p.x(), p.y(), previous)
+            }));
+        }
+
+        self.triangulate_normalized(points, options, Some(options.fill_rule))
+    }
+
+    /// Triangulates the area enclosed by the `path` with the default [`TriangulationOptions`].
+    #[inline]
+    pub fn triangulate_path(&mut self, path: &Path) -> crate::Result<Mesh> {
+        self.triangulate_path_with_options(path, &TriangulationOptions::default())
+    }
+
+    /// Triangulates the area enclosed by the `path` with the given `options`.
+    ///
+    /// The path is triangulated like the polygon, see
+    /// [`Triangulator::triangulate_polygon_with_options`]. The flatness of the options
+    /// isn't used, because curves of the path are tesselated when it is built.
+    pub fn triangulate_path_with_options(
+        &mut self,
+        path: &Path,
+        options: &TriangulationOptions,
+    ) -> crate::Result<Mesh> {
+        self.triangulate_normalized(path.points().to_vec(), options, Some(options.fill_rule))
+    }
+
+    /// Draws the Voronoi diagram of `sites` in the image with the dimension `dim`.
+    ///
+    /// The image borrows the memory of the triangulator, so it must be dropped
+    /// before the triangulator is used again.
+    pub fn draw_voronoi_image(
+        &mut self,
+        sites: Arena<Point>,
+        dim: usize,
+    ) -> crate::Result<VoronoiImage<'_>> {
+        self.voronoi_image_factory.construct_borrowed(sites, dim)
+    }
+
+    // Scales `points` into the Voronoi image, triangulates them and returns the mesh
+    // with the original positions of the points.
+    fn triangulate_normalized(
+        &mut self,
+        mut points: Vec<Point>,
+        options: &TriangulationOptions,
+        fill_rule: Option<FillRule>,
+    ) -> crate::Result<Mesh> {
+        options.validate()?;
+
+        let positions = points.iter().map(|p| [p.x(), p.y()]).collect::<Vec<_>>();
+        let Some(normalization) = Normalization::new(&points, options, self.max_image_size())?
+        else {
+            return Ok(Mesh::new(positions, Vec::new()));
+        };
+
+        points.iter_mut().for_each(|p| {
+            let [x, y] = normalization.transform(p);
+            p.set_coords(Float2::new(x, y));
+        });
+
+        let (points, triangles) =
+            self.triangulate_sites(points.into(), normalization.dim(), fill_rule)?;
+
+        // The image is mirrored vertically, so the triangles are counterclockwise
+        // when the y axis points up.
+        let indices = triangles
+            .handle_iter::<DelaunayTriangleHandle>(&points)
+            .filter_map(|t| t.get().ok().map(|t| t.vertices))
+            .filter(|vertices| vertices.iter().all(|&v| (v as usize) < positions.len()))
+            .flat_map(|vertices| vertices.map(|v| v as u32))
+            .collect::<Vec<_>>();
+
+        Ok(Mesh::new(positions, indices))
+    }
+
+    // Triangulates the glyphs in the regions of the image given by `packing`.
+    //
+    // Returns the triangles of every placed glyph whose vertices are the ids of the points
+    // in its outline.
+    fn triangulate_packed(
+        &mut self,
+        outlined_glyphs: &[OutlinedGlyph],
+        packing: &Packing,
+        options: &TriangulationOptions,
+    ) -> crate::Result<Vec<Vec<DelaunayTriangle>>> {
+        // The points of the glyphs are moved into their regions by whole pixels,
+        // so they are plotted in the same pixels relative to each other.
+        let mut points = vec![];
+        let mut ranges = vec![];
+        packing.placements().iter().for_each(|&(i, [x, y])| {
+            let first = points.len() as PointId;
+            points.extend(outlined_glyphs[i].points().handle_iter::<PointHandle>(None).map(|p| {
+                let previous: PointId = p.previous_in_outline().index().into();
+                let previous = if previous >= 0 { previous + first } else { previous };
+
+                Point::with_previous(p.x() + x as f32, p.y() + y as f32, previous)
+            }));
+            ranges.push(first..points.len() as PointId);
+        });
+
+        let (points, triangles) =
+            self.triangulate_sites(points.into(), packing.dim(), Some(options.fill_rule))?;
+
+        // The outlines of the glyphs are closed, so every visible triangle is inside
+        // the outline of a single glyph.
+        let mut glyph_triangles = vec![vec![]; ranges.len()];
+        triangles
+            .handle_iter::<DelaunayTriangleHandle>(&points)
+            .filter_map(|t| t.get().ok().map(|t| t.vertices))
+            .for_each(|vertices| {
+                let glyph = ranges.partition_point(|range| range.end <= vertices[0]);
+                let Some(range) = ranges.get(glyph) else {
+                    return;
+                };
+
+                if vertices.iter().all(|v| range.contains(v)) {
+                    let mut triangle = DelaunayTriangle::new(vertices.map(|v| v - range.start));
+                    triangle.set_visibility(Visibility::Visible);
+                    glyph_triangles[glyph].push(triangle);
+                }
+            });
+
+        Ok(glyph_triangles)
+    }
+
+    // Triangulates `points` in the Voronoi image with the dimension `dim`.
+    //
+    // If `fill_rule` is given, the outlines of the points are inserted into the triangulation
+    // and only the triangles inside them are kept. Otherwise, the triangles which are
+    // connected to the bounds are removed.
+    fn triangulate_sites(
+        &mut self,
+        points: Arena<Point>,
+        dim: usize,
+        fill_rule: Option<FillRule>,
+    ) -> crate::Result<(Arena<Point>, Arena<DelaunayTriangle>)> {
+        let voronoi_image = self.voronoi_image_factory.construct_borrowed(points, dim)?;
+        let mut delaunay = self.delaunay_factory.construct(&voronoi_image)?;
+
+        if let Some(fill_rule) = fill_rule {
+            // Insert constraint edges which are missing in the triangulation.
+            self.insert_constraint_edges(&mut delaunay)?;
+
+            // Hide triangles which are outside the contour.
+            // The winding number is zero at the bounds of the triangulation.
+            let bounding_point_ids: [PointId; 4] = delaunay.bounds().into();
+            let bounding_triangle = delaunay
+                .points()
+                .handle::<PointHandle>(bounding_point_ids[0].into(), Some(delaunay.triangles()))
+                .try_triangle_fan()?
+                .first()
+                .copied()
+                .ok_or_else(|| {
+                    Error::Triangulation("The bounding point has no triangles".to_string())
+                })?;
+            self.remove_excess_triangles(bounding_triangle, 0, fill_rule);
+        } else {
+            delaunay
+                .triangles()
+                .handle_iter::<DelaunayTriangleHandle>(delaunay.points())
+                .filter(|t| t.get().is_ok())
+                .for_each(|t| {
+                    let is_bounding = t.points().into_iter().any(|p| p.is_bounding());
+                    t.set_visibiity(if is_bounding {
+                        Visibility::Invisible
+                    } else {
+                        Visibility::Visible
+                    });
+                });
+        }
+
+        let (_, points, triangles, _) = delaunay.into_raw_parts();
+
+        // Remove the invisible triangles.
+        let triangles = triangles
+            .handle_iter::<DelaunayTriangleHandle>(&points)
+            .filter_map(|t| t.get().ok().map(|t| *t))
+            .filter(|t| matches!(t.visibility(), Visibility::Visible))
+            .collect::<Arena<DelaunayTriangle>>();
+
+        Ok((points, triangles))
+    }
+
+    // Inserts missing edges into the given `delaunay` triangulation.
+    fn insert_constraint_edges(&self, delaunay: &mut Delaunay) -> crate::Result<()> {
+        let mut edges: Vec<[i64; 2]> = vec![];
+        delaunay
+            .points()
+            .handle_iter::<PointHandle>(Some(delaunay.triangles()))
+            .for_each(|p| {
+                let pp = p.previous_in_outline();
+                if !p.is_connected_to(pp)
+                    && !p.index().is_invalid()
+                    && !pp.index().is_invalid()
+                    && !p.triangle_fan().is_empty()
+                    && !pp.triangle_fan().is_empty()
+                {
+                    edges.push([p.index().into(), pp.index().into()]);
+                }
+            });
+
+        edges.into_iter().try_for_each(|e| delaunay.insert_edge(e))
+    }
+
+    // Calculates winding numbers of triangles starting from `starting_triangle`
+    // and hides triangles which aren't filled according to `fill_rule`.
+    fn remove_excess_triangles(
+        &self,
+        starting_triangle: DelaunayTriangleHandle,
+        winding: i32,
+        fill_rule: FillRule,
+    ) {
+        // Triangles which are waiting for classification with their winding numbers.
+        let mut stack = vec![(starting_triangle, winding)];
+
+        while let Some((triangle, winding)) = stack.pop() {
+            if !matches!(triangle.visibility(), Visibility::Unknown) {
+                continue;
+            }
+
+            let visibility = if fill_rule.is_filled(winding) {
+                Visibility::Visible
+            } else {
+                Visibility::Invisible
+            };
+            triangle.set_visibiity(visibility);
+
+            // Push the neighbours in the reverse order, so the first one is visited first.
+            triangle.neighbours().into_iter().rev().for_each(|n| {
+                // Triangles which overlap after failed repairs can have neighbours
+                // which don't share an edge with them, so the winding can't cross to them.
+                if triangle.shared_points_with(&n).len() != 2 {
+                    return;
+                }
+
+                let shared_edge = triangle.shared_edge_with(&n);
+                let opposite_point =
+                    triangle.points().into_iter().find(|p| !shared_edge.contains(*p));
+
+                // When cross the contour edge, change the winding number
+                // according to the direction of the contour.
+                let winding =
+                    winding + opposite_point.map_or(0, |p| shared_edge.winding_change_from(p));
+
+                stack.push((n, winding));
+            });
+        }
+    }
+}
+
+// Creates a triangulated glyph from the points of `outlined_glyph` and `triangles`
+// which are split from the packed triangulation.
+fn split_glyph(
+    outlined_glyph: &OutlinedGlyph,
+    triangles: Vec<DelaunayTriangle>,
+) -> TriangulatedGlyph {
+    let points = outlined_glyph
+        .points()
+        .handle_iter::<PointHandle>(None)
+        .map(|p| Point::with_previous(p.x(), p.y(), p.previous_in_outline().index().into()))
+        .collect::<Arena<Point>>();
+
+    TriangulatedGlyph::new(
+        outlined_glyph.glyph(),
+        outlined_glyph.dim(),
+        outlined_glyph.placement(),
+        points,
+        triangles.into(),
+    )
+}
@@ -0,0 +1,97 @@
+use crate::error::Error;
+use crate::font::TriangulationOptions;
+use crate::point::Point;
+
+// A margin in pixels between the points and the border of the Voronoi image.
+const MARGIN: f32 = 2.0;
+
+// A transformation of points from the space of the caller into the Voronoi image.
+//
+// The image has the y axis pointing down, so the points are mirrored vertically.
+// A point `(x, y)` is placed at `((x - min[0]) * scale + MARGIN, (max[1] - y) * scale + MARGIN)`.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct Normalization {
+    dim: usize,
+
+    scale: f32,
+    min_x: f32,
+    max_y: f32,
+}
+
+impl Normalization {
+    // Finds a dimension of the Voronoi image which is the power of two, in which
+    // the shortest distance between the points is >= `min_point_distance`,
+    // and scales the points to fill the image.
+    //
+    // Returns `None` if there are less than three points or all of them coincide.
+    pub(super) fn new(
+        points: &[Point],
+        options: &TriangulationOptions,
+        max_image_size: usize,
+    ) -> crate::Result<Option<Self>> {
+        if let Some(point) = points.iter().find(|p| !(p.x().is_finite() && p.y().is_finite())) {
+            return Err(Error::InvalidArgument(format!(
+                "The point ({}; {}) isn't finite",
+                point.x(),
+                point.y()
+            )));
+        }
+
+        let min = [0, 1].map(|i| points.iter().map(|p| p.coords()[i]).fold(f32::MAX, f32::min));
+        let max = [0, 1].map(|i| points.iter().map(|p| p.coords()[i]).fold(f32::MIN, f32::max));
+
+        let extent = (max[0] - min[0]).max(max[1] - min[1]);
+        let shortest_distance = shortest_distance(points);
+        if points.len() < 3 || extent <= 0.0 {
+            return Ok(None);
+        }
+
+        let (min_dim, max_dim) =
+            (options.min_image_size, options.max_image_size.min(max_image_size));
+        let size = (extent * options.min_point_distance / shortest_distance + MARGIN * 2.0)
+            .clamp(min_dim as f32, max_dim as f32);
+        let dim = (size as usize).next_power_of_two().clamp(min_dim, max_dim);
+
+        // Leave the margin at both sides of the image and one pixel for the rounding.
+        let scale = (dim as f32 - MARGIN * 2.0 - 1.0) / extent;
+
+        Ok(Some(Self { dim, scale, min_x: min[0], max_y: max[1] }))
+    }
+
+    // Returns the dimension of the Voronoi image.
+    pub(super) fn dim(&self) -> usize {
+        self.dim
+    }
+
+    // Transforms the `point` into the Voronoi image.
+    pub(super) fn transform(&self, point: &Point) -> [f32; 2] {
+        [
+            (point.x() - self.min_x) * self.scale + MARGIN,
+            (self.max_y - point.y()) * self.scale + MARGIN,
+        ]
+    }
+}
+
+// Returns the shortest distance between distinct points.
+//
+// The points are swept along the x axis, so only close points are compared.
+fn shortest_distance(points: &[Point]) -> f32 {
+    let mut sorted = points.iter().map(|p| [p.x(), p.y()]).collect::<Vec<_>>();
+    sorted.sort_unstable_by(|a, b| a[0].total_cmp(&b[0]));
+
+    let mut shortest = f32::MAX;
+    for (i, p0) in sorted.iter().enumerate() {
+        for p1 in &sorted[i + 1..] {
+            if p1[0] - p0[0] >= shortest {
+                break;
+            }
+
+            let distance = ((p1[0] - p0[0]).powi(2) + (p1[1] - p0[1]).powi(2)).sqrt();
+            if distance > 0.0 {
+                shortest = shortest.min(distance);
+            }
+        }
+    }
+
+    shortest
+}
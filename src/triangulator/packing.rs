@@ -0,0 +1,81 @@
+use crate::font::OutlinedGlyph;
+use crate::point::PointHandle;
+
+// The margin in pixels after the points of the glyph in its region.
+// Points of glyphs are at least two pixels away from the top left corner of their images,
+// so the outlines of neighbouring glyphs are separated by four pixels.
+const MARGIN: usize = 2;
+
+// Placements of glyphs in the square Voronoi image.
+pub(super) struct Packing {
+    dim: usize,
+    // Indices of the glyphs and the offsets of their regions in the image.
+    placements: Vec<(usize, [usize; 2])>,
+}
+
+impl Packing {
+    // Places as many of the `pending` glyphs as possible into the smallest image
+    // which is a power of two from `min_dim` to `max_dim`.
+    //
+    // The glyphs are placed in shelves in the given order, so they should be sorted by
+    // the descending height. The first glyph always fits, because its extent
+    // doesn't exceed its dimension.
+    pub(super) fn new(
+        extents: &[[usize; 2]],
+        pending: &[usize],
+        min_dim: usize,
+        max_dim: usize,
+    ) -> Self {
+        let mut dim = min_dim;
+        loop {
+            let packing = Self::pack(extents, pending, dim);
+            if packing.placements.len() == pending.len() || dim >= max_dim {
+                return packing;
+            }
+
+            dim *= 2;
+        }
+    }
+
+    pub(super) fn dim(&self) -> usize {
+        self.dim
+    }
+
+    pub(super) fn placements(&self) -> &[(usize, [usize; 2])] {
+        &self.placements
+    }
+
+    // Places the glyphs into shelves in the image with the dimension `dim`
+    // skipping the ones which don't fit.
+    fn pack(extents: &[[usize; 2]], pending: &[usize], dim: usize) -> Self {
+        let mut placements = vec![];
+        let (mut x, mut y, mut shelf_height) = (0, 0, 0);
+
+        for &i in pending {
+            let [width, height] = extents[i];
+            if x + width > dim {
+                (x, y, shelf_height) = (0, y + shelf_height, 0);
+            }
+
+            if x + width > dim || y + height > dim {
+                continue;
+            }
+
+            placements.push((i, [x, y]));
+            x += width;
+            shelf_height = shelf_height.max(height);
+        }
+
+        Self { dim, placements }
+    }
+}
+
+// Returns the size of the region which the points of the glyph occupy.
+pub(super) fn extent(outlined_glyph: &OutlinedGlyph) -> [usize; 2] {
+    let max = outlined_glyph
+        .points()
+        .handle_iter::<PointHandle>(None)
+        .fold([0.0f32; 2], |max, p| [max[0].max(p.x()), max[1].max(p.y())]);
+
+    max.map(|m| (m.ceil() as usize + MARGIN).min(outlined_glyph.dim()))
+}
@@ -0,0 +1,248 @@
+use arena_system::Arena;
+
+use crate::error::Error;
+use crate::point::{Point, PointHandle};
+
+use std::iter;
+
+// Draws Voronoi diagrams on CPU.
+//
+// Every step mirrors the corresponding kernel from `voronoi.cl`, so the resulting image
+// is the same as the one drawn by [`OpenClVoronoiImageFactory`].
+//
+// [`OpenClVoronoiImageFactory`]: super::opencl::OpenClVoronoiImageFactory
+pub(super) struct CpuVoronoiImageFactory {
+    max_dim: usize,
+    dim: usize,
+
+    frames: [Vec<i32>; 2],
+    last: usize,
+}
+
+impl CpuVoronoiImageFactory {
+    // Creates a new [`CpuVoronoiImageFactory`].
+    pub(super) fn new(max_dim: usize) -> crate::Result<Self> {
+        if !max_dim.is_power_of_two() {
+            return Err(Error::InvalidArgument(format!(
+                "The given maximal dimension {max_dim} isn't the power of two"
+            )));
+        }
+
+        Ok(Self { max_dim, dim: 0, frames: [vec![], vec![]], last: 0 })
+    }
+
+    // Returns the last drawn image of the diagram.
+    pub(super) fn last(&self) -> &[i32] {
+        &self.frames[self.last]
+    }
+
+    // Draws a Voronoi diagram of `sites` in the image with the dimension `dim`.
+    pub(super) fn draw_voronoi(&mut self, sites: &Arena<Point>, dim: usize) -> crate::Result<()> {
+        self.set_dim(dim)?;
+        self.clear();
+
+        self.plot_sites(sites);
+        self.fill_voronoi();
+        self.conquer_islands();
+
+        Ok(())
+    }
+
+    // Sets the dimension of the image with `dim`.
+    fn set_dim(&mut self, dim: usize) -> crate::Result<()> {
+        if !dim.is_power_of_two() {
+            return Err(Error::InvalidArgument(format!(
+                "The given dimension {dim} isn't the power of two"
+            )));
+        }
+
+        if dim > self.max_dim {
+            return Err(Error::InvalidArgument(format!(
+                "The given dimension {dim} exceeds the maximal dimension of the image {}",
+                self.max_dim
+            )));
+        }
+
+        self.dim = dim;
+
+        Ok(())
+    }
+
+    // Fills both frames with undefined pixels.
+    fn clear(&mut self) {
+        let len = self.dim * self.dim * 4;
+
+        self.frames.iter_mut().for_each(|frame| {
+            frame.clear();
+            frame.resize(len, -1);
+        });
+    }
+
+    // Renders the next frame from the last one using the function `f`.
+    fn render<F, T>(&mut self, f: F) -> T
+    where
+        F: FnOnce(usize, &[i32], &mut [i32]) -> T,
+    {
+        let (first, second) = self.frames.split_at_mut(1);
+        let (last_frame, next_frame) = if self.last == 0 {
+            (&first[0], &mut second[0])
+        } else {
+            (&second[0], &mut first[0])
+        };
+
+        let render_result = f(self.dim, last_frame, next_frame);
+        self.last = (self.last + 1) % 2;
+
+        render_result
+    }
+
+    // Plots sites into the image of the diagram.
+    //
+    // A full algorithm of triangulation is described in the section G1 of
+    // [this paper](https://www.comp.nus.edu.sg/%7Etants/delaunay/GPUDT.pdf).
+    fn plot_sites(&mut self, sites: &Arena<Point>) {
+        self.render(|dim, _, next_frame| {
+            sites.handle_iter::<PointHandle>(None).enumerate().for_each(|(i, s)| {
+                let site = [s.x() as i32, s.y() as i32];
+
+                if exists(site, dim) {
+                    set_color(next_frame, site, dim, [site[0], site[1], i as i32, 1]);
+                }
+            });
+        });
+    }
+
+    // Fills the diagram based on the plotted sites.
+    //
+    // A full algorithm of triangulation is described in the section G1 of
+    // [this paper](https://www.comp.nus.edu.sg/%7Etants/delaunay/GPUDT.pdf).
+    fn fill_voronoi(&mut self) {
+        #[allow(non_snake_case)]
+        let N = self.dim;
+        let max_n = N.ilog2();
+        iter::once(max_n).chain(1..=max_n).map(|n| (N / (1 << n)) as i32).for_each(|k| {
+            self.render(|dim, last_frame, next_frame| {
+                pixel_coords_iter(dim).for_each(|coords| {
+                    let mut p = get_color(last_frame, coords, dim);
+
+                    for i in [-k, 0, k] {
+                        for j in [-k, 0, k] {
+                            let q = get_color(last_frame, [coords[0] + i, coords[1] + j], dim);
+                            if !is_undefined(q)
+                                && (is_undefined(p)
+                                    || dist(coords, [p[0], p[1]]) > dist(coords, [q[0], q[1]]))
+                            {
+                                p = q;
+                            }
+                        }
+                    }
+
+                    set_color(next_frame, coords, dim, p);
+                });
+            });
+        });
+    }
+
+    // Removes islands - the pixels which are not connected to the region of its site.
+    //
+    // A full algorithm of triangulation is described in the section G1 of
+    // [this paper](https://www.comp.nus.edu.sg/%7Etants/delaunay/GPUDT.pdf).
+    fn conquer_islands(&mut self) {
+        const DIRECTIONS: [[[i32; 2]; 3]; 4] = [
+            [[1, 0], [1, 1], [0, 1]],
+            [[0, 1], [-1, 1], [-1, 0]],
+            [[-1, 0], [-1, -1], [0, -1]],
+            [[0, -1], [1, -1], [1, 0]],
+        ];
+
+        let mut is_conquered = false;
+        while !is_conquered {
+            let changed_pixels_number = self.render(|dim, last_frame, next_frame| {
+                let mut changed_pixels_number = 0;
+
+                'pixels: for p in pixel_coords_iter(dim) {
+                    let p_color = get_color(last_frame, p, dim);
+                    let s = [p_color[0], p_color[1]];
+
+                    if p == s {
+                        set_color(next_frame, p, dim, p_color);
+                        continue 'pixels;
+                    }
+
+                    let quadrant = match (s[0] >= p[0], s[1] >= p[1]) {
+                        (true, true) => 0,
+                        (false, true) => 1,
+                        (false, false) => 2,
+                        (true, false) => 3,
+                    };
+
+                    let mut nearest_s = [-(dim as i32) * 10, -(dim as i32) * 10];
+                    for direction in DIRECTIONS[quadrant] {
+                        let q = [p[0] + direction[0], p[1] + direction[1]];
+                        let q_color = get_color(last_frame, q, dim);
+
+                        if p_color == q_color {
+                            set_color(next_frame, p, dim, p_color);
+                            continue 'pixels;
+                        }
+
+                        nearest_s = nearest_site(p, nearest_s, [q_color[0], q_color[1]]);
+                    }
+
+                    set_color(next_frame, p, dim, get_color(last_frame, nearest_s, dim));
+                    changed_pixels_number += 1;
+                }
+
+                changed_pixels_number
+            });
+
+            is_conquered = changed_pixels_number == 0;
+        }
+    }
+}
+
+// Returns an iterator over coordinates of all pixels in the image.
+fn pixel_coords_iter(dim: usize) -> impl Iterator<Item = [i32; 2]> {
+    let dim = dim as i32;
+
+    (0..dim).flat_map(move |y| (0..dim).map(move |x| [x, y]))
+}
+
+fn exists(p: [i32; 2], dim: usize) -> bool {
+    p[0] >= 0 && p[1] >= 0 && p[0] < dim as i32 && p[1] < dim as i32
+}
+
+fn is_undefined(c: [i32; 4]) -> bool {
+    c.iter().any(|c| *c <= -1)
+}
+
+fn dist(p: [i32; 2], s: [i32; 2]) -> f32 {
+    let x = (s[0] - p[0]) as i64;
+    let y = (s[1] - p[1]) as i64;
+
+    ((x * x + y * y) as f32).sqrt()
+}
+
+fn nearest_site(p: [i32; 2], s0: [i32; 2], s1: [i32; 2]) -> [i32; 2] {
+    if dist(p, s0) < dist(p, s1) {
+        s0
+    } else {
+        s1
+    }
+}
+
+fn get_color(frame: &[i32], p: [i32; 2], dim: usize) -> [i32; 4] {
+    if !exists(p, dim) {
+        return [-(dim as i32) * 10, -(dim as i32) * 10, -1, -1];
+    }
+
+    let offset = (p[0] as usize + p[1] as usize * dim) * 4;
+
+    [frame[offset], frame[offset + 1], frame[offset + 2], frame[offset + 3]]
+}
+
+fn set_color(frame: &mut [i32], p: [i32; 2], dim: usize, color: [i32; 4]) {
+    let offset = (p[0] as usize + p[1] as usize * dim) * 4;
+
+    frame[offset..offset + 4].copy_from_slice(&color);
+}
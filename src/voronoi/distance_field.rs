@@ -0,0 +1,262 @@
+use super::VoronoiImage;
+
+use crate::font::FillRule;
+use crate::point::{Point, PointHandle};
+
+use arena_system::{Arena, Handle};
+
+/// A signed distance field.
+///
+/// Every pixel stores the distance in pixels from its center to the nearest point
+/// of the outline. The distance is positive inside the outline and negative outside.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DistanceField {
+    width: usize,
+    height: usize,
+
+    distances: Vec<f32>,
+}
+
+impl DistanceField {
+    /// Creates a new [`DistanceField`] from `distances` which are stored row by row.
+    pub fn new(width: usize, height: usize, distances: Vec<f32>) -> Self {
+        Self { width, height, distances }
+    }
+
+    /// Returns the width of the field in pixels.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Returns the height of the field in pixels.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Returns the distances of all pixels row by row.
+    pub fn distances(&self) -> &[f32] {
+        &self.distances
+    }
+
+    /// Returns the distance of the pixel `(x; y)`.
+    ///
+    /// Returns `None` if the pixel is outside the field.
+    pub fn distance(&self, x: usize, y: usize) -> Option<f32> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+
+        Some(self.distances[x + y * self.width])
+    }
+
+    /// Checks if the field has no pixels.
+    pub fn is_empty(&self) -> bool {
+        self.distances.is_empty()
+    }
+
+    /// Converts the field into a grayscale image.
+    ///
+    /// Distances from `-spread` to `spread` are mapped onto the whole range of the image,
+    /// so the outline is at the middle gray and the inside is lighter than the outside.
+    pub fn to_image(&self, spread: f32) -> image::GrayImage {
+        image::GrayImage::from_fn(self.width as u32, self.height as u32, |x, y| {
+            let distance = self.distances[x as usize + y as usize * self.width];
+            image::Luma([encode_distance(distance, spread)])
+        })
+    }
+
+    // Crops the field leaving its top left corner with the given dimensions.
+    pub(crate) fn crop(self, width: usize, height: usize) -> Self {
+        let (width, height) = (width.min(self.width), height.min(self.height));
+        let distances = crop_rows(&self.distances, self.width, width, height);
+
+        Self::new(width, height, distances)
+    }
+}
+
+// Crops pixels which are stored row by row in the image with the given `image_width`.
+pub(super) fn crop_rows<T: Copy>(
+    pixels: &[T],
+    image_width: usize,
+    width: usize,
+    height: usize,
+) -> Vec<T> {
+    pixels
+        .chunks_exact(image_width.max(1))
+        .take(height)
+        .flat_map(|row| row[..width].iter().copied())
+        .collect()
+}
+
+// Maps the distance from `[-spread; spread]` onto `[0; 255]`.
+pub(crate) fn encode_distance(distance: f32, spread: f32) -> u8 {
+    ((distance / spread * 0.5 + 0.5).clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+// Outline segments which connect the sites of the Voronoi image.
+pub(crate) struct Outline {
+    sites: Vec<[f32; 2]>,
+
+    previous: Vec<Option<usize>>,
+    next: Vec<Option<usize>>,
+}
+
+impl Outline {
+    // Collects segments from the links between the sites.
+    pub(crate) fn new(sites: &Arena<Point>) -> Self {
+        let len = sites.len();
+        let mut outline = Self { sites: vec![], previous: vec![None; len], next: vec![None; len] };
+
+        sites.handle_iter::<PointHandle>(None).enumerate().for_each(|(i, s)| {
+            outline.sites.push([s.x(), s.y()]);
+
+            let previous = s.get().map(|s| s.previous_in_outline()).unwrap_or(-1);
+            if (0..len as i64).contains(&previous) && previous as usize != i {
+                outline.previous[i] = Some(previous as usize);
+                outline.next[previous as usize] = Some(i);
+            }
+        });
+
+        outline
+    }
+
+    // Returns the number of sites.
+    pub(crate) fn len(&self) -> usize {
+        self.sites.len()
+    }
+
+    // Returns coordinates of the site `i`.
+    pub(crate) fn site(&self, i: usize) -> [f32; 2] {
+        self.sites[i]
+    }
+
+    // Checks if the site `i` starts a chain of segments, i.e. isn't preceded by any site.
+    pub(crate) fn is_chain_start(&self, i: usize) -> bool {
+        self.previous[i].is_none()
+    }
+
+    // Checks if the site `i` ends a chain of segments, i.e. isn't followed by any site.
+    pub(crate) fn is_chain_end(&self, i: usize) -> bool {
+        self.next[i].is_none()
+    }
+
+    // Returns the ids of the sites at the ends of the segments which end at the site `i`.
+    pub(crate) fn segment_ids_of(&self, i: usize) -> impl Iterator<Item = [usize; 2]> + '_ {
+        let incoming = self.previous[i].map(|p| [p, i]);
+        let outgoing = self.next[i].map(|n| [i, n]);
+
+        incoming.into_iter().chain(outgoing)
+    }
+
+    // Returns the segments which end at the site `i`.
+    pub(crate) fn segments_of(&self, i: usize) -> impl Iterator<Item = [[f32; 2]; 2]> + '_ {
+        self.segment_ids_of(i).map(|[a, b]| [self.sites[a], self.sites[b]])
+    }
+
+    // Returns all segments of the outline.
+    pub(crate) fn segments(&self) -> impl Iterator<Item = [[f32; 2]; 2]> + '_ {
+        self.previous
+            .iter()
+            .enumerate()
+            .filter_map(|(i, p)| p.map(|p| [self.sites[p], self.sites[i]]))
+    }
+
+    // Returns the distance from the point `p` to the nearest segment which ends
+    // at the site `i`, or to the site itself if it isn't connected.
+    pub(crate) fn distance_near_site(&self, p: [f32; 2], i: usize) -> f32 {
+        self.segments_of(i)
+            .map(|segment| segment_distance(p, segment))
+            .fold(distance(p, self.sites[i]), f32::min)
+    }
+
+    // Checks which pixel centers of the row `y` are filled according to `fill_rule`.
+    pub(crate) fn filled_row(&self, y: usize, width: usize, fill_rule: FillRule) -> Vec<bool> {
+        let center_y = y as f32 + 0.5;
+
+        // The segments which cross the row and their directions.
+        let mut crossings = self
+            .segments()
+            .filter(|[a, b]| (a[1] <= center_y) != (b[1] <= center_y))
+            .map(|[a, b]| {
+                let x = a[0] + (center_y - a[1]) * (b[0] - a[0]) / (b[1] - a[1]);
+                (x, if b[1] > a[1] { 1 } else { -1 })
+            })
+            .collect::<Vec<_>>();
+        crossings.sort_unstable_by(|a, b| a.0.total_cmp(&b.0));
+
+        let mut crossings = crossings.into_iter().peekable();
+        let mut winding = 0;
+        (0..width)
+            .map(|x| {
+                let center_x = x as f32 + 0.5;
+                while let Some((_, direction)) = crossings.next_if(|c| c.0 < center_x) {
+                    winding += direction;
+                }
+
+                fill_rule.is_filled(winding)
+            })
+            .collect()
+    }
+}
+
+// Computes the signed distance field of the outline formed by the sites of `image`.
+pub(super) fn compute(image: &VoronoiImage, fill_rule: FillRule) -> crate::Result<DistanceField> {
+    let dim = image.dim();
+    let outline = Outline::new(image.sites());
+    let pixels = image.to_raw_pixels()?;
+
+    let mut distances = Vec::with_capacity(dim * dim);
+    for y in 0..dim {
+        let filled_row = outline.filled_row(y, dim, fill_rule);
+
+        distances.extend(filled_row.into_iter().enumerate().map(|(x, is_filled)| {
+            // Pixels without sites are infinitely far from the outline.
+            let center = [x as f32 + 0.5, y as f32 + 0.5];
+            let distance = nearby_sites(&pixels, dim, [x, y], outline.len())
+                .map(|site| outline.distance_near_site(center, site))
+                .fold(f32::INFINITY, f32::min);
+
+            if is_filled {
+                distance
+            } else {
+                -distance
+            }
+        }));
+    }
+
+    Ok(DistanceField::new(dim, dim, distances))
+}
+
+// Returns the nearest sites of the pixel and its neighbours.
+//
+// The sites are plotted at the whole pixels, so the site which is nearest to the center
+// of the pixel can be the nearest one of its neighbour.
+pub(super) fn nearby_sites<'a>(
+    pixels: &'a [i32],
+    dim: usize,
+    [x, y]: [usize; 2],
+    sites_len: usize,
+) -> impl Iterator<Item = usize> + 'a {
+    (y.saturating_sub(1)..(y + 2).min(dim))
+        .flat_map(move |y| (x.saturating_sub(1)..(x + 2).min(dim)).map(move |x| x + y * dim))
+        .map(|i| &pixels[i * 4..i * 4 + 4])
+        .filter(move |pixel| pixel[3] >= 0 && (pixel[2] as usize) < sites_len)
+        .map(|pixel| pixel[2] as usize)
+}
+
+pub(super) fn distance(a: [f32; 2], b: [f32; 2]) -> f32 {
+    (b[0] - a[0]).hypot(b[1] - a[1])
+}
+
+// Returns the distance from the point `p` to the segment.
+fn segment_distance(p: [f32; 2], [a, b]: [[f32; 2]; 2]) -> f32 {
+    let ab = [b[0] - a[0], b[1] - a[1]];
+    let length_squared = ab[0] * ab[0] + ab[1] * ab[1];
+    if length_squared == 0.0 {
+        return distance(p, a);
+    }
+
+    let t = (((p[0] - a[0]) * ab[0] + (p[1] - a[1]) * ab[1]) / length_squared).clamp(0.0, 1.0);
+
+    distance(p, [a[0] + ab[0] * t, a[1] + ab[1] * t])
+}
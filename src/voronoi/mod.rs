@@ -1,10 +1,19 @@
+mod cpu;
+mod distance_field;
 mod factory;
+mod msdf;
+mod opencl;
 mod pixel;
 mod swapchain;
 
+pub use distance_field::DistanceField;
 pub use factory::VoronoiImageFactory;
+pub use msdf::MultiDistanceField;
 pub use pixel::Pixel;
 
+pub(crate) use msdf::color_edges;
+
+use crate::font::FillRule;
 use crate::opencl::ImageView;
 use crate::point::Point;
 
@@ -17,7 +26,19 @@ pub struct VoronoiImage<'a> {
     dim: usize,
 
     sites: Arena<Point>,
-    image: Cow<'a, ImageView<i32>>,
+    image: VoronoiImageData<'a>,
+}
+
+/// Pixels of [`VoronoiImage`] which are stored on the device of the backend
+/// which has drawn the image.
+///
+/// Every pixel consists of four channels: the coordinates of the nearest site,
+/// the id of the nearest site and the flag which is `-1` if the pixel is undefined.
+pub enum VoronoiImageData<'a> {
+    /// An image in the memory of the OpenCL device.
+    OpenCl(Cow<'a, ImageView<i32>>),
+    /// An image in the memory of CPU.
+    Cpu(Cow<'a, [i32]>),
 }
 
 impl<'a> VoronoiImage<'a> {
@@ -31,19 +52,31 @@ impl<'a> VoronoiImage<'a> {
         &self.sites
     }
 
-    /// Returns an image view of the diagram.
-    pub fn image(&self) -> &ImageView<i32> {
+    /// Returns the pixel data of the diagram.
+    pub fn data(&self) -> &VoronoiImageData<'a> {
+        &self.image
+    }
+
+    /// Returns an image view of the diagram if it was drawn by the OpenCL backend.
+    pub fn image(&self) -> Option<&ImageView<i32>> {
         match self.image {
-            Cow::Owned(ref image) => image,
-            Cow::Borrowed(image) => image,
+            VoronoiImageData::OpenCl(ref image) => Some(image),
+            VoronoiImageData::Cpu(_) => None,
+        }
+    }
+
+    /// Returns the raw pixel data of the diagram.
+    pub fn to_raw_pixels(&self) -> crate::Result<Cow<'_, [i32]>> {
+        match self.image {
+            VoronoiImageData::OpenCl(ref image) => Ok(Cow::Owned(image.to_vec()?)),
+            VoronoiImageData::Cpu(ref data) => Ok(Cow::Borrowed(data)),
         }
     }
 
     /// Converts the [`VoronoiImage`] into pixels.
-    pub fn to_pixels(&self) -> anyhow::Result<Vec<Pixel>> {
+    pub fn to_pixels(&self) -> crate::Result<Vec<Pixel>> {
         Ok(self
-            .image
-            .to_vec()?
+            .to_raw_pixels()?
             .chunks_exact(4)
             .enumerate()
             .map(|(i, raw_pixel)| {
@@ -55,10 +88,27 @@ impl<'a> VoronoiImage<'a> {
             .collect())
     }
 
+    /// Computes the signed distance field of the outline which connects the sites.
+    ///
+    /// The distance of every pixel is measured to the outline segments which end at
+    /// the nearest sites of the pixel and its neighbours, so the field is exact if the sites
+    /// are dense enough, e.g. the distance between consecutive sites doesn't exceed
+    /// a couple of pixels. The sign is determined by
+    /// the winding number of the pixel according to `fill_rule`.
+    pub fn to_distance_field(&self, fill_rule: FillRule) -> crate::Result<DistanceField> {
+        distance_field::compute(self, fill_rule)
+    }
+
+    // Computes the signed pseudo-distances to the chains of segments formed by the sites,
+    // see [`MultiDistanceField`].
+    pub(crate) fn to_pseudo_distances(&self) -> crate::Result<Vec<f32>> {
+        msdf::compute_pseudo_distances(self)
+    }
+
     // Creates a new [`VoronoiImage`].
     //
     // The validity of `dim`, `sites`, `image` is ensured by [`VoronoiImageFactory`].
-    fn new(dim: usize, sites: Arena<Point>, image: Cow<'a, ImageView<i32>>) -> Self {
+    fn new(dim: usize, sites: Arena<Point>, image: VoronoiImageData<'a>) -> Self {
         Self { dim, sites, image }
     }
 }
@@ -0,0 +1,371 @@
+use super::distance_field::{
+    self, crop_rows, encode_distance, nearby_sites, DistanceField, Outline,
+};
+use super::VoronoiImage;
+
+use crate::point::{Point, PointId};
+
+use std::ops::Range;
+
+// Colors of edges which are sets of channels of the field.
+const RED: u8 = 0b001;
+const GREEN: u8 = 0b010;
+const BLUE: u8 = 0b100;
+const CYAN: u8 = GREEN | BLUE;
+const MAGENTA: u8 = RED | BLUE;
+const YELLOW: u8 = RED | GREEN;
+const WHITE: u8 = RED | GREEN | BLUE;
+
+/// A multi-channel signed distance field.
+///
+/// Every channel stores the signed pseudo-distance to the edges of the outline which
+/// have the color of the channel. Edges meeting at a sharp corner have different colors,
+/// so the median of the channels preserves the corner, while the distance in a single
+/// channel rounds it off. The median is positive inside the outline and negative outside.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MultiDistanceField {
+    width: usize,
+    height: usize,
+
+    distances: Vec<[f32; 3]>,
+}
+
+impl MultiDistanceField {
+    /// Creates a new [`MultiDistanceField`] from `distances` which are stored row by row.
+    pub fn new(width: usize, height: usize, distances: Vec<[f32; 3]>) -> Self {
+        Self { width, height, distances }
+    }
+
+    /// Returns the width of the field in pixels.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Returns the height of the field in pixels.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Returns the distances of all pixels row by row.
+    pub fn distances(&self) -> &[[f32; 3]] {
+        &self.distances
+    }
+
+    /// Returns the distances of the pixel `(x; y)` in the red, green and blue channels.
+    ///
+    /// Returns `None` if the pixel is outside the field.
+    pub fn distance(&self, x: usize, y: usize) -> Option<[f32; 3]> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+
+        Some(self.distances[x + y * self.width])
+    }
+
+    /// Returns the median of the channels of the pixel `(x; y)`, which is
+    /// the signed distance to the outline with sharp corners.
+    ///
+    /// Returns `None` if the pixel is outside the field.
+    pub fn median(&self, x: usize, y: usize) -> Option<f32> {
+        self.distance(x, y).map(median)
+    }
+
+    /// Checks if the field has no pixels.
+    pub fn is_empty(&self) -> bool {
+        self.distances.is_empty()
+    }
+
+    /// Converts the field into an RGB image.
+    ///
+    /// Distances from `-spread` to `spread` are mapped onto the whole range of every channel,
+    /// see [`DistanceField::to_image`].
+    pub fn to_image(&self, spread: f32) -> image::RgbImage {
+        image::RgbImage::from_fn(self.width as u32, self.height as u32, |x, y| {
+            let distances = self.distances[x as usize + y as usize * self.width];
+            image::Rgb(distances.map(|distance| encode_distance(distance, spread)))
+        })
+    }
+
+    // Crops the field leaving its top left corner with the given dimensions.
+    pub(crate) fn crop(self, width: usize, height: usize) -> Self {
+        let (width, height) = (width.min(self.width), height.min(self.height));
+        let distances = crop_rows(&self.distances, self.width, width, height);
+
+        Self::new(width, height, distances)
+    }
+
+    // Combines the signed pseudo-distances of the channels with the signed distance `field`.
+    //
+    // The pixels in which the median disagrees with the field, e.g. where the edges
+    // of different contours clash, take the distance of the field.
+    pub(crate) fn combine(field: &DistanceField, channels: [Vec<f32>; 3]) -> Self {
+        let distances = field
+            .distances()
+            .iter()
+            .enumerate()
+            .map(|(i, &distance)| {
+                let pseudo = [0, 1, 2].map(|c| channels[c][i]);
+                let is_consistent = pseudo.iter().all(|d| d.is_finite())
+                    && (median(pseudo) > 0.0) == (distance > 0.0);
+
+                if is_consistent {
+                    pseudo
+                } else {
+                    [distance; 3]
+                }
+            })
+            .collect();
+
+        Self::new(field.width(), field.height(), distances)
+    }
+}
+
+// Returns the median of three values.
+fn median([a, b, c]: [f32; 3]) -> f32 {
+    a.min(b).max(a.max(b).min(c))
+}
+
+// Splits contours of the outline into edges at `corners` and colors them,
+// so edges which meet at a corner have different colors.
+//
+// Returns the sites of every channel. The edges of the channel form chains of sites
+// which are linked within the edges only, so every chain ends at corners. Every contour
+// is oriented, so the filled side of its edges is at the left, i.e. where the cross
+// product of the edge and the direction to the point is positive.
+pub(crate) fn color_edges(sites: &[Point], corners: &[bool]) -> [Vec<Point>; 3] {
+    let mut channels: [Vec<Point>; 3] = Default::default();
+
+    let contours = contours(sites);
+    for contour in &contours {
+        // The sites of the contour in the order in which the filled side is at the left.
+        let mut order = contour.clone().collect::<Vec<_>>();
+        if !is_filled_at_left(sites, &contours, contour.clone()) {
+            order.reverse();
+        }
+        let len = order.len();
+
+        let contour_corners = (0..len).filter(|&i| corners[order[i]]).collect::<Vec<_>>();
+        for (color, positions, is_closed) in contour_edges(&contour_corners, len) {
+            for (channel, channel_sites) in channels.iter_mut().enumerate() {
+                if color & (1 << channel) == 0 {
+                    continue;
+                }
+
+                let first = channel_sites.len() as PointId;
+                let last = first + positions.len() as PointId - 1;
+                channel_sites.extend(positions.iter().enumerate().map(|(i, &position)| {
+                    let previous = match i {
+                        0 if is_closed => last,
+                        0 => -1,
+                        _ => first + i as PointId - 1,
+                    };
+                    let site = &sites[order[position]];
+
+                    Point::with_previous(site.x(), site.y(), previous)
+                }));
+            }
+        }
+    }
+
+    channels
+}
+
+// Returns the ranges of the sites of every contour.
+//
+// The first site of the contour is linked with the last one.
+fn contours(sites: &[Point]) -> Vec<Range<usize>> {
+    let mut contours = Vec::new();
+
+    let mut start = 0;
+    while start < sites.len() {
+        let previous = sites[start].previous_in_outline();
+        let end = if previous as usize >= start && (previous as usize) < sites.len() {
+            previous as usize
+        } else {
+            start
+        };
+
+        contours.push(start..end + 1);
+        start = end + 1;
+    }
+
+    contours
+}
+
+// Checks if the outline is filled at the left of the longest segment of the `contour`
+// according to the nonzero rule, while it isn't filled at the right.
+//
+// Contours whose both sides are filled or empty, e.g. overlapping ones, keep their order.
+fn is_filled_at_left(sites: &[Point], contours: &[Range<usize>], contour: Range<usize>) -> bool {
+    // The distance from the segment to the points at which the winding is counted.
+    const OFFSET: f32 = 1e-2;
+
+    let segments = |contour: Range<usize>| {
+        let len = contour.len();
+        contour.clone().map(move |i| {
+            let a = &sites[contour.start + (i - contour.start + len - 1) % len];
+            let b = &sites[i];
+            [[a.x(), a.y()], [b.x(), b.y()]]
+        })
+    };
+    let length = |[a, b]: [[f32; 2]; 2]| (b[0] - a[0]).hypot(b[1] - a[1]);
+
+    let Some([a, b]) = segments(contour).max_by(|s, t| length(*s).total_cmp(&length(*t))) else {
+        return true;
+    };
+    let length = length([a, b]);
+    if length == 0.0 {
+        return true;
+    }
+
+    // The winding number of the whole outline around the point.
+    let winding = |p: [f32; 2]| -> i32 {
+        contours
+            .iter()
+            .flat_map(|contour| segments(contour.clone()))
+            .filter(|[a, b]| (a[1] <= p[1]) != (b[1] <= p[1]))
+            .filter(|[a, b]| a[0] + (p[1] - a[1]) * (b[0] - a[0]) / (b[1] - a[1]) < p[0])
+            .map(|[a, b]| if b[1] > a[1] { 1 } else { -1 })
+            .sum()
+    };
+
+    let middle = [(a[0] + b[0]) / 2.0, (a[1] + b[1]) / 2.0];
+    let normal = [(a[1] - b[1]) / length * OFFSET, (b[0] - a[0]) / length * OFFSET];
+    let left = winding([middle[0] + normal[0], middle[1] + normal[1]]) != 0;
+    let right = winding([middle[0] - normal[0], middle[1] - normal[1]]) != 0;
+
+    left || !right
+}
+
+// Splits the contour of `len` sites into colored edges.
+//
+// Returns colors of the edges, positions of their sites in the contour and flags
+// which are set for edges which are closed, i.e. cover the whole contour without corners.
+fn contour_edges(corners: &[usize], len: usize) -> Vec<(u8, Vec<usize>, bool)> {
+    // Returns the positions of the sites from `from` to `to` going forward along the contour.
+    let positions = |from: usize, to: usize| {
+        let count = (to + len - from) % len;
+        let count = if count == 0 { len } else { count };
+
+        (0..=count).map(|i| (from + i) % len).collect::<Vec<_>>()
+    };
+
+    match corners {
+        // A smooth contour has the same distance in all channels.
+        [] => vec![(WHITE, (0..len).collect(), true)],
+        // A contour with a single corner is split into three edges,
+        // so the corner is between edges of different colors.
+        &[corner] => {
+            let edge = positions(corner, corner);
+            let [first, second] = [edge.len() / 3, edge.len() * 2 / 3];
+
+            vec![
+                (MAGENTA, edge[..=first].to_vec(), false),
+                (WHITE, edge[first..=second].to_vec(), false),
+                (YELLOW, edge[second..].to_vec(), false),
+            ]
+        }
+        corners => {
+            const COLORS: [u8; 3] = [CYAN, MAGENTA, YELLOW];
+
+            let n = corners.len();
+            (0..n)
+                .map(|i| {
+                    // The last edge differs from both the previous and the first edges.
+                    let color = if i == n - 1 && n % 3 == 1 { COLORS[1] } else { COLORS[i % 3] };
+
+                    (color, positions(corners[i], corners[(i + 1) % n]), false)
+                })
+                .collect()
+        }
+    }
+}
+
+// Computes the signed pseudo-distances to the chains of segments formed by the sites
+// of `image`.
+//
+// The pseudo-distance to the end of the chain is the distance to the line which extends
+// its last segment, so corners between chains stay sharp. The sign depends on the side
+// of the nearest segment, and it is positive inside, because [`color_edges`] orients
+// the contours.
+pub(super) fn compute_pseudo_distances(image: &VoronoiImage) -> crate::Result<Vec<f32>> {
+    let dim = image.dim();
+    let outline = Outline::new(image.sites());
+    let pixels = image.to_raw_pixels()?;
+
+    let pseudo_distances = (0..dim * dim)
+        .map(|i| {
+            let (x, y) = (i % dim, i / dim);
+            let center = [x as f32 + 0.5, y as f32 + 0.5];
+
+            nearby_sites(&pixels, dim, [x, y], outline.len())
+                .flat_map(|site| outline.segment_ids_of(site))
+                .map(|segment| SegmentDistance::new(&outline, segment, center))
+                .reduce(SegmentDistance::nearest)
+                .map_or(f32::INFINITY, |distance| distance.pseudo_distance)
+        })
+        .collect();
+
+    Ok(pseudo_distances)
+}
+
+// A distance from a point to a segment of the outline.
+#[derive(Clone, Copy)]
+struct SegmentDistance {
+    distance: f32,
+    // The distance to the line of the segment relative to the distance to the segment.
+    orthogonality: f32,
+    pseudo_distance: f32,
+}
+
+impl SegmentDistance {
+    fn new(outline: &Outline, [a, b]: [usize; 2], p: [f32; 2]) -> Self {
+        let (site_a, site_b) = (outline.site(a), outline.site(b));
+        let ab = [site_b[0] - site_a[0], site_b[1] - site_a[1]];
+        let ap = [p[0] - site_a[0], p[1] - site_a[1]];
+        let length = ab[0].hypot(ab[1]);
+
+        if length == 0.0 {
+            let distance = distance_field::distance(p, site_a);
+            return Self { distance, orthogonality: 0.0, pseudo_distance: distance };
+        }
+
+        let t = (ap[0] * ab[0] + ap[1] * ab[1]) / (length * length);
+        let line_distance = (ab[0] * ap[1] - ab[1] * ap[0]) / length;
+        let t_clamped = t.clamp(0.0, 1.0);
+        let distance = distance_field::distance(
+            p,
+            [site_a[0] + ab[0] * t_clamped, site_a[1] + ab[1] * t_clamped],
+        );
+
+        // Beyond the ends of the chain, the distance is measured to the extended segment.
+        let is_extended =
+            t < 0.0 && outline.is_chain_start(a) || t > 1.0 && outline.is_chain_end(b);
+        let magnitude = if is_extended { line_distance.abs() } else { distance };
+        let sign = if line_distance < 0.0 { -1.0 } else { 1.0 };
+        let orthogonality = if distance > 0.0 { line_distance.abs() / distance } else { 1.0 };
+
+        Self { distance, orthogonality, pseudo_distance: magnitude * sign }
+    }
+
+    // Selects the nearest segment. If the distances are equal, e.g. near the site which joins
+    // the segments, the segment whose line is nearer to the perpendicular is selected,
+    // because the point is on its side of the outline.
+    fn nearest(self, other: Self) -> Self {
+        const TOLERANCE: f32 = 1e-4;
+
+        if (self.distance - other.distance).abs() <= TOLERANCE * self.distance.max(1.0) {
+            if other.orthogonality > self.orthogonality {
+                return other;
+            }
+
+            return self;
+        }
+
+        if other.distance < self.distance {
+            other
+        } else {
+            self
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use crate::error::Error;
 use crate::ocl;
 use crate::opencl::ImageView;
 
@@ -12,9 +13,11 @@ pub(super) struct Swapchain {
 
 impl Swapchain {
     // Creates a new [`Swapchain`].
-    pub(super) fn new(queue: &ocl::Queue, dim: usize) -> anyhow::Result<Self> {
+    pub(super) fn new(queue: &ocl::Queue, dim: usize) -> crate::Result<Self> {
         if !dim.is_power_of_two() {
-            anyhow::bail!("The given maximal dimension {dim} isn't the power of two");
+            return Err(Error::InvalidArgument(format!(
+                "The given maximal dimension {dim} isn't the power of two"
+            )));
         }
 
         let undefined_data = vec![-1; dim * dim * 4];
@@ -54,16 +57,18 @@ impl Swapchain {
     }
 
     // Sets the dimension of the swapchain wiht `dim`.
-    pub(super) fn set_dim(&mut self, dim: usize) -> anyhow::Result<()> {
+    pub(super) fn set_dim(&mut self, dim: usize) -> crate::Result<()> {
         if !dim.is_power_of_two() {
-            anyhow::bail!("The given dimension {dim} isn't the power of two");
+            return Err(Error::InvalidArgument(format!(
+                "The given dimension {dim} isn't the power of two"
+            )));
         }
 
         if dim > self.max_dim {
-            anyhow::bail!(
+            return Err(Error::InvalidArgument(format!(
                 "The given dimension {dim} exceeds the maximal dimension of the image {}",
                 self.max_dim
-            )
+            )));
         }
 
         self.dim = dim;
@@ -74,9 +79,9 @@ impl Swapchain {
     }
 
     // Renders into the swapchain using the function `f`.
-    pub(super) fn render<F, T>(&mut self, f: F) -> anyhow::Result<T>
+    pub(super) fn render<F, T>(&mut self, f: F) -> crate::Result<T>
     where
-        F: FnOnce(&ImageView<i32>, &ImageView<i32>) -> anyhow::Result<T>,
+        F: FnOnce(&ImageView<i32>, &ImageView<i32>) -> crate::Result<T>,
     {
         let last_frame = self.last();
         let next_frame = self.next();
@@ -103,7 +108,7 @@ impl Swapchain {
     }
 
     // Clears the swapchain.
-    pub(super) fn clear(&self) -> anyhow::Result<()> {
+    pub(super) fn clear(&self) -> crate::Result<()> {
         let undefined_data = vec![-1; self.dim * self.dim * 4];
 
         self.last().write(&undefined_data)?;
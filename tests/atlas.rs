@@ -0,0 +1,102 @@
+mod common;
+
+use vdtfont::*;
+
+fn triangulate(
+    font: &Font,
+    triangulator: &mut Triangulator,
+    options: &TriangulationOptions,
+    c: char,
+) -> TriangulatedGlyph {
+    let outlined_glyph = font.outline_glyph_with_options(font.glyph(c), options).unwrap();
+    font.triangulate_glyph_with_options(triangulator, outlined_glyph, options)
+        .unwrap()
+}
+
+#[test]
+fn test_mesh_atlas_ranges() {
+    let (mut triangulator, font, options) = common::opensans_regular();
+    let glyphs = ['o', ' ', 'l'].map(|c| triangulate(&font, &mut triangulator, &options, c));
+
+    let mut atlas = MeshAtlas::new();
+    atlas.extend(&glyphs);
+    assert_eq!(atlas.len(), 3);
+
+    for glyph in &glyphs {
+        let entry = atlas.entry(glyph.glyph()).unwrap();
+        let mesh = glyph.to_em_mesh();
+
+        // The triangles of the glyph refer to its own vertices in the atlas.
+        let vertices = entry.vertices();
+        let positions = &atlas.mesh().positions()[vertices.clone()];
+        let indices = atlas.mesh().indices()[entry.indices()]
+            .iter()
+            .map(|&i| i - vertices.start as u32)
+            .collect::<Vec<_>>();
+
+        assert_eq!(positions, mesh.positions());
+        assert_eq!(indices, mesh.indices());
+        assert_eq!(entry.triangle_count(), mesh.triangle_count());
+        assert_eq!(entry.is_empty(), glyph.is_empty());
+
+        let bounds = entry.bounds();
+        assert!(positions.iter().all(|p| {
+            (bounds[0] - 1e-3..=bounds[2] + 1e-3).contains(&p[0])
+                && (bounds[1] - 1e-3..=bounds[3] + 1e-3).contains(&p[1])
+        }));
+    }
+}
+
+#[test]
+fn test_mesh_atlas_changes() {
+    let (mut triangulator, font, options) = common::opensans_regular();
+    let [o, l] = ['o', 'l'].map(|c| triangulate(&font, &mut triangulator, &options, c));
+
+    let mut atlas = MeshAtlas::new();
+    assert!(atlas.take_changes().is_none());
+
+    let o_entry = atlas.insert(&o).clone();
+    let changes = atlas.take_changes().unwrap();
+    assert_eq!(changes.vertices(), o_entry.vertices());
+    assert_eq!(changes.indices(), o_entry.indices());
+    assert_eq!(changes.glyphs(), [o.glyph()]);
+
+    // The glyph which is already in the atlas doesn't change it.
+    assert_eq!(atlas.insert(&o), &o_entry);
+    assert!(atlas.take_changes().is_none());
+
+    // Only the appended glyph is reported and the existing ranges stay in place.
+    let l_entry = atlas.insert(&l).clone();
+    let changes = atlas.take_changes().unwrap();
+    assert_eq!(changes.vertices(), l_entry.vertices());
+    assert_eq!(changes.indices(), l_entry.indices());
+    assert_eq!(l_entry.vertices().start, o_entry.vertices().end);
+    assert_eq!(atlas.entry(o.glyph()), Some(&o_entry));
+
+    atlas.clear();
+    assert!(atlas.is_empty() && atlas.mesh().is_empty());
+    assert!(!atlas.contains(o.glyph()));
+}
+
+#[test]
+fn test_mesh_atlas_metrics() {
+    let (mut triangulator, font, options) = common::opensans_regular();
+    let units_per_em = font.units_per_em().unwrap();
+
+    let mut atlas = MeshAtlas::new();
+    for c in ['o', ' ', 'l'] {
+        let glyph = triangulate(&font, &mut triangulator, &options, c);
+        let entry = atlas.insert(&glyph);
+
+        // The metrics are taken from the font and stored in the em square like the bounds.
+        let advance = font.hor_advance(glyph.glyph()).unwrap();
+        let side_bearing = font.hor_side_bearing(glyph.glyph()).unwrap();
+        assert_eq!(entry.advance(), advance / units_per_em);
+        assert_eq!(entry.side_bearing(), side_bearing / units_per_em);
+        assert!(entry.advance() > 0.0);
+    }
+
+    // The side bearing of a glyph with an outline is the left bound of its outline.
+    let o = atlas.entry(font.glyph('o')).unwrap();
+    assert!((o.side_bearing() - o.bounds()[0]).abs() < 1e-6);
+}
@@ -0,0 +1,99 @@
+mod common;
+
+use vdtfont::*;
+
+#[test]
+fn test_glyph_cache_hits_and_misses() {
+    let (mut triangulator, font, options) = common::opensans_regular();
+    let (o, l) = (font.glyph('o'), font.glyph('l'));
+
+    let mut cache = GlyphCache::new(font, usize::MAX);
+
+    let triangle_count = cache
+        .triangulate_glyph_with_options(&mut triangulator, o, &options)
+        .unwrap()
+        .triangles()
+        .len();
+    let cached = cache
+        .triangulate_glyph_with_options(&mut triangulator, o, &options)
+        .unwrap();
+    assert_eq!(cached.glyph(), o);
+    assert_eq!(cached.triangles().len(), triangle_count);
+
+    // Other options are cached separately.
+    let fine_options = TriangulationOptions { max_image_size: 512, ..options };
+    cache
+        .triangulate_glyph_with_options(&mut triangulator, o, &fine_options)
+        .unwrap();
+    cache
+        .triangulate_glyph_with_options(&mut triangulator, l, &options)
+        .unwrap();
+
+    assert_eq!(cache.stats(), CacheStats { hits: 1, misses: 3, evictions: 0 });
+    assert_eq!(cache.stats().hit_rate(), 0.25);
+    assert_eq!(cache.len(), 3);
+    assert!(cache.contains(o, &fine_options));
+
+    // Missing glyphs aren't cached.
+    let missing = Glyph(u16::MAX);
+    assert!(matches!(
+        cache.triangulate_glyph_with_options(&mut triangulator, missing, &options),
+        Err(Error::MissingGlyph(_))
+    ));
+    assert_eq!(cache.len(), 3);
+
+    // The font isn't variable.
+    let weight = ttfp::Tag::from_bytes(b"wght");
+    assert!(cache.font_mut().set_variation(weight, 700.0).is_err());
+    assert!(cache.font().variation_coordinates().is_empty());
+}
+
+#[test]
+fn test_glyph_cache_eviction() {
+    let (mut triangulator, font, options) = common::opensans_regular();
+    let [o, l, i] = ['o', 'l', 'i'].map(|c| font.glyph(c));
+
+    let mut cache = GlyphCache::new(font, usize::MAX);
+    cache.prewarm(&mut triangulator, "oli".chars(), &options).unwrap();
+    assert_eq!(cache.len(), 3);
+    assert_eq!(cache.stats(), CacheStats::default());
+
+    // 'o' becomes the most recently used glyph, so 'l' is the least recently used one.
+    cache
+        .triangulate_glyph_with_options(&mut triangulator, o, &options)
+        .unwrap();
+    let usage = cache.memory_usage();
+    cache.set_memory_budget(usage - 1);
+
+    assert_eq!(cache.len(), 2);
+    assert!(!cache.contains(l, &options));
+    assert!(cache.contains(o, &options) && cache.contains(i, &options));
+    assert!(cache.memory_usage() <= cache.memory_budget());
+    assert_eq!(cache.stats().evictions, 1);
+
+    // The requested glyph is kept even if it doesn't fit into the budget alone.
+    cache.set_memory_budget(0);
+    cache
+        .triangulate_glyph_with_options(&mut triangulator, l, &options)
+        .unwrap();
+    assert_eq!(cache.len(), 1);
+    assert!(cache.contains(l, &options));
+
+    cache.clear();
+    assert!(cache.is_empty());
+    assert_eq!(cache.memory_usage(), 0);
+
+    // Prewarming stops when the cache is full instead of evicting the prewarmed glyphs.
+    cache.set_memory_budget(usize::MAX);
+    cache.prewarm(&mut triangulator, "o".chars(), &options).unwrap();
+    let o_usage = cache.memory_usage();
+    cache.clear();
+    cache.reset_stats();
+
+    cache.set_memory_budget(o_usage);
+    cache.prewarm(&mut triangulator, "oli".chars(), &options).unwrap();
+    assert_eq!(cache.len(), 1);
+    assert!(cache.contains(o, &options));
+    assert_eq!(cache.memory_usage(), o_usage);
+    assert_eq!(cache.stats(), CacheStats::default());
+}
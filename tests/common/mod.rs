@@ -1,10 +1,25 @@
+#![allow(dead_code)]
+
 use vdtfont::{delaunay::*, *};
 
 use std::mem;
 
 use arena_system::Handle;
 
-pub fn plot(bitmap: &mut Vec<f32>, width: usize, height: usize, x: usize, y: usize, c: f32) {
+pub const OPENSANS_REGULAR: &[u8] =
+    include_bytes!("/usr/share/fonts/truetype/open-sans/OpenSans-Regular.ttf");
+
+/// Returns the CPU triangulator, the Open Sans Regular font and the options
+/// with the small image, in which glyphs are triangulated quickly.
+pub fn opensans_regular() -> (Triangulator, Font, TriangulationOptions) {
+    let triangulator = Triangulator::new(Backend::Cpu).unwrap();
+    let font = Font::from_vec(OPENSANS_REGULAR.to_vec()).unwrap();
+    let options = TriangulationOptions { max_image_size: 256, ..Default::default() };
+
+    (triangulator, font, options)
+}
+
+pub fn plot(bitmap: &mut [f32], width: usize, height: usize, x: usize, y: usize, c: f32) {
     if x < width && y < height {
         unsafe {
             let pixel = bitmap.get_unchecked_mut(x + y * width);
@@ -13,7 +28,7 @@ pub fn plot(bitmap: &mut Vec<f32>, width: usize, height: usize, x: usize, y: usi
     }
 }
 
-pub fn draw_line(bitmap: &mut Vec<f32>, width: usize, height: usize, p0: Point, p1: Point) {
+pub fn draw_line(bitmap: &mut [f32], width: usize, height: usize, p0: Point, p1: Point) {
     let mut x0 = p0.x();
     let mut y0 = p0.y();
     let mut x1 = p1.x();
@@ -63,7 +78,7 @@ pub fn rasterize_glyph(glyph: &TriangulatedGlyph) -> Vec<u8> {
 
     glyph
         .triangles()
-        .handle_iter::<DelaunayTriangleHandle>(&glyph.points())
+        .handle_iter::<DelaunayTriangleHandle>(glyph.points())
         .for_each(|t| {
             if let Ok(t) = t.get() {
                 if matches!(t.visibility(), Visibility::Visible) {
@@ -3,16 +3,17 @@ mod common;
 use vdtfont::*;
 use std::io::Cursor;
 
-const OPENSANS_REGULAR: &'static [u8] =
-    include_bytes!("/usr/share/fonts/truetype/open-sans/OpenSans-Regular.ttf");
-
 macro_rules! test_glyph {
     ($letter: literal, $font: expr, $reference: literal) => {
-        let mut font = Font::from_vec($font.to_vec()).unwrap();
+        test_glyph!($letter, $font, Backend::default_opencl().unwrap(), $reference);
+    };
+    ($letter: literal, $font: expr, $backend: expr, $reference: literal) => {
+        let mut triangulator = Triangulator::new($backend).unwrap();
+        let font = Font::from_vec($font.to_vec()).unwrap();
 
         let glyph = font.glyph($letter);
-        let outlined_glyph = font.outline_glyph(glyph);
-        let triangulated_glyph = font.triangulate_glyph(outlined_glyph).unwrap();
+        let outlined_glyph = font.outline_glyph(glyph).unwrap();
+        let triangulated_glyph = font.triangulate_glyph(&mut triangulator, outlined_glyph).unwrap();
 
         let glyph = common::rasterize_glyph(&triangulated_glyph);
         let reference_glyph = image::load(
@@ -31,5 +32,272 @@ macro_rules! test_glyph {
 
 #[test]
 fn test_opensans_regular_r() {
-    test_glyph!('r', OPENSANS_REGULAR, "../reference_glyphs/opensans_regular_r.png");
+    test_glyph!('r', common::OPENSANS_REGULAR, "../reference_glyphs/opensans_regular_r.png");
+}
+
+#[test]
+fn test_opensans_regular_r_cpu() {
+    test_glyph!(
+        'r',
+        common::OPENSANS_REGULAR,
+        Backend::Cpu,
+        "../reference_glyphs/opensans_regular_r.png"
+    );
+}
+
+#[test]
+fn test_opensans_regular_space() {
+    let (mut triangulator, font, _) = common::opensans_regular();
+
+    let glyph = font.glyph(' ');
+    let outlined_glyph = font.outline_glyph(glyph).unwrap();
+    assert!(outlined_glyph.is_empty());
+
+    let triangulated_glyph = font.triangulate_glyph(&mut triangulator, outlined_glyph).unwrap();
+    assert!(triangulated_glyph.is_empty());
+    assert!(font.hor_advance(glyph).unwrap() > 0.0);
+}
+
+#[test]
+fn test_opensans_regular_coarse_options() {
+    let (_, font, options) = common::opensans_regular();
+    let glyph = font.glyph('o');
+
+    let fine_glyph = font.outline_glyph(glyph).unwrap();
+    let coarse_options = TriangulationOptions {
+        flatness: Flatness::Pixels { distance: 0.5, pixels_per_em: 16.0 },
+        ..options
+    };
+    let coarse_glyph = font.outline_glyph_with_options(glyph, &coarse_options).unwrap();
+
+    assert!(coarse_glyph.points().len() < fine_glyph.points().len());
+    assert!(coarse_glyph.dim() <= options.max_image_size);
+
+    let tiny_options = TriangulationOptions { min_image_size: 4, ..Default::default() };
+    assert!(matches!(tiny_options.validate(), Err(Error::InvalidArgument(_))));
+}
+
+#[test]
+fn test_opensans_regular_max_image_size() {
+    let (_, font, options) = common::opensans_regular();
+
+    // The maximal image size only clamps the dimension which is chosen for the glyph.
+    for glyph in (0..font.glyph_count() as u16).map(Glyph) {
+        let dim = font.outline_glyph(glyph).unwrap().dim();
+        let clamped_dim = font.outline_glyph_with_options(glyph, &options).unwrap().dim();
+
+        assert_eq!(clamped_dim, dim.min(options.max_image_size));
+    }
+}
+
+#[test]
+fn test_opensans_regular_o_mesh() {
+    let (mut triangulator, font, options) = common::opensans_regular();
+
+    let glyph = font.glyph('o');
+    let outlined_glyph = font.outline_glyph_with_options(glyph, &options).unwrap();
+    let triangulated_glyph = font
+        .triangulate_glyph_with_options(&mut triangulator, outlined_glyph, &options)
+        .unwrap();
+
+    let mesh = triangulated_glyph.to_mesh();
+    assert!(!mesh.is_empty());
+    assert_eq!(mesh.indices().len() % 3, 0);
+    assert!(mesh.positions().len() < triangulated_glyph.points().len());
+    assert!(mesh.indices().iter().all(|&i| (i as usize) < mesh.positions().len()));
+}
+
+#[test]
+fn test_opensans_regular_o_placement() {
+    let (mut triangulator, font, options) = common::opensans_regular();
+
+    let glyph = font.glyph('o');
+    let outlined_glyph = font.outline_glyph_with_options(glyph, &options).unwrap();
+    let triangulated_glyph = font
+        .triangulate_glyph_with_options(&mut triangulator, outlined_glyph, &options)
+        .unwrap();
+
+    let placement = triangulated_glyph.placement();
+    let bounds = triangulated_glyph.bounds();
+    let tolerance = 2.0 / placement.scale();
+
+    let mesh = triangulated_glyph.to_mesh_in_font_units();
+    assert!(mesh.positions().iter().all(|p| {
+        p[0] >= bounds.x_min as f32 - tolerance
+            && p[0] <= bounds.x_max as f32 + tolerance
+            && p[1] >= bounds.y_min as f32 - tolerance
+            && p[1] <= bounds.y_max as f32 + tolerance
+    }));
+
+    let em_mesh = triangulated_glyph.to_em_mesh();
+    let pixel_mesh = triangulated_glyph.to_mesh_at_pixel_size(32.0);
+    for i in 0..mesh.positions().len() {
+        let (p, em, px) = (mesh.positions()[i], em_mesh.positions()[i], pixel_mesh.positions()[i]);
+        assert!((p[0] / placement.units_per_em() - em[0]).abs() < 1e-4);
+        assert!((em[1] * 32.0 - px[1]).abs() < 1e-3);
+    }
+}
+
+#[test]
+fn test_opensans_regular_o_sub_pixel_points() {
+    let (mut triangulator, font, options) = common::opensans_regular();
+
+    let glyph = font.glyph('o');
+    let outlined_glyph = font.outline_glyph_with_options(glyph, &options).unwrap();
+    let outline = outlined_glyph
+        .points()
+        .handle_iter::<PointHandle>(None)
+        .map(|p| [p.x(), p.y()])
+        .collect::<Vec<_>>();
+    assert!(outline.iter().flatten().any(|c| c.fract() != 0.0));
+
+    let triangulated_glyph = font
+        .triangulate_glyph_with_options(&mut triangulator, outlined_glyph, &options)
+        .unwrap();
+
+    let points = triangulated_glyph.points();
+    for (i, p) in outline.into_iter().enumerate() {
+        let point = points.handle::<PointHandle>((i as i64).into(), None);
+        assert_eq!([point.x(), point.y()], p);
+    }
+
+    triangulated_glyph
+        .triangles()
+        .handle_iter::<delaunay::DelaunayTriangleHandle>(points)
+        .for_each(|t| assert!(t.is_counterclockwise()));
+}
+
+#[test]
+fn test_opensans_regular_inverted_triangles() {
+    let (mut triangulator, font, options) = common::opensans_regular();
+    let options = TriangulationOptions { max_image_size: 64, ..options };
+
+    // The points of the glyphs are closer to each other than a few pixels, so restoring
+    // their sub-pixel coordinates inverts the triangles which are built on the pixel grid.
+    // The triangles of 's' are repaired by flips, while the ones of 'o' are rebuilt.
+    for c in ['s', 'o'] {
+        let glyph = font.glyph(c);
+        let outlined_glyph = font.outline_glyph_with_options(glyph, &options).unwrap();
+        let triangulated_glyph = font
+            .triangulate_glyph_with_options(&mut triangulator, outlined_glyph, &options)
+            .unwrap();
+
+        let points = triangulated_glyph.points();
+        // Some of the resulting triangles are degenerate or clockwise on the pixel grid.
+        let is_folded_on_grid = |t: &delaunay::DelaunayTriangleHandle| {
+            let [p0, p1, p2] = t.points().map(|p| [p.x().floor(), p.y().floor()]);
+            (p1[0] - p0[0]) * (p2[1] - p0[1]) - (p1[1] - p0[1]) * (p2[0] - p0[0]) >= 0.0
+        };
+        assert!(triangulated_glyph
+            .triangles()
+            .handle_iter::<delaunay::DelaunayTriangleHandle>(points)
+            .any(|t| is_folded_on_grid(&t)));
+
+        let report = triangulated_glyph.validate();
+        assert!(report.is_valid(), "{c}: {report}");
+    }
+}
+
+#[test]
+fn test_opensans_regular_o_fill_rules() {
+    let (mut triangulator, font, options) = common::opensans_regular();
+
+    // The contours of 'o' don't overlap, so both rules must produce the same triangulation.
+    let triangle_counts = [FillRule::NonZero, FillRule::EvenOdd].map(|fill_rule| {
+        let options = TriangulationOptions { fill_rule, ..options };
+
+        let glyph = font.glyph('o');
+        let outlined_glyph = font.outline_glyph_with_options(glyph, &options).unwrap();
+        font.triangulate_glyph_with_options(&mut triangulator, outlined_glyph, &options)
+            .unwrap()
+            .to_mesh()
+            .triangle_count()
+    });
+
+    assert!(triangle_counts[0] > 0);
+    assert_eq!(triangle_counts[0], triangle_counts[1]);
+}
+
+#[test]
+fn test_opensans_regular_o_validate() {
+    let (mut triangulator, font, options) = common::opensans_regular();
+
+    let glyph = font.glyph('o');
+    let outlined_glyph = font.outline_glyph_with_options(glyph, &options).unwrap();
+    let triangulated_glyph = font
+        .triangulate_glyph_with_options(&mut triangulator, outlined_glyph, &options)
+        .unwrap();
+
+    let report = triangulated_glyph.validate();
+    assert!(report.is_valid(), "{report}");
+}
+
+#[test]
+fn test_opensans_regular_o_sdf() {
+    let (mut triangulator, font, _) = common::opensans_regular();
+
+    let sdf_glyph = font.glyph_sdf(&mut triangulator, font.glyph('o'), 64.0, 4.0).unwrap();
+    let field = sdf_glyph.field();
+    assert!(!sdf_glyph.is_empty());
+
+    // The middle row crosses the outer contour and the hole of 'o' twice.
+    let y = field.height() / 2;
+    let row = (0..field.width()).map(|x| field.distance(x, y).unwrap()).collect::<Vec<_>>();
+    let sign_changes = row.windows(2).filter(|w| (w[0] > 0.0) != (w[1] > 0.0)).count();
+    assert_eq!(sign_changes, 4);
+    assert!(row[0] < 0.0 && row[field.width() / 2] < 0.0);
+
+    // The distance to the outline changes by at most a pixel between adjacent pixels.
+    for y in 0..field.height() {
+        for x in 1..field.width() {
+            let (d0, d1) = (field.distance(x - 1, y).unwrap(), field.distance(x, y).unwrap());
+            assert!((d0 - d1).abs() <= 1.05, "{d0} and {d1} at ({x}; {y})");
+        }
+    }
+
+    let image = sdf_glyph.to_image();
+    assert_eq!(image.dimensions(), (field.width() as u32, field.height() as u32));
+
+    // The image of a tiny field isn't smaller than the OpenCL work groups.
+    let tiny_glyph = font.glyph_sdf(&mut triangulator, font.glyph('o'), 2.0, 1.0).unwrap();
+    assert!(!tiny_glyph.is_empty());
+
+    let space = font.glyph_sdf(&mut triangulator, font.glyph(' '), 64.0, 4.0).unwrap();
+    assert!(space.is_empty());
+}
+
+#[test]
+fn test_opensans_regular_l_msdf() {
+    let (mut triangulator, font, _) = common::opensans_regular();
+
+    let glyph = font.glyph('L');
+    let msdf_glyph = font.glyph_msdf(&mut triangulator, glyph, 64.0, 4.0).unwrap();
+    let sdf_glyph = font.glyph_sdf(&mut triangulator, glyph, 64.0, 4.0).unwrap();
+    let (msdf, sdf) = (msdf_glyph.field(), sdf_glyph.field());
+    assert_eq!((msdf.width(), msdf.height()), (sdf.width(), sdf.height()));
+
+    // The median has the same sign as the distance to the outline.
+    for y in 0..msdf.height() {
+        for x in 0..msdf.width() {
+            let (median, distance) = (msdf.median(x, y).unwrap(), sdf.distance(x, y).unwrap());
+            assert_eq!(median > 0.0, distance > 0.0, "{median} and {distance} at ({x}; {y})");
+        }
+    }
+
+    // Outside the corners of 'L', the median is the distance to the sides of the corners,
+    // while the distance to the outline is the distance to the corners themselves.
+    let sharp_pixels = (0..msdf.height())
+        .flat_map(|y| (0..msdf.width()).map(move |x| (x, y)))
+        .filter(|&(x, y)| {
+            let (median, distance) = (msdf.median(x, y).unwrap(), sdf.distance(x, y).unwrap());
+            distance < 0.0 && median > distance + 0.5
+        })
+        .count();
+    assert!(sharp_pixels > 0);
+
+    let image = msdf_glyph.to_image();
+    assert_eq!(image.dimensions(), (msdf.width() as u32, msdf.height() as u32));
+
+    let tiny_glyph = font.glyph_msdf(&mut triangulator, glyph, 2.0, 1.0).unwrap();
+    assert!(!tiny_glyph.is_empty());
 }
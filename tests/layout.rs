@@ -0,0 +1,235 @@
+use vdtfont::*;
+
+const OPENSANS_REGULAR: &[u8] =
+    include_bytes!("/usr/share/fonts/truetype/open-sans/OpenSans-Regular.ttf");
+
+#[test]
+fn test_text_layout_advances() {
+    let font = Font::from_vec(OPENSANS_REGULAR.to_vec()).unwrap();
+    let scale = 32.0 / font.units_per_em().unwrap();
+
+    let layout = TextLayout::new(&font, "AV\r\nTo", 32.0).unwrap();
+    let [a, v, t, o] = ['A', 'V', 'T', 'o'].map(|c| font.glyph(c));
+    let advance = |glyph| font.hor_advance(glyph).unwrap() * scale;
+    let line_height = (font.ascender() - font.descender() + font.line_gap()) * scale;
+
+    assert_eq!(layout.line_count(), 2);
+    assert_eq!(layout.line_height(), line_height);
+
+    let glyphs = layout.glyphs();
+    assert_eq!(glyphs.iter().map(|g| g.glyph()).collect::<Vec<_>>(), [a, v, t, o]);
+    assert_eq!(glyphs.iter().map(|g| g.cluster()).collect::<Vec<_>>(), [0, 1, 4, 5]);
+
+    // The kerning is added to the advance of the first glyph of the pair.
+    let kerning = font.hor_kerning(a, v) * scale;
+    assert_eq!(glyphs[0].position(), [0.0, 0.0]);
+    assert_eq!(glyphs[0].advance(), advance(a) + kerning);
+    assert_eq!(glyphs[1].position(), [advance(a) + kerning, 0.0]);
+
+    // The second line starts below the first one.
+    assert_eq!(glyphs[2].position(), [0.0, -line_height]);
+    let second_line = advance(t) + font.hor_kerning(t, o) * scale + advance(o);
+    let first_line = glyphs[1].position()[0] + advance(v);
+    assert!((layout.width() - first_line.max(second_line)).abs() < 1e-4);
+
+    assert!(TextLayout::new(&font, "", 32.0).unwrap().is_empty());
+
+    for pixels_per_em in [0.0, -32.0, f32::NAN] {
+        let layout = TextLayout::new(&font, "AV", pixels_per_em);
+        assert!(matches!(layout, Err(Error::InvalidArgument(_))));
+    }
+}
+
+#[test]
+fn test_text_layout_mesh() {
+    let mut triangulator = Triangulator::new(Backend::Cpu).unwrap();
+    let font = Font::from_vec(OPENSANS_REGULAR.to_vec()).unwrap();
+    let options = TriangulationOptions { max_image_size: 256, ..Default::default() };
+
+    let layout = TextLayout::new(&font, "lo l", 20.0).unwrap();
+    let mut atlas = MeshAtlas::new();
+    assert!(layout.to_mesh(&atlas).is_err());
+
+    // Every glyph is triangulated once.
+    let missing_glyphs = layout.missing_glyphs(&atlas);
+    assert_eq!(missing_glyphs.len(), 3);
+
+    let outlined_glyphs = missing_glyphs
+        .iter()
+        .map(|&glyph| font.outline_glyph_with_options(glyph, &options).unwrap())
+        .collect::<Vec<_>>();
+    atlas.extend(
+        &triangulator
+            .triangulate_glyphs_with_options(&outlined_glyphs, &options)
+            .unwrap(),
+    );
+    assert!(layout.missing_glyphs(&atlas).is_empty());
+
+    let mesh = layout.to_mesh(&atlas).unwrap();
+    let triangle_count: usize = layout
+        .glyphs()
+        .iter()
+        .map(|g| atlas.entry(g.glyph()).unwrap().triangle_count())
+        .sum();
+    assert_eq!(mesh.triangle_count(), triangle_count);
+
+    // The vertices of the glyphs are placed around their origins.
+    let (mut first_vertex, mut first_index) = (0, 0);
+    for positioned_glyph in layout.glyphs() {
+        let entry = atlas.entry(positioned_glyph.glyph()).unwrap();
+        let bounds = entry.bounds().map(|b| b * layout.pixels_per_em());
+        let [x, y] = positioned_glyph.position();
+
+        let vertices = first_vertex..first_vertex + entry.vertices().len();
+        assert!(mesh.positions()[vertices.clone()].iter().all(|p| {
+            (x + bounds[0] - 1e-2..=x + bounds[2] + 1e-2).contains(&p[0])
+                && (y + bounds[1] - 1e-2..=y + bounds[3] + 1e-2).contains(&p[1])
+        }));
+
+        let indices = &mesh.indices()[first_index..first_index + entry.indices().len()];
+        assert!(indices.iter().all(|&i| vertices.contains(&(i as usize))));
+
+        first_vertex = vertices.end;
+        first_index += entry.indices().len();
+    }
+}
+
+#[test]
+fn test_text_layout_paragraph() {
+    let font = Font::from_vec(OPENSANS_REGULAR.to_vec()).unwrap();
+    let text = "lol lol lol lol";
+    let max_width = TextLayout::new(&font, "lol lol", 32.0).unwrap().width() + 1.0;
+
+    let layout_with_alignment = |alignment| {
+        let options = LayoutOptions { max_width: Some(max_width), alignment };
+        TextLayout::with_options(&font, text, 32.0, &options).unwrap()
+    };
+
+    // The lines are broken after the spaces which stay at the ends of the lines.
+    let layout = layout_with_alignment(Alignment::Left);
+    let lines = layout.lines();
+    assert_eq!(lines.iter().map(|l| l.text()).collect::<Vec<_>>(), [0..8, 8..15]);
+    assert_eq!(lines.iter().map(|l| l.glyphs()).collect::<Vec<_>>(), [0..8, 8..15]);
+    assert_eq!(lines[1].baseline(), -layout.line_height());
+    assert!(lines.iter().all(|l| l.bounds()[0] == 0.0 && l.width() <= max_width));
+
+    let scale = 32.0 / font.units_per_em().unwrap();
+    assert_eq!(lines[0].bounds()[1], font.descender() * scale);
+    assert_eq!(lines[0].bounds()[3], font.ascender() * scale);
+
+    let layout = layout_with_alignment(Alignment::Right);
+    assert!(layout.lines().iter().all(|l| (l.bounds()[2] - max_width).abs() < 1e-3));
+
+    let layout = layout_with_alignment(Alignment::Center);
+    assert!(layout
+        .lines()
+        .iter()
+        .all(|l| (l.bounds()[0] + l.bounds()[2] - max_width).abs() < 1e-3));
+
+    // The spaces of the wrapped line are stretched, but the last line isn't justified.
+    let layout = layout_with_alignment(Alignment::Justify);
+    let lines = layout.lines();
+    assert!((lines[0].width() - max_width).abs() < 1e-3);
+    assert!(lines[1].width() < max_width);
+
+    let glyphs = layout.glyphs();
+    let [space, next] = [3, 4].map(|i| glyphs[i]);
+    assert!((space.position()[0] + space.advance() - next.position()[0]).abs() < 1e-3);
+
+    let options = LayoutOptions { max_width: Some(0.0), ..Default::default() };
+    assert!(TextLayout::with_options(&font, text, 32.0, &options).is_err());
+}
+
+#[test]
+fn test_text_layout_carets() {
+    let font = Font::from_vec(OPENSANS_REGULAR.to_vec()).unwrap();
+
+    let layout = TextLayout::new(&font, "lo\nl\n", 32.0).unwrap();
+    let line_height = layout.line_height();
+    let glyphs = layout.glyphs();
+    assert_eq!(layout.line_count(), 3);
+
+    let o_end = glyphs[1].position()[0] + glyphs[1].advance();
+    assert_eq!(layout.caret_position(0), [0.0, 0.0]);
+    assert_eq!(layout.caret_position(1), [glyphs[1].position()[0], 0.0]);
+    assert_eq!(layout.caret_position(2), [o_end, 0.0]);
+    assert_eq!(layout.caret_position(3), [0.0, -line_height]);
+    assert_eq!(layout.caret_position(4), [glyphs[2].advance(), -line_height]);
+    assert_eq!(layout.caret_position(5), [0.0, -2.0 * line_height]);
+
+    assert_eq!(layout.line_at(2), 0);
+    assert_eq!(layout.line_at(3), 1);
+    assert_eq!(layout.line_at(5), 2);
+
+    // Points are matched to the closest carets.
+    assert_eq!(layout.hit_test([-10.0, 100.0]), 0);
+    assert_eq!(layout.hit_test([glyphs[1].position()[0] + 0.1, 0.0]), 1);
+    assert_eq!(layout.hit_test([o_end - 0.1, 0.0]), 2);
+    assert_eq!(layout.hit_test([o_end + 100.0, 0.0]), 2);
+    assert_eq!(layout.hit_test([0.1, -line_height]), 3);
+    assert_eq!(layout.hit_test([100.0, -10.0 * line_height]), 5);
+
+    let layout = TextLayout::new(&font, "", 32.0).unwrap();
+    assert_eq!(layout.line_count(), 1);
+    assert_eq!(layout.caret_position(0), [0.0, 0.0]);
+    assert_eq!(layout.hit_test([10.0, 10.0]), 0);
+}
+
+#[test]
+fn test_text_layout_bidi() {
+    let font = Font::from_vec(OPENSANS_REGULAR.to_vec()).unwrap();
+
+    // The Hebrew run is reversed inside the left-to-right line.
+    let layout = TextLayout::new(&font, "ab אב cd", 32.0).unwrap();
+    let glyphs = layout.glyphs();
+    assert_eq!(glyphs.iter().map(|g| g.cluster()).collect::<Vec<_>>(), [0, 1, 2, 5, 3, 7, 8, 9]);
+    assert_eq!(
+        glyphs
+            .iter()
+            .filter(|g| g.is_rtl())
+            .map(|g| g.cluster())
+            .collect::<Vec<_>>(),
+        [5, 3]
+    );
+    assert!(glyphs
+        .windows(2)
+        .all(|g| g[0].position()[0] + g[0].advance() == g[1].position()[0]));
+
+    // Parentheses of the right-to-left line are mirrored.
+    let layout = TextLayout::new(&font, "א(ב)", 32.0).unwrap();
+    let glyphs = layout.glyphs();
+    assert_eq!(glyphs.iter().map(|g| g.cluster()).collect::<Vec<_>>(), [5, 3, 2, 0]);
+    assert_eq!(glyphs[0].glyph(), font.glyph('('));
+    assert_eq!(glyphs[2].glyph(), font.glyph(')'));
+    assert!(glyphs.iter().all(|g| g.is_rtl()));
+
+    // Carets of the right-to-left text move from right to left.
+    let layout = TextLayout::new(&font, "אב", 32.0).unwrap();
+    let [bet, alef] = [layout.glyphs()[0], layout.glyphs()[1]];
+    let width = alef.position()[0] + alef.advance();
+    assert_eq!(layout.caret_position(0), [width, 0.0]);
+    assert_eq!(layout.caret_position(2), [bet.advance(), 0.0]);
+    assert_eq!(layout.caret_position(4), [0.0, 0.0]);
+
+    assert_eq!(layout.hit_test([width + 10.0, 0.0]), 0);
+    assert_eq!(layout.hit_test([width - 0.1, 0.0]), 0);
+    assert_eq!(layout.hit_test([bet.advance() - 0.1, 0.0]), 2);
+    assert_eq!(layout.hit_test([-10.0, 0.0]), 4);
+
+    // The trailing whitespace of the wrapped right-to-left line hangs at its visual left.
+    let max_width = TextLayout::new(&font, "אב אב", 32.0).unwrap().width() + 1.0;
+    let options = LayoutOptions { max_width: Some(max_width), alignment: Alignment::Right };
+    let layout = TextLayout::with_options(&font, "אב אב אב אב", 32.0, &options).unwrap();
+    let lines = layout.lines();
+    assert_eq!(lines.iter().map(|l| l.text()).collect::<Vec<_>>(), [0..10, 10..19]);
+
+    for line in lines {
+        let glyphs = &layout.glyphs()[line.glyphs()];
+        let right = glyphs.iter().map(|g| g.position()[0] + g.advance()).fold(0.0, f32::max);
+        assert!((right - max_width).abs() < 1e-3 && (line.bounds()[2] - max_width).abs() < 1e-3);
+    }
+
+    let space = layout.glyphs()[lines[0].glyphs().start];
+    assert_eq!(space.cluster(), 9);
+    assert!((space.position()[0] + space.advance() - lines[0].bounds()[0]).abs() < 1e-3);
+}
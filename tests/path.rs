@@ -0,0 +1,144 @@
+use vdtfont::*;
+
+use std::f32::consts::PI;
+
+// Returns the signed area enclosed by the contours of the path.
+fn path_area(path: &Path) -> f32 {
+    let points = path.points();
+    points
+        .iter()
+        .map(|p| {
+            let previous = &points[p.previous_in_outline() as usize];
+            (previous.x() * p.y() - p.x() * previous.y()) / 2.0
+        })
+        .sum()
+}
+
+#[test]
+fn test_svg_relative_commands() {
+    let absolute = Path::from_svg(
+        "M 10 10 L 20 10 H 30 V 20 C 30 25 25 30 20 30 S 10 25 10 20 \
+        Q 5 15 10 10 T 10 0 A 5 5 0 0 1 10 10 Z",
+        0.1,
+    )
+    .unwrap();
+    let relative = Path::from_svg(
+        "m10,10l10,0h10v10c0,5-5,10-10,10s-10-5-10-10q-5-5 0-10t0-10a5,5,0,0,1,0,10z",
+        0.1,
+    )
+    .unwrap();
+
+    assert!(!absolute.is_empty());
+    assert_eq!(absolute.points().len(), relative.points().len());
+    absolute.points().iter().zip(relative.points()).for_each(|(a, r)| {
+        assert!((a.x() - r.x()).abs() < 1e-4 && (a.y() - r.y()).abs() < 1e-4);
+        assert_eq!(a.previous_in_outline(), r.previous_in_outline());
+    });
+}
+
+#[test]
+fn test_svg_arc_circle() {
+    // The circle with the radius 10 which consists of two arcs.
+    let path = Path::from_svg("M-10 0A10 10 0 1 0 10 0A10 10 0 1 0-10 0z", 0.01).unwrap();
+
+    assert!(path.points().iter().all(|p| (p.x().hypot(p.y()) - 10.0).abs() < 0.02));
+    assert!((path_area(&path).abs() - PI * 100.0).abs() < 1.0);
+
+    // The radii are scaled up to connect the points.
+    let path = Path::from_svg("M0 0A1 1 0 0 1 20 0z", 0.01).unwrap();
+    assert!((path_area(&path).abs() - PI * 50.0).abs() < 1.0);
+}
+
+#[test]
+fn test_svg_number_syntax() {
+    let compact = Path::from_svg("M.5-.5l1.5.5-1e1-1E-1z", 0.1).unwrap();
+    let spaced = Path::from_svg("M 0.5 -0.5 l 1.5 0.5 -10 -0.1 z", 0.1).unwrap();
+
+    assert_eq!(compact, spaced);
+}
+
+#[test]
+fn test_svg_errors() {
+    let offset = |data: &str| match Path::from_svg(data, 0.1) {
+        Err(Error::PathParsing { offset, .. }) => offset,
+        result => panic!("Unexpected result {result:?} for {data}"),
+    };
+
+    assert_eq!(offset("L 10 10"), 0);
+    assert_eq!(offset("M 0 0 L 10"), 10);
+    assert_eq!(offset("M 0 0 X 10 10"), 6);
+    assert_eq!(offset("M 0 0 L 1 1 1 0 Z 5"), 18);
+    assert_eq!(offset("M 0 0 A 1 1 0 2 0 1 1"), 14);
+
+    assert!(matches!(Path::from_svg("M 0 0", 0.0), Err(Error::InvalidArgument(_))));
+}
+
+#[test]
+fn test_path_builder_contours() {
+    let mut builder = PathBuilder::new(0.1).unwrap();
+
+    // The contour isn't closed explicitly.
+    builder.move_to(0.0, 0.0);
+    builder.line_to(1.0, 0.0);
+    builder.line_to(1.0, 1.0);
+    // The contour doesn't enclose any area.
+    builder.move_to(5.0, 5.0);
+    builder.line_to(6.0, 5.0);
+    // The contour returns to its first point.
+    builder.move_to(2.0, 0.0);
+    builder.line_to(3.0, 0.0);
+    builder.line_to(3.0, 1.0);
+    builder.line_to(2.0, 0.0);
+    builder.close();
+    // Segments after the closed contour start a new one at the same point.
+    builder.line_to(2.0, -1.0);
+    builder.line_to(3.0, -1.0);
+
+    let path = builder.build();
+    let links = path.points().iter().map(|p| p.previous_in_outline()).collect::<Vec<_>>();
+
+    assert_eq!(path.points().len(), 9);
+    assert_eq!(links, [2, 0, 1, 5, 3, 4, 8, 6, 7]);
+    assert_eq!([path.points()[6].x(), path.points()[6].y()], [2.0, 0.0]);
+}
+
+#[test]
+fn test_triangulate_svg_path() {
+    let mut triangulator = Triangulator::new(Backend::Cpu).unwrap();
+
+    // The square with a round hole.
+    let path =
+        Path::from_svg("M0 0H40V40H0Z M20 10A10 10 0 1 0 20 30A10 10 0 1 0 20 10Z", 0.1).unwrap();
+    let mesh = triangulator.triangulate_path(&path).unwrap();
+
+    let area: f32 = mesh
+        .indices()
+        .chunks(3)
+        .map(|t| {
+            let [a, b, c] = [0, 1, 2].map(|i| mesh.positions()[t[i] as usize]);
+            ((b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0])) / 2.0
+        })
+        .sum();
+
+    assert_eq!(mesh.positions().len(), path.points().len());
+    assert!((area - path_area(&path)).abs() < 1.0);
+    assert!((area - (1600.0 - PI * 100.0)).abs() < 2.0);
+}
+
+#[test]
+fn test_path_corners() {
+    let square = Path::from_svg("M0 0H10V10H0Z", 0.1).unwrap();
+    assert_eq!(square.corners(), [true; 4]);
+
+    // The contour which returns to its first point explicitly has the same corners.
+    let returned = Path::from_svg("M0 0H10V10H0V0Z", 0.1).unwrap();
+    assert_eq!(returned, square);
+
+    let circle = Path::from_svg("M-10 0A10 10 0 1 0 10 0A10 10 0 1 0-10 0z", 0.1).unwrap();
+    assert!(circle.corners().iter().all(|&corner| !corner));
+
+    // Only the ends of the arc meet the chord at corners.
+    let segment = Path::from_svg("M-10 0A10 10 0 0 0 10 0Z", 0.1).unwrap();
+    let corners = segment.corners().iter().filter(|&&corner| corner).count();
+    assert_eq!(corners, 2);
+}
@@ -0,0 +1,36 @@
+use vdtfont::predicates::{incircle, orient2d};
+
+#[test]
+fn test_orient2d_nearly_collinear() {
+    // The points `b` and `c` are on the line `y = x`, so the orientation of `a`
+    // depends only on the sign of `a.y - a.x`.
+    let b = [12.0, 12.0];
+    let c = [24.0, 24.0];
+
+    for i in 0..64 {
+        for j in 0..64 {
+            let x = 0.5 + i as f64 * f64::EPSILON;
+            let y = 0.5 + j as f64 * f64::EPSILON;
+            let orientation = orient2d([x, y], b, c);
+
+            match y.partial_cmp(&x).unwrap() {
+                std::cmp::Ordering::Greater => assert!(orientation > 0.0),
+                std::cmp::Ordering::Less => assert!(orientation < 0.0),
+                std::cmp::Ordering::Equal => assert_eq!(orientation, 0.0),
+            }
+        }
+    }
+}
+
+#[test]
+fn test_incircle_cocircular() {
+    let a = [0.0, 0.0];
+    let b = [1.0, 0.0];
+    let c = [1.0, 1.0];
+
+    assert_eq!(incircle(a, b, c, [0.0, 1.0]), 0.0);
+    assert!(incircle(a, b, c, [0.0, 1.0 - f64::EPSILON]) > 0.0);
+    assert!(incircle(a, b, c, [0.0, 1.0 + f64::EPSILON]) < 0.0);
+    // The sign is reversed for clockwise points.
+    assert!(incircle(c, b, a, [0.5, 0.5]) < 0.0);
+}
@@ -0,0 +1,64 @@
+#![cfg(feature = "shaping")]
+
+use vdtfont::*;
+
+const OPENSANS_REGULAR: &[u8] =
+    include_bytes!("/usr/share/fonts/truetype/open-sans/OpenSans-Regular.ttf");
+
+#[test]
+fn test_shape() {
+    let font = Font::from_vec(OPENSANS_REGULAR.to_vec()).unwrap();
+
+    let shaped_glyphs = font.shape("lol");
+    let glyphs = shaped_glyphs.iter().map(|g| g.glyph()).collect::<Vec<_>>();
+    let clusters = shaped_glyphs.iter().map(|g| g.cluster()).collect::<Vec<_>>();
+    assert_eq!(glyphs, ['l', 'o', 'l'].map(|c| font.glyph(c)));
+    assert_eq!(clusters, [0, 1, 2]);
+
+    let o = font.shape("o")[0];
+    assert_eq!(o.advance(), [font.hor_advance(font.glyph('o')).unwrap(), 0.0]);
+    assert_eq!(o.offset(), [0.0, 0.0]);
+
+    // Glyphs of the right-to-left text are in the visual order.
+    let options = ShapingOptions { direction: Some(Direction::RightToLeft), ..Default::default() };
+    let shaped_glyphs = font.shape_with_options("lol", &options).unwrap();
+    let clusters = shaped_glyphs.iter().map(|g| g.cluster()).collect::<Vec<_>>();
+    assert_eq!(clusters, [2, 1, 0]);
+
+    let options = ShapingOptions { features: vec!["liga=".into()], ..Default::default() };
+    assert!(font.shape_with_options("lol", &options).is_err());
+}
+
+#[test]
+fn test_text_layout_shaped() {
+    let font = Font::from_vec(OPENSANS_REGULAR.to_vec()).unwrap();
+    let scale = 32.0 / font.units_per_em().unwrap();
+
+    let layout = TextLayout::shaped(&font, "lo\r\nl", 32.0, &ShapingOptions::default()).unwrap();
+    assert_eq!(layout.line_count(), 2);
+
+    let glyphs = layout.glyphs();
+    assert_eq!(glyphs.iter().map(|g| g.cluster()).collect::<Vec<_>>(), [0, 1, 4]);
+
+    let l = font.shape("l")[0];
+    assert_eq!(glyphs[1].position(), [l.advance()[0] * scale, 0.0]);
+    assert_eq!(glyphs[2].position(), [0.0, -layout.line_height()]);
+
+    // Trailing whitespace doesn't widen the line.
+    let spaced_layout =
+        TextLayout::shaped(&font, "lo  ", 32.0, &ShapingOptions::default()).unwrap();
+    assert_eq!(spaced_layout.width(), layout.lines()[0].width());
+}
+
+#[test]
+fn test_text_layout_shaped_bidi() {
+    let font = Font::from_vec(OPENSANS_REGULAR.to_vec()).unwrap();
+
+    let layout = TextLayout::shaped(&font, "ab אב", 32.0, &ShapingOptions::default()).unwrap();
+    let glyphs = layout.glyphs();
+    assert_eq!(glyphs.iter().map(|g| g.cluster()).collect::<Vec<_>>(), [0, 1, 2, 5, 3]);
+    assert_eq!(
+        glyphs.iter().map(|g| g.is_rtl()).collect::<Vec<_>>(),
+        [false, false, false, true, true]
+    );
+}
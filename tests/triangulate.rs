@@ -0,0 +1,100 @@
+use vdtfont::*;
+
+// Returns the sum of signed areas of the triangles of the mesh.
+fn mesh_area(mesh: &Mesh) -> f32 {
+    mesh.indices()
+        .chunks(3)
+        .map(|t| {
+            let [a, b, c] = [0, 1, 2].map(|i| mesh.positions()[t[i] as usize]);
+            ((b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0])) / 2.0
+        })
+        .sum()
+}
+
+#[test]
+fn test_triangulate_points_grid() {
+    let mut triangulator = Triangulator::new(Backend::Cpu).unwrap();
+
+    let points = (0..25)
+        .map(|i| Point::new((i % 5) as f32 * 10.0, (i / 5) as f32 * 10.0))
+        .collect::<Vec<_>>();
+    let mesh = triangulator.triangulate_points(&points).unwrap();
+
+    // The positions are given in the space of the caller.
+    assert_eq!(mesh.positions().len(), points.len());
+    assert_eq!(mesh.positions()[7], [20.0, 10.0]);
+
+    // The convex hull is the square 40x40 which is split into 32 triangles.
+    assert_eq!(mesh.indices().len(), 32 * 3);
+    assert!((mesh_area(&mesh) - 1600.0).abs() < 1e-3);
+}
+
+#[test]
+fn test_triangulate_points_degenerate() {
+    let mut triangulator = Triangulator::new(Backend::Cpu).unwrap();
+
+    let points = [Point::new(1.0, 1.0), Point::new(2.0, 2.0)];
+    assert!(triangulator.triangulate_points(&points).unwrap().is_empty());
+
+    let points = [Point::new(0.0, 0.0), Point::new(f32::NAN, 1.0), Point::new(1.0, 0.0)];
+    assert!(matches!(triangulator.triangulate_points(&points), Err(Error::InvalidArgument(_))));
+}
+
+#[test]
+fn test_triangulate_polygon_with_hole() {
+    let mut triangulator = Triangulator::new(Backend::Cpu).unwrap();
+
+    let outer = [[0.0, 0.0], [30.0, 0.0], [30.0, 30.0], [0.0, 30.0]].map(|[x, y]| Point::new(x, y));
+    // The hole is directed opposite to the outer contour.
+    let hole =
+        [[10.0, 10.0], [10.0, 20.0], [20.0, 20.0], [20.0, 10.0]].map(|[x, y]| Point::new(x, y));
+
+    let mesh = triangulator.triangulate_polygon(&[outer, hole]).unwrap();
+
+    assert_eq!(mesh.positions().len(), 8);
+    assert_eq!(mesh.indices().len(), 8 * 3);
+    assert!((mesh_area(&mesh) - 800.0).abs() < 1e-3);
+}
+
+#[test]
+fn test_triangulate_polygon_invalid_contour() {
+    let mut triangulator = Triangulator::new(Backend::Cpu).unwrap();
+
+    let contour = [Point::new(0.0, 0.0), Point::new(1.0, 0.0)];
+    assert!(matches!(triangulator.triangulate_polygon(&[contour]), Err(Error::InvalidArgument(_))));
+}
+
+#[test]
+fn test_triangulate_glyphs_batched() {
+    let mut triangulator = Triangulator::new(Backend::Cpu).unwrap();
+    let font_data = include_bytes!("/usr/share/fonts/truetype/open-sans/OpenSans-Regular.ttf");
+    let font = Font::from_vec(font_data.to_vec()).unwrap();
+
+    // The glyphs don't fit into a single image, so they are triangulated in several passes.
+    let options = TriangulationOptions { max_image_size: 512, ..Default::default() };
+    let outline = |c| font.outline_glyph_with_options(font.glyph(c), &options).unwrap();
+    let chars = ['o', ' ', 'l', 'B', 'x', 'g', '8', '%'];
+
+    let outlined_glyphs = chars.map(outline);
+    let batched = triangulator
+        .triangulate_glyphs_with_options(&outlined_glyphs, &options)
+        .unwrap();
+    assert_eq!(batched.len(), chars.len());
+
+    for (c, glyph) in chars.into_iter().zip(batched) {
+        let single = triangulator.triangulate_glyph_with_options(outline(c), &options).unwrap();
+
+        assert_eq!(glyph.glyph(), single.glyph());
+        assert_eq!(glyph.placement(), single.placement());
+        assert_eq!(glyph.is_empty(), c == ' ');
+        assert!(glyph.validate().is_valid(), "{c}: {}", glyph.validate());
+
+        // The triangles cover the same area as the ones of the glyph triangulated alone.
+        let (area, single_area) =
+            (mesh_area(&glyph.to_mesh_in_font_units()), mesh_area(&single.to_mesh_in_font_units()));
+        assert!(
+            (area - single_area).abs() <= single_area.abs() * 1e-3,
+            "{c}: {area} and {single_area}"
+        );
+    }
+}